@@ -20,6 +20,8 @@ pub enum Error {
     },
     #[error("Parse error: {0:?}")]
     Parse(Option<(PathBuf, usize)>),
+    #[error("Parse error: SystemVerilog constructs are not allowed in IEEE 1364: {0:?}")]
+    NotAllowed(Vec<NotAllowed>),
     #[error("Preprocess error")]
     Preprocess,
     #[error("Define argument not found: {0}")]
@@ -30,6 +32,24 @@ pub enum Error {
     DefineNoArgs,
     #[error("Exceed recursive limit")]
     ExceedRecursiveLimit,
+    #[error("Exceed nesting limit: {0:?}")]
+    ExceedNestingLimit(Option<(PathBuf, usize)>),
+    #[error("Cancelled")]
+    Cancelled,
     #[error("Include line can't have other items")]
     IncludeLine,
+    #[error("Overlapping edits at {0}")]
+    OverlappingEdits(usize),
+    #[error("Edit of text not copied from a source file at {0}")]
+    EditOrigin(usize),
+    #[error("Query error: {message} at {offset}")]
+    Query { message: String, offset: usize },
+}
+
+/// A SystemVerilog construct in a region restricted to IEEE 1364
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotAllowed {
+    pub construct: String,
+    pub version: String,
+    pub origin: Option<(PathBuf, usize)>,
 }
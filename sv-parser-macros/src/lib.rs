@@ -5,7 +5,8 @@ extern crate proc_macro;
 use crate::proc_macro::TokenStream;
 use quote::quote;
 use syn::Data::{Enum, Struct};
-use syn::{self, DeriveInput};
+use syn::ext::IdentExt;
+use syn::{self, DeriveInput, FnArg, Ident, ItemFn, Visibility};
 
 #[proc_macro_derive(Node)]
 pub fn node_derive(input: TokenStream) -> TokenStream {
@@ -48,6 +49,45 @@ fn impl_node(ast: &DeriveInput) -> TokenStream {
         }
     };
 
+    let next_mut = match ast.data {
+        Struct(_) => {
+            quote! {
+                (&mut (self.nodes)).into()
+            }
+        }
+        _ => next.clone(),
+    };
+
+    // `Serialize` and `Deserialize` are implemented by a remote definition of the node,
+    // which has the same representation as deriving them on the node itself.
+    let name_str = name.to_string();
+    let mut remote = ast.clone();
+    remote.ident = Ident::new(&format!("__{}", name), name.span());
+    remote.attrs.clear();
+    let remote_name = &remote.ident;
+
+    let serde = quote! {
+        #[cfg(feature = "serde")]
+        const _: () = {
+            #[allow(dead_code, non_camel_case_types, clippy::all)]
+            #[derive(serde::Serialize, serde::Deserialize)]
+            #[serde(remote = #name_str, rename = #name_str)]
+            #remote
+
+            impl serde::Serialize for #name {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    #remote_name::serialize(self, serializer)
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for #name {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    #remote_name::deserialize(deserializer)
+                }
+            }
+        };
+    };
+
     let gen = quote! {
         impl<'a> Node<'a> for #name {
             fn next(&'a self) -> RefNodes<'a> {
@@ -55,12 +95,24 @@ fn impl_node(ast: &DeriveInput) -> TokenStream {
             }
         }
 
+        impl<'a> NodeMut<'a> for #name {
+            fn next_mut(&'a mut self) -> RefNodesMut<'a> {
+                #next_mut
+            }
+        }
+
         impl<'a> From<&'a #name> for RefNodes<'a> {
             fn from(x: &'a #name) -> Self {
                 vec![RefNode::#name(x)].into()
             }
         }
 
+        impl<'a> From<&'a mut #name> for RefNodesMut<'a> {
+            fn from(x: &'a mut #name) -> Self {
+                vec![RefNodeMut::#name(x)].into()
+            }
+        }
+
         impl From<#name> for AnyNode {
             fn from(x: #name) -> Self {
                 AnyNode::#name(x)
@@ -73,6 +125,12 @@ fn impl_node(ast: &DeriveInput) -> TokenStream {
             }
         }
 
+        impl<'a> From<&'a mut #name> for RefNodeMut<'a> {
+            fn from(x: &'a mut #name) -> Self {
+                RefNodeMut::#name(x)
+            }
+        }
+
         impl core::convert::TryFrom<#name> for Locate {
             type Error = ();
             fn try_from(x: #name) -> Result<Self, Self::Error> {
@@ -109,6 +167,8 @@ fn impl_node(ast: &DeriveInput) -> TokenStream {
                 Iter { next: nodes }
             }
         }
+
+        #serde
     };
     gen.into()
 }
@@ -127,6 +187,7 @@ fn impl_any_node(ast: &DeriveInput) -> TokenStream {
 
     let mut try_froms = quote! {};
     let mut from_items = quote! {};
+    let mut from_mut_items = quote! {};
     for v in &data.variants {
         let ident = &v.ident;
 
@@ -147,6 +208,11 @@ fn impl_any_node(ast: &DeriveInput) -> TokenStream {
             #from_items
             AnyNode::#ident(x) => RefNode::#ident(&x),
         };
+
+        from_mut_items = quote! {
+            #from_mut_items
+            AnyNode::#ident(x) => RefNodeMut::#ident(x),
+        };
     }
 
     let gen = quote! {
@@ -159,6 +225,14 @@ fn impl_any_node(ast: &DeriveInput) -> TokenStream {
                 }
             }
         }
+
+        impl<'a> From<&'a mut AnyNode> for RefNodeMut<'a>  {
+            fn from(x: &'a mut AnyNode) -> Self {
+                match x {
+                    #from_mut_items
+                }
+            }
+        }
     };
     gen.into()
 }
@@ -177,6 +251,7 @@ fn impl_ref_node(ast: &DeriveInput) -> TokenStream {
 
     let mut next_items = quote! {};
     let mut into_iter_items = quote! {};
+    let mut ptr_items = quote! {};
     for v in &data.variants {
         let ident = &v.ident;
         next_items = quote! {
@@ -187,6 +262,10 @@ fn impl_ref_node(ast: &DeriveInput) -> TokenStream {
             #into_iter_items
             RefNode::#ident(x) => x.into_iter(),
         };
+        ptr_items = quote! {
+            #ptr_items
+            RefNode::#ident(x) => *x as *const _ as *const (),
+        };
     }
 
     let name = &ast.ident;
@@ -197,6 +276,12 @@ fn impl_ref_node(ast: &DeriveInput) -> TokenStream {
                     #next_items
                 }
             }
+
+            pub(crate) fn ptr(&self) -> *const () {
+                match self {
+                    #ptr_items
+                }
+            }
         }
 
         impl<'a> IntoIterator for #name<'a> {
@@ -212,3 +297,73 @@ fn impl_ref_node(ast: &DeriveInput) -> TokenStream {
     };
     gen.into()
 }
+
+#[proc_macro_derive(RefNodeMut)]
+pub fn ref_node_mut_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+    impl_ref_node_mut(&ast)
+}
+
+fn impl_ref_node_mut(ast: &DeriveInput) -> TokenStream {
+    let data = &match ast.data {
+        Enum(ref data) => data,
+        _ => unreachable!(),
+    };
+
+    let mut next_items = quote! {};
+    for v in &data.variants {
+        let ident = &v.ident;
+        next_items = quote! {
+            #next_items
+            RefNodeMut::#ident(x) => x.next_mut(),
+        };
+    }
+
+    let name = &ast.ident;
+    let gen = quote! {
+        impl<'a> #name<'a> {
+            /// Returns the child nodes.
+            pub fn next_mut(self) -> RefNodesMut<'a> {
+                match self {
+                    #next_items
+                }
+            }
+        }
+    };
+    gen.into()
+}
+
+#[proc_macro_attribute]
+pub fn packrat_parser(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item: ItemFn = syn::parse(item).unwrap();
+    impl_packrat_parser(&item)
+}
+
+fn impl_packrat_parser(item: &ItemFn) -> TokenStream {
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let sig = &item.sig;
+    // The name of statistics, without `r#` of raw identifiers such as `r#const`
+    let name = sig.ident.unraw().to_string();
+
+    let arg = match sig.inputs.first() {
+        Some(FnArg::Typed(x)) => &x.pat,
+        _ => panic!("packrat_parser requires a parser function"),
+    };
+
+    let mut body = item.clone();
+    body.attrs = vec![];
+    body.vis = Visibility::Inherited;
+    // A fixed name doesn't shadow parsers such as `method_call_body` called by `method_call`
+    body.sig.ident = syn::Ident::new("packrat_body", sig.ident.span());
+    let body_ident = &body.sig.ident;
+
+    let gen = quote! {
+        #(#attrs)*
+        #vis #sig {
+            #body
+            crate::packrat(#name, #arg, #body_ident)
+        }
+    };
+    gen.into()
+}
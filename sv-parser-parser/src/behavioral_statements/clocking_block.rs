@@ -18,7 +18,7 @@ pub(crate) fn clocking_declaration_local(s: Span) -> IResult<Span, ClockingDecla
     let (s, e) = symbol(";")(s)?;
     let (s, f) = many0(clocking_item)(s)?;
     let (s, g) = keyword("endclocking")(s)?;
-    let (s, h) = opt(pair(symbol(":"), clocking_identifier))(s)?;
+    let (s, h) = end_label(clocking_identifier)(s)?;
     Ok((
         s,
         ClockingDeclaration::Local(Box::new(ClockingDeclarationLocal {
@@ -43,7 +43,7 @@ pub(crate) fn clocking_declaration_global(s: Span) -> IResult<Span, ClockingDecl
     let (s, d) = clocking_event(s)?;
     let (s, e) = symbol(";")(s)?;
     let (s, f) = keyword("endclocking")(s)?;
-    let (s, g) = opt(pair(symbol(":"), clocking_identifier))(s)?;
+    let (s, g) = end_label(clocking_identifier)(s)?;
     Ok((
         s,
         ClockingDeclaration::Global(Box::new(ClockingDeclarationGlobal {
@@ -33,7 +33,7 @@ pub(crate) fn seq_block(s: Span) -> IResult<Span, SeqBlock> {
     let (s, c) = many0(block_item_declaration)(s)?;
     let (s, d) = many0(statement_or_null)(s)?;
     let (s, e) = keyword("end")(s)?;
-    let (s, f) = opt(pair(symbol(":"), block_identifier))(s)?;
+    let (s, f) = end_label(block_identifier)(s)?;
     Ok((
         s,
         SeqBlock {
@@ -50,7 +50,7 @@ pub(crate) fn par_block(s: Span) -> IResult<Span, ParBlock> {
     let (s, c) = many0(block_item_declaration)(s)?;
     let (s, d) = many0(statement_or_null)(s)?;
     let (s, e) = join_keyword(s)?;
-    let (s, f) = opt(pair(symbol(":"), block_identifier))(s)?;
+    let (s, f) = end_label(block_identifier)(s)?;
     Ok((
         s,
         ParBlock {
@@ -38,6 +38,7 @@ pub(crate) fn statement(s: Span) -> IResult<Span, Statement> {
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn statement_item(s: Span) -> IResult<Span, StatementItem> {
+    let _nest = nest(&s)?;
     alt((
         map(pair(blocking_assignment, symbol(";")), |x| {
             StatementItem::BlockingAssignment(Box::new(x))
@@ -93,6 +94,9 @@ pub(crate) fn statement_item(s: Span) -> IResult<Span, StatementItem> {
         map(expect_property_statement, |x| {
             StatementItem::ExpectPropertyStatement(Box::new(x))
         }),
+        map(pair(kept_text_macro_usage, opt(symbol(";"))), |x| {
+            StatementItem::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -235,7 +235,7 @@ pub(crate) fn property_declaration(s: Span) -> IResult<Span, PropertyDeclaration
     let (s, f) = property_spec(s)?;
     let (s, g) = opt(symbol(";"))(s)?;
     let (s, h) = keyword("endproperty")(s)?;
-    let (s, i) = opt(pair(symbol(":"), property_identifier))(s)?;
+    let (s, i) = end_label(property_identifier)(s)?;
     Ok((
         s,
         PropertyDeclaration {
@@ -625,7 +625,7 @@ pub(crate) fn sequence_declaration(s: Span) -> IResult<Span, SequenceDeclaration
     let (s, f) = sequence_expr(s)?;
     let (s, g) = opt(symbol(";"))(s)?;
     let (s, h) = keyword("endsequence")(s)?;
-    let (s, i) = opt(pair(symbol(":"), sequence_identifier))(s)?;
+    let (s, i) = end_label(sequence_identifier)(s)?;
     Ok((
         s,
         SequenceDeclaration {
@@ -12,7 +12,7 @@ pub(crate) fn covergroup_declaration(s: Span) -> IResult<Span, CovergroupDeclara
     let (s, e) = symbol(";")(s)?;
     let (s, f) = many0(coverage_spec_or_option)(s)?;
     let (s, g) = keyword("endgroup")(s)?;
-    let (s, h) = opt(pair(symbol(":"), covergroup_identifier))(s)?;
+    let (s, h) = end_label(covergroup_identifier)(s)?;
     Ok((
         s,
         CovergroupDeclaration {
@@ -33,9 +33,15 @@ pub(crate) fn function_data_type_or_implicit(s: Span) -> IResult<Span, FunctionD
 #[packrat_parser]
 pub(crate) fn function_declaration(s: Span) -> IResult<Span, FunctionDeclaration> {
     let (s, a) = keyword("function")(s)?;
-    let (s, b) = opt(lifetime)(s)?;
-    let (s, c) = function_body_declaration(s)?;
-    Ok((s, FunctionDeclaration { nodes: (a, b, c) }))
+    let (s, b) = opt(dynamic_override_specifiers)(s)?;
+    let (s, c) = opt(lifetime)(s)?;
+    let (s, d) = function_body_declaration(s)?;
+    Ok((
+        s,
+        FunctionDeclaration {
+            nodes: (a, b, c, d),
+        },
+    ))
 }
 
 #[tracable_parser]
@@ -59,7 +65,7 @@ pub(crate) fn function_body_declaration_without_port(
     let (s, e) = many0(tf_item_declaration)(s)?;
     let (s, f) = many0(function_statement_or_null)(s)?;
     let (s, g) = keyword("endfunction")(s)?;
-    let (s, h) = opt(pair(symbol(":"), function_identifier))(s)?;
+    let (s, h) = end_label(function_identifier)(s)?;
     Ok((
         s,
         FunctionBodyDeclaration::WithoutPort(Box::new(FunctionBodyDeclarationWithoutPort {
@@ -81,7 +87,7 @@ pub(crate) fn function_body_declaration_with_port(
     let (s, f) = many0(block_item_declaration)(s)?;
     let (s, g) = many0(function_statement_or_null)(s)?;
     let (s, h) = keyword("endfunction")(s)?;
-    let (s, i) = opt(pair(symbol(":"), function_identifier))(s)?;
+    let (s, i) = end_label(function_identifier)(s)?;
     Ok((
         s,
         FunctionBodyDeclaration::WithPort(Box::new(FunctionBodyDeclarationWithPort {
@@ -109,13 +115,14 @@ pub(crate) fn interface_identifier_or_class_scope(
 #[packrat_parser]
 pub(crate) fn function_prototype(s: Span) -> IResult<Span, FunctionPrototype> {
     let (s, a) = keyword("function")(s)?;
-    let (s, b) = data_type_or_void(s)?;
-    let (s, c) = function_identifier(s)?;
-    let (s, d) = opt(paren(opt(tf_port_list)))(s)?;
+    let (s, b) = opt(dynamic_override_specifiers)(s)?;
+    let (s, c) = data_type_or_void(s)?;
+    let (s, d) = function_identifier(s)?;
+    let (s, e) = opt(paren(opt(tf_port_list)))(s)?;
     Ok((
         s,
         FunctionPrototype {
-            nodes: (a, b, c, d),
+            nodes: (a, b, c, d, e),
         },
     ))
 }
@@ -50,12 +50,13 @@ pub(crate) fn data_type(s: Span) -> IResult<Span, DataType> {
         map(keyword("string"), |x| DataType::String(Box::new(x))),
         map(keyword("chandle"), |x| DataType::Chandle(Box::new(x))),
         data_type_virtual,
-        map(terminated(class_type, peek(not(packed_dimension))), |x| {
-            DataType::ClassType(Box::new(x))
-        }),
-        data_type_type,
+        map(
+            sv_only(terminated(class_type, peek(not(packed_dimension)))),
+            |x| DataType::ClassType(Box::new(x)),
+        ),
+        sv_only(data_type_type),
         map(keyword("event"), |x| DataType::Chandle(Box::new(x))),
-        map(ps_covergroup_identifier, |x| {
+        map(sv_only(ps_covergroup_identifier), |x| {
             DataType::PsCovergroupIdentifier(Box::new(x))
         }),
         map(type_reference, |x| DataType::TypeReference(Box::new(x))),
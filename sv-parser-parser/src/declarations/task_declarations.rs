@@ -6,9 +6,15 @@ use crate::*;
 #[packrat_parser]
 pub(crate) fn task_declaration(s: Span) -> IResult<Span, TaskDeclaration> {
     let (s, a) = keyword("task")(s)?;
-    let (s, b) = opt(lifetime)(s)?;
-    let (s, c) = task_body_declaration(s)?;
-    Ok((s, TaskDeclaration { nodes: (a, b, c) }))
+    let (s, b) = opt(dynamic_override_specifiers)(s)?;
+    let (s, c) = opt(lifetime)(s)?;
+    let (s, d) = task_body_declaration(s)?;
+    Ok((
+        s,
+        TaskDeclaration {
+            nodes: (a, b, c, d),
+        },
+    ))
 }
 
 #[tracable_parser]
@@ -29,7 +35,7 @@ pub(crate) fn task_body_declaration_without_port(s: Span) -> IResult<Span, TaskB
     let (s, d) = many0(tf_item_declaration)(s)?;
     let (s, e) = many0(statement_or_null)(s)?;
     let (s, f) = keyword("endtask")(s)?;
-    let (s, g) = opt(pair(symbol(":"), task_identifier))(s)?;
+    let (s, g) = end_label(task_identifier)(s)?;
     Ok((
         s,
         TaskBodyDeclaration::WithoutPort(Box::new(TaskBodyDeclarationWithoutPort {
@@ -48,7 +54,7 @@ pub(crate) fn task_body_declaration_with_port(s: Span) -> IResult<Span, TaskBody
     let (s, e) = many0(block_item_declaration)(s)?;
     let (s, f) = many0(statement_or_null)(s)?;
     let (s, g) = keyword("endtask")(s)?;
-    let (s, h) = opt(pair(symbol(":"), task_identifier))(s)?;
+    let (s, h) = end_label(task_identifier)(s)?;
     Ok((
         s,
         TaskBodyDeclaration::WithPort(Box::new(TaskBodyDeclarationWithPort {
@@ -182,7 +188,13 @@ pub(crate) fn data_type_or_implicit_tf_port_declaration(
 #[packrat_parser]
 pub(crate) fn task_prototype(s: Span) -> IResult<Span, TaskPrototype> {
     let (s, a) = keyword("task")(s)?;
-    let (s, b) = task_identifier(s)?;
-    let (s, c) = opt(paren(opt(tf_port_list)))(s)?;
-    Ok((s, TaskPrototype { nodes: (a, b, c) }))
+    let (s, b) = opt(dynamic_override_specifiers)(s)?;
+    let (s, c) = task_identifier(s)?;
+    let (s, d) = opt(paren(opt(tf_port_list)))(s)?;
+    Ok((
+        s,
+        TaskPrototype {
+            nodes: (a, b, c, d),
+        },
+    ))
 }
@@ -150,7 +150,7 @@ pub(crate) fn net_declaration(s: Span) -> IResult<Span, NetDeclaration> {
     alt((
         net_declaration_interconnect,
         net_declaration_net_type,
-        net_declaration_net_type_identifier,
+        sv_only(net_declaration_net_type_identifier),
     ))(s)
 }
 
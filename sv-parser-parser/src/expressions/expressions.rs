@@ -37,12 +37,47 @@ pub(crate) fn inc_or_dec_expression_suffix(s: Span) -> IResult<Span, IncOrDecExp
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn conditional_expression(s: Span) -> IResult<Span, ConditionalExpression> {
-    let (s, a) = cond_predicate_ternary(s)?;
-    let (s, b) = symbol("?")(s)?;
-    let (s, c) = many0(attribute_instance)(s)?;
-    let (s, d) = expression(s)?;
-    let (s, e) = symbol(":")(s)?;
-    let (s, f) = expression(s)?;
+    // Conditional expressions in else branches are parsed by a loop instead of recursion,
+    // so a long chain of them doesn't consume the stack.
+    let (mut s, (a, b, c, d, e)) = conditional_expression_head(s)?;
+    let mut heads = Vec::new();
+    let mut f = loop {
+        // `expression` tries these alternatives before `conditional_expression`.
+        // The chain can't be an operand of `expression_binary`, because the else branch
+        // takes the following binary operators.
+        let t = left_recursion(s, "conditional_expression");
+        match alt((
+            map(terminated(primary, peek(one_of(",();"))), |x| {
+                Expression::Primary(Box::new(x))
+            }),
+            expression_binary,
+        ))(t)
+        {
+            Ok((t, x)) => {
+                s = t;
+                break x;
+            }
+            Err(Err::Error(_)) => (),
+            Err(x) => return Err(x),
+        }
+        match conditional_expression_head(t) {
+            Ok((t, x)) => {
+                s = t;
+                heads.push(x);
+            }
+            Err(Err::Error(_)) => {
+                let (t, x) = expression(s)?;
+                s = t;
+                break x;
+            }
+            Err(x) => return Err(x),
+        }
+    };
+    for (a, b, c, d, e) in heads.into_iter().rev() {
+        f = Expression::ConditionalExpression(Box::new(ConditionalExpression {
+            nodes: (a, b, c, d, e, f),
+        }));
+    }
     Ok((
         s,
         ConditionalExpression {
@@ -51,6 +86,27 @@ pub(crate) fn conditional_expression(s: Span) -> IResult<Span, ConditionalExpres
     ))
 }
 
+#[tracable_parser]
+pub(crate) fn conditional_expression_head(
+    s: Span,
+) -> IResult<
+    Span,
+    (
+        CondPredicate,
+        Symbol,
+        Vec<AttributeInstance>,
+        Expression,
+        Symbol,
+    ),
+> {
+    let (s, a) = cond_predicate_ternary(s)?;
+    let (s, b) = symbol("?")(s)?;
+    let (s, c) = many0(attribute_instance)(s)?;
+    let (s, d) = expression(s)?;
+    let (s, e) = symbol(":")(s)?;
+    Ok((s, (a, b, c, d, e)))
+}
+
 #[recursive_parser]
 #[tracable_parser]
 #[packrat_parser]
@@ -103,10 +159,33 @@ pub(crate) fn constant_expression_unary(s: Span) -> IResult<Span, ConstantExpres
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn constant_expression_binary(s: Span) -> IResult<Span, ConstantExpression> {
+    // The operands are parsed by a loop as `expression_binary`
     let (s, a) = constant_expression(s)?;
     let (s, b) = binary_operator(s)?;
     let (s, c) = many0(attribute_instance)(s)?;
-    let (s, d) = constant_expression(s)?;
+    let (mut s, mut d) = constant_expression(left_recursion(s, "constant_expression_binary"))?;
+    let mut operands = Vec::new();
+    loop {
+        let ret = binary_operator(s).and_then(|(t, x)| {
+            let (t, y) = many0(attribute_instance)(t)?;
+            let (t, z) = constant_expression(left_recursion(t, "constant_expression_binary"))?;
+            Ok((t, (x, y, z)))
+        });
+        match ret {
+            Ok((t, (x, y, z))) => {
+                s = t;
+                operands.push((d, x, y));
+                d = z;
+            }
+            Err(Err::Error(_)) => break,
+            Err(x) => return Err(x),
+        }
+    }
+    for (x, y, z) in operands.into_iter().rev() {
+        d = ConstantExpression::Binary(Box::new(ConstantExpressionBinary {
+            nodes: (x, y, z, d),
+        }));
+    }
     Ok((
         s,
         ConstantExpression::Binary(Box::new(ConstantExpressionBinary {
@@ -119,12 +198,38 @@ pub(crate) fn constant_expression_binary(s: Span) -> IResult<Span, ConstantExpre
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn constant_expression_ternary(s: Span) -> IResult<Span, ConstantExpression> {
-    let (s, a) = constant_expression(s)?;
-    let (s, b) = symbol("?")(s)?;
-    let (s, c) = many0(attribute_instance)(s)?;
-    let (s, d) = constant_expression(s)?;
-    let (s, e) = symbol(":")(s)?;
-    let (s, f) = constant_expression(s)?;
+    // Ternaries in else branches are parsed by a loop as `conditional_expression`
+    let (mut s, (a, b, c, d, e)) = constant_expression_ternary_head(s)?;
+    let mut heads = Vec::new();
+    let mut f = loop {
+        // `constant_expression` tries it before `constant_expression_ternary`
+        let t = left_recursion(s, "constant_expression_ternary");
+        match constant_expression_binary(t) {
+            Ok((t, x)) => {
+                s = t;
+                break x;
+            }
+            Err(Err::Error(_)) => (),
+            Err(x) => return Err(x),
+        }
+        match constant_expression_ternary_head(t) {
+            Ok((t, x)) => {
+                s = t;
+                heads.push(x);
+            }
+            Err(Err::Error(_)) => {
+                let (t, x) = constant_expression(s)?;
+                s = t;
+                break x;
+            }
+            Err(x) => return Err(x),
+        }
+    };
+    for (a, b, c, d, e) in heads.into_iter().rev() {
+        f = ConstantExpression::Ternary(Box::new(ConstantExpressionTernary {
+            nodes: (a, b, c, d, e, f),
+        }));
+    }
     Ok((
         s,
         ConstantExpression::Ternary(Box::new(ConstantExpressionTernary {
@@ -133,6 +238,27 @@ pub(crate) fn constant_expression_ternary(s: Span) -> IResult<Span, ConstantExpr
     ))
 }
 
+#[tracable_parser]
+pub(crate) fn constant_expression_ternary_head(
+    s: Span,
+) -> IResult<
+    Span,
+    (
+        ConstantExpression,
+        Symbol,
+        Vec<AttributeInstance>,
+        ConstantExpression,
+        Symbol,
+    ),
+> {
+    let (s, a) = constant_expression(s)?;
+    let (s, b) = symbol("?")(s)?;
+    let (s, c) = many0(attribute_instance)(s)?;
+    let (s, d) = constant_expression(s)?;
+    let (s, e) = symbol(":")(s)?;
+    Ok((s, (a, b, c, d, e)))
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn constant_mintypmax_expression(s: Span) -> IResult<Span, ConstantMintypmaxExpression> {
@@ -289,10 +415,35 @@ pub(crate) fn expression_operator_assignment(s: Span) -> IResult<Span, Expressio
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn expression_binary(s: Span) -> IResult<Span, Expression> {
+    // The operands after the first one are parsed by a loop instead of recursion,
+    // so a long chain of binary operators doesn't consume the stack.
+    // The chain is nested to the right as the recursion did.
     let (s, a) = expression(s)?;
     let (s, b) = binary_operator(s)?;
     let (s, c) = many0(attribute_instance)(s)?;
-    let (s, d) = expression(s)?;
+    let (mut s, mut d) = expression(left_recursion(s, "expression_binary"))?;
+    let mut operands = Vec::new();
+    loop {
+        let ret = binary_operator(s).and_then(|(t, x)| {
+            let (t, y) = many0(attribute_instance)(t)?;
+            let (t, z) = expression(left_recursion(t, "expression_binary"))?;
+            Ok((t, (x, y, z)))
+        });
+        match ret {
+            Ok((t, (x, y, z))) => {
+                s = t;
+                operands.push((d, x, y));
+                d = z;
+            }
+            Err(Err::Error(_)) => break,
+            Err(x) => return Err(x),
+        }
+    }
+    for (x, y, z) in operands.into_iter().rev() {
+        d = Expression::Binary(Box::new(ExpressionBinary {
+            nodes: (x, y, z, d),
+        }));
+    }
     Ok((
         s,
         Expression::Binary(Box::new(ExpressionBinary {
@@ -12,7 +12,7 @@ pub(crate) fn constant_primary(s: Span) -> IResult<Span, ConstantPrimary> {
         map(constant_assignment_pattern_expression, |x| {
             ConstantPrimary::ConstantAssignmentPatternExpression(Box::new(x))
         }),
-        map(constant_cast, |x| {
+        map(sv_only(constant_cast), |x| {
             ConstantPrimary::ConstantCast(Box::new(x))
         }),
         map(primary_literal, |x| {
@@ -38,6 +38,9 @@ pub(crate) fn constant_primary(s: Span) -> IResult<Span, ConstantPrimary> {
         map(type_reference, |x| {
             ConstantPrimary::TypeReference(Box::new(x))
         }),
+        map(kept_text_macro_usage, |x| {
+            ConstantPrimary::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -202,7 +205,7 @@ pub(crate) fn primary(s: Span) -> IResult<Span, Primary> {
         map(assignment_pattern_expression, |x| {
             Primary::AssignmentPatternExpression(Box::new(x))
         }),
-        map(cast, |x| Primary::Cast(Box::new(x))),
+        map(sv_only(cast), |x| Primary::Cast(Box::new(x))),
         map(primary_literal, |x| Primary::PrimaryLiteral(Box::new(x))),
         map(empty_unpacked_array_concatenation, |x| {
             Primary::EmptyUnpackedArrayConcatenation(Box::new(x))
@@ -223,6 +226,9 @@ pub(crate) fn primary(s: Span) -> IResult<Span, Primary> {
         map(keyword("this"), |x| Primary::This(Box::new(x))),
         map(keyword("$"), |x| Primary::Dollar(Box::new(x))),
         map(keyword("null"), |x| Primary::Null(Box::new(x))),
+        map(kept_text_macro_usage, |x| {
+            Primary::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -309,9 +315,11 @@ pub(crate) fn range_expression(s: Span) -> IResult<Span, RangeExpression> {
 #[packrat_parser]
 pub(crate) fn primary_literal(s: Span) -> IResult<Span, PrimaryLiteral> {
     alt((
-        map(time_literal, |x| PrimaryLiteral::TimeLiteral(Box::new(x))),
+        map(sv_only(time_literal), |x| {
+            PrimaryLiteral::TimeLiteral(Box::new(x))
+        }),
         map(number, |x| PrimaryLiteral::Number(Box::new(x))),
-        map(unbased_unsized_literal, |x| {
+        map(sv_only(unbased_unsized_literal), |x| {
             PrimaryLiteral::UnbasedUnsizedLiteral(Box::new(x))
         }),
         map(string_literal, |x| {
@@ -262,7 +262,7 @@ pub(crate) fn primary_method_call_root(s: Span) -> IResult<Span, Primary> {
             Primary::AssignmentPatternExpression(Box::new(x))
         }),
         map(primary_literal, |x| Primary::PrimaryLiteral(Box::new(x))),
-        map(cast, |x| Primary::Cast(Box::new(x))),
+        map(sv_only(cast), |x| Primary::Cast(Box::new(x))),
         terminated(primary_hierarchical_method_call_root, peek(none_of("("))),
         map(keyword("this"), |x| Primary::This(Box::new(x))),
         map(empty_unpacked_array_concatenation, |x| {
@@ -337,6 +337,7 @@ pub(crate) fn array_method_name(s: Span) -> IResult<Span, ArrayMethodName> {
         map(keyword("and"), |x| ArrayMethodName::And(Box::new(x))),
         map(keyword("or"), |x| ArrayMethodName::Or(Box::new(x))),
         map(keyword("xor"), |x| ArrayMethodName::Xor(Box::new(x))),
+        map(keyword("map"), |x| ArrayMethodName::Map(Box::new(x))),
         map(method_identifier, |x| {
             ArrayMethodName::MethodIdentifier(Box::new(x))
         }),
@@ -5,7 +5,7 @@ use crate::*;
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn compiler_directive(s: Span) -> IResult<Span, CompilerDirective> {
-    begin_directive();
+    begin_directive(&s);
     let ret = alt((
         map(resetall_compiler_directive, |x| {
             CompilerDirective::ResetallCompilerDirective(Box::new(x))
@@ -60,14 +60,14 @@ pub(crate) fn compiler_directive(s: Span) -> IResult<Span, CompilerDirective> {
             CompilerDirective::TextMacroUsage(Box::new(x))
         }),
     ))(s);
-    end_directive();
+    end_directive(&s);
     ret
 }
 
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn compiler_directive_without_resetall(s: Span) -> IResult<Span, CompilerDirective> {
-    begin_directive();
+    begin_directive(&s);
     let ret = alt((
         map(include_compiler_directive, |x| {
             CompilerDirective::IncludeCompilerDirective(Box::new(x))
@@ -81,6 +81,9 @@ pub(crate) fn compiler_directive_without_resetall(s: Span) -> IResult<Span, Comp
         map(undefineall_compiler_directive, |x| {
             CompilerDirective::UndefineallCompilerDirective(Box::new(x))
         }),
+        map(kept_conditional_directive_line, |x| {
+            CompilerDirective::ConditionalDirectiveLine(Box::new(x))
+        }),
         map(conditional_compiler_directive, |x| {
             CompilerDirective::ConditionalCompilerDirective(Box::new(x))
         }),
@@ -119,7 +122,7 @@ pub(crate) fn compiler_directive_without_resetall(s: Span) -> IResult<Span, Comp
             CompilerDirective::TextMacroUsage(Box::new(x))
         }),
     ))(s);
-    end_directive();
+    end_directive(&s);
     ret
 }
 
@@ -213,9 +216,10 @@ pub(crate) fn angle_bracket_literal_impl(s: Span) -> IResult<Span, Locate> {
 pub(crate) fn text_macro_definition(s: Span) -> IResult<Span, TextMacroDefinition> {
     let (s, a) = symbol("`")(s)?;
     let (s, b) = keyword("define")(s)?;
-    begin_keywords("directive");
-    let (s, c) = text_macro_name(s)?;
-    end_keywords();
+    begin_keywords(&s, Version::Directive);
+    let ret = text_macro_name(s);
+    end_keywords(&s);
+    let (s, c) = ret?;
     let (s, d) = opt(macro_text)(s)?;
     Ok((
         s,
@@ -306,13 +310,42 @@ pub(crate) fn default_text(s: Span) -> IResult<Span, DefaultText> {
 #[packrat_parser]
 pub(crate) fn text_macro_usage(s: Span) -> IResult<Span, TextMacroUsage> {
     let (s, a) = symbol("`")(s)?;
-    begin_keywords("directive");
-    let (s, b) = text_macro_identifier(s)?;
-    end_keywords();
+    begin_keywords(&s, Version::Directive);
+    let ret = text_macro_identifier(s);
+    end_keywords(&s);
+    let (s, b) = ret?;
     let (s, c) = opt(paren(list_of_actual_arguments))(s)?;
     Ok((s, TextMacroUsage { nodes: (a, b, c) }))
 }
 
+/// Text macro usage kept as a node by `SpanInfo::keep_macros`
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn kept_text_macro_usage(s: Span) -> IResult<Span, TextMacroUsage> {
+    if !s.extra.keep_macros {
+        return Err(Err::Error(make_error(s, ErrorKind::Fix)));
+    }
+    begin_directive(&s);
+    let ret = alt((text_macro_usage, position_macro_usage))(s);
+    end_directive(&s);
+    ret
+}
+
+/// `` `__FILE__ `` and `` `__LINE__ `` kept as text macro usages
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn position_macro_usage(s: Span) -> IResult<Span, TextMacroUsage> {
+    let (s, a) = position_compiler_directive(s)?;
+    let (a, b) = a.nodes;
+    let b = Identifier::SimpleIdentifier(Box::new(SimpleIdentifier { nodes: b.nodes }));
+    Ok((
+        s,
+        TextMacroUsage {
+            nodes: (a, TextMacroIdentifier { nodes: (b,) }, None),
+        },
+    ))
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn list_of_actual_arguments(s: Span) -> IResult<Span, ListOfActualArguments> {
@@ -460,12 +493,12 @@ pub(crate) fn conditional_compiler_directive(
 pub(crate) fn ifdef_directive(s: Span) -> IResult<Span, IfdefDirective> {
     let (s, a) = symbol("`")(s)?;
     let (s, b) = keyword("ifdef")(s)?;
-    let (s, c) = text_macro_identifier(s)?;
+    let (s, c) = ifdef_condition(s)?;
     let (s, d) = ifdef_group_of_lines(s)?;
     let (s, e) = many0(tuple((
         symbol("`"),
         keyword("elsif"),
-        text_macro_identifier,
+        ifdef_condition,
         elsif_group_of_lines,
     )))(s)?;
     let (s, f) = opt(tuple((symbol("`"), keyword("else"), else_group_of_lines)))(s)?;
@@ -484,12 +517,12 @@ pub(crate) fn ifdef_directive(s: Span) -> IResult<Span, IfdefDirective> {
 pub(crate) fn ifndef_directive(s: Span) -> IResult<Span, IfndefDirective> {
     let (s, a) = symbol("`")(s)?;
     let (s, b) = keyword("ifndef")(s)?;
-    let (s, c) = text_macro_identifier(s)?;
+    let (s, c) = ifdef_condition(s)?;
     let (s, d) = ifndef_group_of_lines(s)?;
     let (s, e) = many0(tuple((
         symbol("`"),
         keyword("elsif"),
-        text_macro_identifier,
+        ifdef_condition,
         elsif_group_of_lines,
     )))(s)?;
     let (s, f) = opt(tuple((symbol("`"), keyword("else"), else_group_of_lines)))(s)?;
@@ -503,6 +536,120 @@ pub(crate) fn ifndef_directive(s: Span) -> IResult<Span, IfndefDirective> {
     ))
 }
 
+/// Line of a conditional compilation directive kept as a node by `SpanInfo::keep_macros`
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn kept_conditional_directive_line(s: Span) -> IResult<Span, ConditionalDirectiveLine> {
+    if !s.extra.keep_macros {
+        return Err(Err::Error(make_error(s, ErrorKind::Fix)));
+    }
+    alt((
+        map(
+            tuple((
+                symbol("`"),
+                alt((keyword("ifdef"), keyword("ifndef"), keyword("elsif"))),
+                ifdef_condition,
+            )),
+            |(a, b, c)| {
+                ConditionalDirectiveLine::Condition(Box::new(ConditionalDirectiveLineCondition {
+                    nodes: (a, b, c),
+                }))
+            },
+        ),
+        map(
+            pair(symbol("`"), alt((keyword("else"), keyword("endif")))),
+            |(a, b)| {
+                ConditionalDirectiveLine::Keyword(Box::new(ConditionalDirectiveLineKeyword {
+                    nodes: (a, b),
+                }))
+            },
+        ),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_condition(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    alt((
+        map(text_macro_identifier, |x| {
+            IfdefMacroExpression::TextMacroIdentifier(Box::new(x))
+        }),
+        ifdef_macro_expression_paren,
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = ifdef_macro_expression_or(s)?;
+    let (s, b) = opt(pair(
+        alt((symbol("->"), symbol("<->"))),
+        ifdef_macro_expression,
+    ))(s)?;
+    let ret = match b {
+        Some((b, c)) => {
+            IfdefMacroExpression::Binary(Box::new(IfdefMacroExpressionBinary { nodes: (a, b, c) }))
+        }
+        None => a,
+    };
+    Ok((s, ret))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression_or(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = ifdef_macro_expression_and(s)?;
+    let (s, b) = many0(pair(symbol("||"), ifdef_macro_expression_and))(s)?;
+    let ret = b.into_iter().fold(a, |a, (b, c)| {
+        IfdefMacroExpression::Binary(Box::new(IfdefMacroExpressionBinary { nodes: (a, b, c) }))
+    });
+    Ok((s, ret))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression_and(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = ifdef_macro_expression_unary(s)?;
+    let (s, b) = many0(pair(symbol("&&"), ifdef_macro_expression_unary))(s)?;
+    let ret = b.into_iter().fold(a, |a, (b, c)| {
+        IfdefMacroExpression::Binary(Box::new(IfdefMacroExpressionBinary { nodes: (a, b, c) }))
+    });
+    Ok((s, ret))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression_unary(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    alt((
+        map(text_macro_identifier, |x| {
+            IfdefMacroExpression::TextMacroIdentifier(Box::new(x))
+        }),
+        ifdef_macro_expression_paren,
+        ifdef_macro_expression_not,
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression_paren(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = paren(ifdef_macro_expression)(s)?;
+    Ok((
+        s,
+        IfdefMacroExpression::Paren(Box::new(IfdefMacroExpressionParen { nodes: (a,) })),
+    ))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression_not(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = symbol("!")(s)?;
+    let (s, b) = ifdef_macro_expression_unary(s)?;
+    Ok((
+        s,
+        IfdefMacroExpression::Not(Box::new(IfdefMacroExpressionNot { nodes: (a, b) })),
+    ))
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn ifdef_group_of_lines(s: Span) -> IResult<Span, IfdefGroupOfLines> {
@@ -828,40 +975,20 @@ pub(crate) fn keywords_directive(s: Span) -> IResult<Span, KeywordsDirective> {
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn version_specifier(s: Span) -> IResult<Span, VersionSpecifier> {
-    let (s, a) = alt((
-        map(keyword("1800-2017"), |x| {
-            begin_keywords("1800-2017");
-            x
-        }),
-        map(keyword("1800-2012"), |x| {
-            begin_keywords("1800-2012");
-            x
-        }),
-        map(keyword("1800-2009"), |x| {
-            begin_keywords("1800-2009");
-            x
-        }),
-        map(keyword("1800-2005"), |x| {
-            begin_keywords("1800-2005");
-            x
-        }),
-        map(keyword("1364-2005"), |x| {
-            begin_keywords("1364-2005");
-            x
-        }),
-        map(keyword("1364-2001"), |x| {
-            begin_keywords("1364-2001");
-            x
-        }),
+    let (s, (a, b)) = alt((
+        map(keyword("1800-2023"), |x| (x, Version::Ieee1800_2023)),
+        map(keyword("1800-2017"), |x| (x, Version::Ieee1800_2017)),
+        map(keyword("1800-2012"), |x| (x, Version::Ieee1800_2012)),
+        map(keyword("1800-2009"), |x| (x, Version::Ieee1800_2009)),
+        map(keyword("1800-2005"), |x| (x, Version::Ieee1800_2005)),
+        map(keyword("1364-2005"), |x| (x, Version::Ieee1364_2005)),
+        map(keyword("1364-2001"), |x| (x, Version::Ieee1364_2001)),
         map(keyword("1364-2001-noconfig"), |x| {
-            begin_keywords("1364-2001-noconfig");
-            x
-        }),
-        map(keyword("1364-1995"), |x| {
-            begin_keywords("1364-1995");
-            x
+            (x, Version::Ieee1364_2001Noconfig)
         }),
+        map(keyword("1364-1995"), |x| (x, Version::Ieee1364_1995)),
     ))(s)?;
+    begin_keywords(&s, b);
     Ok((s, VersionSpecifier { nodes: (a,) }))
 }
 
@@ -870,6 +997,6 @@ pub(crate) fn version_specifier(s: Span) -> IResult<Span, VersionSpecifier> {
 pub(crate) fn endkeywords_directive(s: Span) -> IResult<Span, EndkeywordsDirective> {
     let (s, a) = symbol("`")(s)?;
     let (s, b) = keyword("end_keywords")(s)?;
-    end_keywords();
+    end_keywords(&s);
     Ok((s, EndkeywordsDirective { nodes: (a, b) }))
 }
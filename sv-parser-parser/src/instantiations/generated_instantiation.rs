@@ -164,6 +164,7 @@ pub(crate) fn case_generate_item_default(s: Span) -> IResult<Span, CaseGenerateI
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn generate_block(s: Span) -> IResult<Span, GenerateBlock> {
+    let _nest = nest(&s)?;
     alt((
         map(generate_item, |x| GenerateBlock::GenerateItem(Box::new(x))),
         generate_block_multiple,
@@ -178,7 +179,7 @@ pub(crate) fn generate_block_multiple(s: Span) -> IResult<Span, GenerateBlock> {
     let (s, c) = opt(pair(symbol(":"), generate_block_identifier))(s)?;
     let (s, d) = many0(generate_item)(s)?;
     let (s, e) = keyword("end")(s)?;
-    let (s, f) = opt(pair(symbol(":"), generate_block_identifier))(s)?;
+    let (s, f) = end_label(generate_block_identifier)(s)?;
     Ok((
         s,
         GenerateBlock::Multiple(Box::new(GenerateBlockMultiple {
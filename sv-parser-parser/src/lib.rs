@@ -1,10 +1,13 @@
 #![allow(clippy::many_single_char_names, clippy::module_inception)]
 
-pub mod keywords;
+pub mod lint;
+pub mod stream;
+pub mod tokenizer;
 #[macro_use]
 pub mod utils;
-pub(crate) use keywords::*;
+pub(crate) use sv_parser_syntaxtree::keywords::*;
 pub(crate) use utils::*;
+pub use utils::{PackratConfig, PackratEviction, PackratStats, ProductionStats, Version};
 
 mod tests;
 
@@ -38,26 +41,167 @@ pub(crate) use nom::multi::*;
 pub(crate) use nom::sequence::*;
 pub(crate) use nom::Err;
 pub(crate) use nom_greedyerror::GreedyError;
-pub(crate) use nom_packrat::{self, packrat_parser, HasExtraState};
 pub(crate) use nom_recursive::{recursive_parser, HasRecursiveInfo, RecursiveInfo};
 pub(crate) use nom_tracable::tracable_parser;
 #[cfg(feature = "trace")]
 pub(crate) use nom_tracable::{HasTracableInfo, TracableInfo};
+pub(crate) use sv_parser_macros::packrat_parser;
 pub(crate) use sv_parser_syntaxtree::*;
 
+use core::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 // -----------------------------------------------------------------------------
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct SpanInfo {
+/// Default of `SpanInfo::max_depth`.
+///
+/// The stack of a parse reaching it is grown on the heap to tens of megabytes in debug builds.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SpanInfo<'a> {
     #[cfg(feature = "trace")]
     pub tracable_info: TracableInfo,
     pub recursive_info: RecursiveInfo,
+    /// Keyword version used when no `` `begin_keywords `` directive is active.
+    /// `None` means IEEE 1800-2017.
+    pub default_version: Option<Version>,
+    /// Keep text macro usages in expression, statement and item positions as nodes
+    /// instead of white spaces. It is used to parse the source text without preprocessing.
+    pub keep_macros: bool,
+    /// Maximum nesting depth of brackets, statements and items. `None` means unlimited.
+    pub max_depth: Option<usize>,
+    /// Restrict the grammar to IEEE 1364 in regions where the version in effect is IEEE 1364.
+    /// SystemVerilog-only keywords, operators and productions don't match there.
+    pub strict_version: bool,
+    pub control: ParseControl<'a>,
+    /// Mutable state of the parse. Each parse must use its own `ParserState`.
+    pub state: &'a ParserState,
+}
+
+impl<'a> SpanInfo<'a> {
+    pub fn new(state: &'a ParserState) -> Self {
+        SpanInfo {
+            #[cfg(feature = "trace")]
+            tracable_info: TracableInfo::new(),
+            recursive_info: RecursiveInfo::new(),
+            default_version: None,
+            keep_macros: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            strict_version: false,
+            control: ParseControl::default(),
+            state,
+        }
+    }
+}
+
+/// State of a parse shared by all spans of the parsed text.
+///
+/// It holds the `` `begin_keywords `` version stack and the packrat cache
+/// instead of thread-local storage, so parses on different threads or nested parses don't interfere.
+#[derive(Debug, Default)]
+pub struct ParserState {
+    pub(crate) in_directive: Cell<usize>,
+    pub(crate) versions: RefCell<Vec<Version>>,
+    pub(crate) packrat: RefCell<PackratStorage>,
+    pub(crate) depth: Cell<usize>,
+    pub(crate) exceeded_depth: Cell<Option<usize>>,
+    pub(crate) progress: Cell<usize>,
+    pub(crate) cancelled: Cell<bool>,
+}
+
+impl ParserState {
+    pub fn new() -> Self {
+        Self::with_packrat(PackratConfig::default())
+    }
+
+    pub fn with_packrat(config: PackratConfig) -> Self {
+        ParserState {
+            in_directive: Cell::new(0),
+            versions: RefCell::new(Vec::new()),
+            packrat: RefCell::new(PackratStorage::new(config)),
+            depth: Cell::new(0),
+            exceeded_depth: Cell::new(None),
+            progress: Cell::new(0),
+            cancelled: Cell::new(false),
+        }
+    }
+
+    /// Returns the statistics of the packrat memoization accumulated by the parses with this state.
+    /// They are collected if `PackratConfig::stats` is set, and aren't reset by `clear`.
+    pub fn packrat_stats(&self) -> PackratStats {
+        self.packrat.borrow().stats.clone()
+    }
+
+    /// Clears the state to start a new parse.
+    pub fn clear(&self) {
+        self.in_directive.set(0);
+        self.versions.borrow_mut().clear();
+        self.packrat.borrow_mut().clear();
+        self.depth.set(0);
+        self.exceeded_depth.set(None);
+        self.progress.set(0);
+        self.cancelled.set(false);
+    }
+
+    /// Returns the offset where the nesting exceeded `SpanInfo::max_depth` in the last parse.
+    pub fn exceeded_depth(&self) -> Option<usize> {
+        self.exceeded_depth.get()
+    }
+
+    /// Whether the last parse was aborted by `ParseControl::cancel`.
+    pub fn cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
+/// Token to cancel parses from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken(Arc::new(AtomicBool::new(false)))
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Cancellation and progress reporting of a parse.
+#[derive(Clone, Copy, Default)]
+pub struct ParseControl<'a> {
+    /// The parse fails as soon as the token is cancelled.
+    pub cancel: Option<&'a CancellationToken>,
+    /// Called with the number of consumed bytes of the parsed text as the parse proceeds.
+    pub progress: Option<&'a dyn Fn(usize)>,
+}
+
+impl ParseControl<'_> {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|x| x.is_cancelled())
+    }
 }
 
-pub type Span<'a> = nom_locate::LocatedSpan<&'a str, SpanInfo>;
+impl core::fmt::Debug for ParseControl<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ParseControl")
+            .field("cancel", &self.cancel)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+pub type Span<'a> = nom_locate::LocatedSpan<&'a str, SpanInfo<'a>>;
 pub type IResult<T, U> = nom::IResult<T, U, GreedyError<T>>;
 
-impl HasRecursiveInfo for SpanInfo {
+impl HasRecursiveInfo for SpanInfo<'_> {
     fn get_recursive_info(&self) -> RecursiveInfo {
         self.recursive_info
     }
@@ -69,7 +213,7 @@ impl HasRecursiveInfo for SpanInfo {
 }
 
 #[cfg(feature = "trace")]
-impl HasTracableInfo for SpanInfo {
+impl HasTracableInfo for SpanInfo<'_> {
     fn get_tracable_info(&self) -> TracableInfo {
         self.tracable_info
     }
@@ -80,27 +224,92 @@ impl HasTracableInfo for SpanInfo {
     }
 }
 
-impl HasExtraState<bool> for SpanInfo {
-    fn get_extra_state(&self) -> bool {
-        in_directive()
-    }
-}
-
 // -----------------------------------------------------------------------------
 
-nom_packrat::storage!(AnyNode, bool, 1024);
-
 pub fn sv_parser(s: Span) -> IResult<Span, SourceText> {
-    nom_packrat::init!();
+    s.extra.state.clear();
     source_text(s)
 }
 
 pub fn lib_parser(s: Span) -> IResult<Span, LibraryText> {
-    nom_packrat::init!();
+    s.extra.state.clear();
     library_text(s)
 }
 
 pub fn pp_parser(s: Span) -> IResult<Span, PreprocessorText> {
-    nom_packrat::init!();
+    s.extra.state.clear();
     preprocessor_text(s)
 }
+
+/// Productions which can be parsed individually.
+pub trait Parse: Sized {
+    /// Parses the production after leading white spaces, which are discarded.
+    fn parse(s: Span) -> IResult<Span, Self>;
+}
+
+macro_rules! productions {
+    ($($production:ident -> $ty:ty,)*) => {
+        $(
+            impl Parse for $ty {
+                fn parse(s: Span) -> IResult<Span, Self> {
+                    s.extra.state.clear();
+                    preceded(many0(white_space), $production)(s)
+                }
+            }
+        )*
+    };
+}
+
+productions! {
+    description -> Description,
+    module_declaration -> ModuleDeclaration,
+    interface_declaration -> InterfaceDeclaration,
+    program_declaration -> ProgramDeclaration,
+    checker_declaration -> CheckerDeclaration,
+    class_declaration -> ClassDeclaration,
+    package_declaration -> PackageDeclaration,
+    config_declaration -> ConfigDeclaration,
+    udp_declaration -> UdpDeclaration,
+    module_item -> ModuleItem,
+    non_port_module_item -> NonPortModuleItem,
+    module_or_generate_item -> ModuleOrGenerateItem,
+    interface_item -> InterfaceItem,
+    program_item -> ProgramItem,
+    package_item -> PackageItem,
+    class_item -> ClassItem,
+    port_declaration -> PortDeclaration,
+    ansi_port_declaration -> AnsiPortDeclaration,
+    parameter_declaration -> ParameterDeclaration,
+    local_parameter_declaration -> LocalParameterDeclaration,
+    data_declaration -> DataDeclaration,
+    data_type -> DataType,
+    function_declaration -> FunctionDeclaration,
+    task_declaration -> TaskDeclaration,
+    covergroup_declaration -> CovergroupDeclaration,
+    constraint_block -> ConstraintBlock,
+    module_instantiation -> ModuleInstantiation,
+    generate_region -> GenerateRegion,
+    continuous_assign -> ContinuousAssign,
+    initial_construct -> InitialConstruct,
+    always_construct -> AlwaysConstruct,
+    statement -> Statement,
+    statement_item -> StatementItem,
+    seq_block -> SeqBlock,
+    conditional_statement -> ConditionalStatement,
+    case_statement -> CaseStatement,
+    loop_statement -> LoopStatement,
+    assertion_item -> AssertionItem,
+    concurrent_assertion_item -> ConcurrentAssertionItem,
+    property_expr -> PropertyExpr,
+    sequence_expr -> SequenceExpr,
+    expression -> Expression,
+    constant_expression -> ConstantExpression,
+    mintypmax_expression -> MintypmaxExpression,
+    primary -> Primary,
+    constant_primary -> ConstantPrimary,
+    variable_lvalue -> VariableLvalue,
+    net_lvalue -> NetLvalue,
+    number -> Number,
+    identifier -> Identifier,
+    attribute_instance -> AttributeInstance,
+}
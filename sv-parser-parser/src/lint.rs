@@ -0,0 +1,162 @@
+use crate::*;
+use core::str::FromStr;
+
+// -----------------------------------------------------------------------------
+
+/// A SystemVerilog construct found in a region selected as IEEE 1364.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionViolation {
+    /// Offset of the construct in the parsed text
+    pub offset: usize,
+    /// Source text of the construct
+    pub construct: String,
+    /// Version in effect at the construct
+    pub version: Version,
+}
+
+/// Reports SystemVerilog-only constructs in regions selected as IEEE 1364 by `` `begin_keywords ``
+/// or `default_version`.
+///
+/// It reports the constructs rejected by the grammar restricted by `SpanInfo::strict_version`,
+/// so a tree parsed by the SystemVerilog grammar tells why the restricted parse failed.
+/// Reported are keywords reserved only by IEEE 1800, SystemVerilog-only operators, casts,
+/// time literals, unbased unsized literals, user-defined data types and labels after end keywords.
+/// SystemVerilog constructs written only with IEEE 1364 tokens otherwise,
+/// such as an ANSI port list of `.*` connections, are not detected.
+///
+/// Returns all violations in the order of the text. A construct inside a reported one
+/// is not reported again.
+pub fn lint_version(
+    node: RefNode,
+    text: &str,
+    default_version: Option<Version>,
+) -> Vec<VersionViolation> {
+    let mut ret = Vec::new();
+    let mut versions = Vec::new();
+    let mut in_white_space = 0;
+    // An end label is the token after an end keyword and `:`
+    let mut label = EndLabel::None;
+    // Node of the next `Locate`
+    let mut token = Token::Other;
+    // End of the last reported construct
+    let mut reported = 0;
+    for event in node.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::KeywordsDirective(x)) => {
+                let (_, _, _, ref version, _) = x.nodes;
+                let (ref version,) = version.nodes;
+                let version = Version::from_str(version.nodes.0.str(text)).ok();
+                versions.push(version);
+            }
+            NodeEvent::Enter(RefNode::EndkeywordsDirective(_)) => {
+                versions.pop();
+            }
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => in_white_space += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => in_white_space -= 1,
+            NodeEvent::Enter(x) if in_white_space == 0 => {
+                let version = match versions.last() {
+                    Some(x) => *x,
+                    None => default_version,
+                };
+                let version = match version {
+                    Some(x) if x.is_ieee1364() => x,
+                    _ => continue,
+                };
+                let keywords = version.keywords();
+                let range = match &x {
+                    RefNode::Keyword(x) => {
+                        let t = x.nodes.0.str(text);
+                        token = Token::Keyword;
+                        if KEYWORDS_1800_2017.contains(&t) && !keywords.contains(&t) {
+                            extent((*x).into())
+                        } else {
+                            None
+                        }
+                    }
+                    RefNode::Symbol(x) => {
+                        token = Token::Symbol;
+                        if SYMBOLS_1800.contains(&x.nodes.0.str(text)) {
+                            extent((*x).into())
+                        } else {
+                            None
+                        }
+                    }
+                    RefNode::Locate(x) => {
+                        let t = x.str(text);
+                        let ret = match (label, token) {
+                            (EndLabel::Colon(start), Token::Other) => {
+                                Some((start, x.offset + x.len))
+                            }
+                            _ => None,
+                        };
+                        label = match (label, token) {
+                            (_, Token::Keyword)
+                                if t.starts_with("end") || t.starts_with("join") =>
+                            {
+                                EndLabel::Keyword(x.offset)
+                            }
+                            (EndLabel::Keyword(x), Token::Symbol) if t == ":" => EndLabel::Colon(x),
+                            _ => EndLabel::None,
+                        };
+                        token = Token::Other;
+                        ret
+                    }
+                    RefNode::DataType(DataType::ClassType(_))
+                    | RefNode::DataType(DataType::Type(_))
+                    | RefNode::DataType(DataType::PsCovergroupIdentifier(_))
+                    | RefNode::DataType(DataType::TypeReference(_))
+                    | RefNode::NetDeclaration(NetDeclaration::NetTypeIdentifier(_))
+                    | RefNode::Cast(_)
+                    | RefNode::ConstantCast(_)
+                    | RefNode::TimeLiteral(_)
+                    | RefNode::UnbasedUnsizedLiteral(_) => extent(x.clone()),
+                    _ => None,
+                };
+                if let Some((start, end)) = range {
+                    if start >= reported {
+                        ret.push(VersionViolation {
+                            offset: start,
+                            construct: String::from(&text[start..end]),
+                            version,
+                        });
+                        reported = end;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    ret
+}
+
+#[derive(Clone, Copy)]
+enum EndLabel {
+    None,
+    Keyword(usize),
+    Colon(usize),
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+    Keyword,
+    Symbol,
+    Other,
+}
+
+/// Returns the range of `node` in the text without the white spaces after it.
+fn extent(node: RefNode) -> Option<(usize, usize)> {
+    let mut ret: Option<(usize, usize)> = None;
+    let mut in_white_space = 0;
+    for event in node.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => in_white_space += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => in_white_space -= 1,
+            NodeEvent::Enter(RefNode::Locate(x)) if in_white_space == 0 => {
+                let start = ret.map_or(x.offset, |(x, _)| x);
+                ret = Some((start, x.offset + x.len));
+            }
+            _ => (),
+        }
+    }
+    ret
+}
@@ -5,6 +5,7 @@ use crate::*;
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn class_item(s: Span) -> IResult<Span, ClassItem> {
+    let _nest = nest(&s)?;
     alt((
         class_item_property,
         class_item_method,
@@ -18,6 +19,9 @@ pub(crate) fn class_item(s: Span) -> IResult<Span, ClassItem> {
             ClassItem::ParameterDeclaration(Box::new(x))
         }),
         map(symbol(";"), |x| ClassItem::Empty(Box::new(x))),
+        map(pair(kept_text_macro_usage, opt(symbol(";"))), |x| {
+            ClassItem::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -309,6 +313,44 @@ pub(crate) fn method_prototype(s: Span) -> IResult<Span, MethodPrototype> {
     ))(s)
 }
 
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn dynamic_override_specifiers(s: Span) -> IResult<Span, DynamicOverrideSpecifiers> {
+    alt((
+        map(
+            pair(
+                map(initial_or_extends_specifier, Some),
+                opt(final_specifier),
+            ),
+            |x| DynamicOverrideSpecifiers { nodes: x },
+        ),
+        map(final_specifier, |x| DynamicOverrideSpecifiers {
+            nodes: (None, Some(x)),
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn initial_or_extends_specifier(s: Span) -> IResult<Span, InitialOrExtendsSpecifier> {
+    alt((
+        map(pair(symbol(":"), keyword("initial")), |x| {
+            InitialOrExtendsSpecifier::Initial(Box::new(x))
+        }),
+        map(pair(symbol(":"), keyword("extends")), |x| {
+            InitialOrExtendsSpecifier::Extends(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn final_specifier(s: Span) -> IResult<Span, FinalSpecifier> {
+    let (s, a) = symbol(":")(s)?;
+    let (s, b) = keyword("final")(s)?;
+    Ok((s, FinalSpecifier { nodes: (a, b) }))
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn class_constructor_declaration(s: Span) -> IResult<Span, ClassConstructorDeclaration> {
@@ -327,7 +369,7 @@ pub(crate) fn class_constructor_declaration(s: Span) -> IResult<Span, ClassConst
     )))(s)?;
     let (s, h) = many0(function_statement_or_null)(s)?;
     let (s, i) = keyword("endfunction")(s)?;
-    let (s, j) = opt(pair(symbol(":"), new))(s)?;
+    let (s, j) = end_label(new)(s)?;
     Ok((
         s,
         ClassConstructorDeclaration {
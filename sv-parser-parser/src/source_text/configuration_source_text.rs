@@ -12,7 +12,7 @@ pub(crate) fn config_declaration(s: Span) -> IResult<Span, ConfigDeclaration> {
     let (s, e) = design_statement(s)?;
     let (s, f) = many0(config_rule_statement)(s)?;
     let (s, g) = keyword("endconfig")(s)?;
-    let (s, h) = opt(pair(symbol(":"), config_identifier))(s)?;
+    let (s, h) = end_label(config_identifier)(s)?;
     Ok((
         s,
         ConfigDeclaration {
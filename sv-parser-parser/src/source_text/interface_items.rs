@@ -8,6 +8,9 @@ pub(crate) fn interface_or_generate_item(s: Span) -> IResult<Span, InterfaceOrGe
     alt((
         interface_or_generate_item_module,
         interface_or_generate_item_extern,
+        map(pair(kept_text_macro_usage, opt(symbol(";"))), |x| {
+            InterfaceOrGenerateItem::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -123,6 +123,7 @@ pub(crate) fn module_common_item(s: Span) -> IResult<Span, ModuleCommonItem> {
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn module_item(s: Span) -> IResult<Span, ModuleItem> {
+    let _nest = nest(&s)?;
     alt((
         map(pair(port_declaration, symbol(";")), |x| {
             ModuleItem::PortDeclaration(Box::new(x))
@@ -142,6 +143,9 @@ pub(crate) fn module_or_generate_item(s: Span) -> IResult<Span, ModuleOrGenerate
         module_or_generate_item_module_item,
         module_or_generate_item_gate,
         module_or_generate_item_udp,
+        map(pair(kept_text_macro_usage, opt(symbol(";"))), |x| {
+            ModuleOrGenerateItem::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
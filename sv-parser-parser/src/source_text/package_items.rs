@@ -18,6 +18,9 @@ pub(crate) fn package_item(s: Span) -> IResult<Span, PackageItem> {
         map(timeunits_declaration, |x| {
             PackageItem::TimeunitsDeclaration(Box::new(x))
         }),
+        map(pair(kept_text_macro_usage, opt(symbol(";"))), |x| {
+            PackageItem::TextMacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -124,7 +124,7 @@ pub(crate) fn module_declaration_nonansi(s: Span) -> IResult<Span, ModuleDeclara
     let (s, b) = opt(timeunits_declaration)(s)?;
     let (s, c) = many0(module_item)(s)?;
     let (s, d) = keyword("endmodule")(s)?;
-    let (s, e) = opt(pair(symbol(":"), module_identifier))(s)?;
+    let (s, e) = end_label(module_identifier)(s)?;
     Ok((
         s,
         ModuleDeclaration::Nonansi(Box::new(ModuleDeclarationNonansi {
@@ -140,7 +140,7 @@ pub(crate) fn module_declaration_ansi(s: Span) -> IResult<Span, ModuleDeclaratio
     let (s, b) = opt(timeunits_declaration)(s)?;
     let (s, c) = many0(non_port_module_item)(s)?;
     let (s, d) = keyword("endmodule")(s)?;
-    let (s, e) = opt(pair(symbol(":"), module_identifier))(s)?;
+    let (s, e) = end_label(module_identifier)(s)?;
     Ok((
         s,
         ModuleDeclaration::Ansi(Box::new(ModuleDeclarationAnsi {
@@ -161,7 +161,7 @@ pub(crate) fn module_declaration_wildcard(s: Span) -> IResult<Span, ModuleDeclar
     let (s, g) = opt(timeunits_declaration)(s)?;
     let (s, h) = many0(module_item)(s)?;
     let (s, i) = keyword("endmodule")(s)?;
-    let (s, j) = opt(pair(symbol(":"), module_identifier))(s)?;
+    let (s, j) = end_label(module_identifier)(s)?;
     Ok((
         s,
         ModuleDeclaration::Wildcard(Box::new(ModuleDeclarationWildcard {
@@ -224,7 +224,7 @@ pub(crate) fn interface_declaration_nonansi(s: Span) -> IResult<Span, InterfaceD
     let (s, b) = opt(timeunits_declaration)(s)?;
     let (s, c) = many0(interface_item)(s)?;
     let (s, d) = keyword("endinterface")(s)?;
-    let (s, e) = opt(pair(symbol(":"), interface_identifier))(s)?;
+    let (s, e) = end_label(interface_identifier)(s)?;
     Ok((
         s,
         InterfaceDeclaration::Nonansi(Box::new(InterfaceDeclarationNonansi {
@@ -240,7 +240,7 @@ pub(crate) fn interface_declaration_ansi(s: Span) -> IResult<Span, InterfaceDecl
     let (s, b) = opt(timeunits_declaration)(s)?;
     let (s, c) = many0(non_port_interface_item)(s)?;
     let (s, d) = keyword("endinterface")(s)?;
-    let (s, e) = opt(pair(symbol(":"), interface_identifier))(s)?;
+    let (s, e) = end_label(interface_identifier)(s)?;
     Ok((
         s,
         InterfaceDeclaration::Ansi(Box::new(InterfaceDeclarationAnsi {
@@ -261,7 +261,7 @@ pub(crate) fn interface_declaration_wildcard(s: Span) -> IResult<Span, Interface
     let (s, g) = opt(timeunits_declaration)(s)?;
     let (s, h) = many0(interface_item)(s)?;
     let (s, i) = keyword("endinterface")(s)?;
-    let (s, j) = opt(pair(symbol(":"), interface_identifier))(s)?;
+    let (s, j) = end_label(interface_identifier)(s)?;
     Ok((
         s,
         InterfaceDeclaration::Wildcard(Box::new(InterfaceDeclarationWildcard {
@@ -353,7 +353,7 @@ pub(crate) fn program_declaration_nonansi(s: Span) -> IResult<Span, ProgramDecla
     let (s, b) = opt(timeunits_declaration)(s)?;
     let (s, c) = many0(program_item)(s)?;
     let (s, d) = keyword("endprogram")(s)?;
-    let (s, e) = opt(pair(symbol(":"), program_identifier))(s)?;
+    let (s, e) = end_label(program_identifier)(s)?;
     Ok((
         s,
         ProgramDeclaration::Nonansi(Box::new(ProgramDeclarationNonansi {
@@ -369,7 +369,7 @@ pub(crate) fn program_declaration_ansi(s: Span) -> IResult<Span, ProgramDeclarat
     let (s, b) = opt(timeunits_declaration)(s)?;
     let (s, c) = many0(non_port_program_item)(s)?;
     let (s, d) = keyword("endprogram")(s)?;
-    let (s, e) = opt(pair(symbol(":"), program_identifier))(s)?;
+    let (s, e) = end_label(program_identifier)(s)?;
     Ok((
         s,
         ProgramDeclaration::Ansi(Box::new(ProgramDeclarationAnsi {
@@ -389,7 +389,7 @@ pub(crate) fn program_declaration_wildcard(s: Span) -> IResult<Span, ProgramDecl
     let (s, f) = opt(timeunits_declaration)(s)?;
     let (s, g) = many0(program_item)(s)?;
     let (s, h) = keyword("endprogram")(s)?;
-    let (s, i) = opt(pair(symbol(":"), program_identifier))(s)?;
+    let (s, i) = end_label(program_identifier)(s)?;
     Ok((
         s,
         ProgramDeclaration::Wildcard(Box::new(ProgramDeclarationWildcard {
@@ -469,7 +469,7 @@ pub(crate) fn checker_declaration(s: Span) -> IResult<Span, CheckerDeclaration>
     let (s, d) = symbol(";")(s)?;
     let (s, e) = many0(pair(many0(attribute_instance), checker_or_generate_item))(s)?;
     let (s, f) = keyword("endchecker")(s)?;
-    let (s, g) = opt(pair(symbol(":"), checker_identifier))(s)?;
+    let (s, g) = end_label(checker_identifier)(s)?;
     Ok((
         s,
         CheckerDeclaration {
@@ -483,26 +483,27 @@ pub(crate) fn checker_declaration(s: Span) -> IResult<Span, CheckerDeclaration>
 pub(crate) fn class_declaration(s: Span) -> IResult<Span, ClassDeclaration> {
     let (s, a) = opt(map(keyword("virtual"), |x| Virtual { nodes: (x,) }))(s)?;
     let (s, b) = keyword("class")(s)?;
-    let (s, c) = opt(lifetime)(s)?;
-    let (s, d) = class_identifier(s)?;
-    let (s, e) = opt(parameter_port_list)(s)?;
-    let (s, f) = opt(triple(
+    let (s, c) = opt(final_specifier)(s)?;
+    let (s, d) = opt(lifetime)(s)?;
+    let (s, e) = class_identifier(s)?;
+    let (s, f) = opt(parameter_port_list)(s)?;
+    let (s, g) = opt(triple(
         keyword("extends"),
         class_type,
         opt(paren(list_of_arguments)),
     ))(s)?;
-    let (s, g) = opt(pair(
+    let (s, h) = opt(pair(
         keyword("implements"),
         list(symbol(","), interface_class_type),
     ))(s)?;
-    let (s, h) = symbol(";")(s)?;
-    let (s, i) = many0(class_item)(s)?;
-    let (s, j) = keyword("endclass")(s)?;
-    let (s, k) = opt(pair(symbol(":"), class_identifier))(s)?;
+    let (s, i) = symbol(";")(s)?;
+    let (s, j) = many0(class_item)(s)?;
+    let (s, k) = keyword("endclass")(s)?;
+    let (s, l) = end_label(class_identifier)(s)?;
     Ok((
         s,
         ClassDeclaration {
-            nodes: (a, b, c, d, e, f, g, h, i, j, k),
+            nodes: (a, b, c, d, e, f, g, h, i, j, k, l),
         },
     ))
 }
@@ -529,7 +530,7 @@ pub(crate) fn interface_class_declaration(s: Span) -> IResult<Span, InterfaceCla
     let (s, f) = symbol(";")(s)?;
     let (s, g) = many0(interface_class_item)(s)?;
     let (s, h) = keyword("endclass")(s)?;
-    let (s, i) = opt(pair(symbol(":"), class_identifier))(s)?;
+    let (s, i) = end_label(class_identifier)(s)?;
     Ok((
         s,
         InterfaceClassDeclaration {
@@ -593,7 +594,7 @@ pub(crate) fn package_declaration(s: Span) -> IResult<Span, PackageDeclaration>
     let (s, f) = opt(timeunits_declaration)(s)?;
     let (s, g) = many0(pair(many0(attribute_instance), package_item))(s)?;
     let (s, h) = keyword("endpackage")(s)?;
-    let (s, i) = opt(pair(symbol(":"), package_identifier))(s)?;
+    let (s, i) = end_label(package_identifier)(s)?;
     Ok((
         s,
         PackageDeclaration {
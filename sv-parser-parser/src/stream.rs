@@ -0,0 +1,149 @@
+use crate::*;
+use nom::error::ParseError;
+
+// -----------------------------------------------------------------------------
+
+/// Part of a source text passed by `stream_parser`.
+///
+/// Module declarations are split into their headers, items and `endmodule`s,
+/// and the other descriptions are passed as a whole.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamItem {
+    /// Leading white spaces and the timeunits declaration of the source text
+    SourceText(Vec<WhiteSpace>, Option<TimeunitsDeclaration>),
+    Description(Description),
+    ModuleNonansiHeader(Box<ModuleNonansiHeader>, Option<TimeunitsDeclaration>),
+    ModuleAnsiHeader(Box<ModuleAnsiHeader>, Option<TimeunitsDeclaration>),
+    ModuleItem(ModuleItem),
+    NonPortModuleItem(NonPortModuleItem),
+    /// `endmodule` and its label
+    Endmodule(Keyword, Option<(Symbol, ModuleIdentifier)>),
+}
+
+impl<'a> From<&'a StreamItem> for RefNodes<'a> {
+    fn from(x: &'a StreamItem) -> Self {
+        let mut ret = Vec::new();
+        match x {
+            StreamItem::SourceText(x, y) => {
+                ret.extend(x.iter().map(RefNode::from));
+                ret.extend(y.iter().map(RefNode::from));
+            }
+            StreamItem::Description(x) => ret.push(x.into()),
+            StreamItem::ModuleNonansiHeader(x, y) => {
+                ret.push(x.as_ref().into());
+                ret.extend(y.iter().map(RefNode::from));
+            }
+            StreamItem::ModuleAnsiHeader(x, y) => {
+                ret.push(x.as_ref().into());
+                ret.extend(y.iter().map(RefNode::from));
+            }
+            StreamItem::ModuleItem(x) => ret.push(x.into()),
+            StreamItem::NonPortModuleItem(x) => ret.push(x.into()),
+            StreamItem::Endmodule(x, y) => {
+                ret.push(x.into());
+                if let Some((y, z)) = y {
+                    ret.push(y.into());
+                    ret.push(z.into());
+                }
+            }
+        }
+        ret.into()
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// Parses a source text item by item, and passes each `StreamItem` to `f` without keeping it.
+///
+/// The parse stops successfully when `f` returns `false`, and the rest of the text is returned.
+/// A module is tried as non-ANSI, ANSI and the other module declarations in the order of
+/// `module_declaration`, and the furthest error of them is returned when all of them fail.
+/// Once a header is passed to `f` the module isn't tried again as the others, but it doesn't
+/// change the result because `module_item` includes `non_port_module_item`.
+pub fn stream_parser<F>(s: Span, mut f: F) -> IResult<Span, ()>
+where
+    F: FnMut(StreamItem) -> bool,
+{
+    s.extra.state.clear();
+    let (s, a) = many0(white_space)(s)?;
+    let (mut s, b) = opt(timeunits_declaration)(s)?;
+    if !f(StreamItem::SourceText(a, b)) {
+        return Ok((s, ()));
+    }
+
+    loop {
+        if let Ok((t, _)) = eof(s) {
+            return Ok((t, ()));
+        }
+        let nonansi = pair(module_nonansi_header, opt(timeunits_declaration))(s);
+        let (t, ret) = match nonansi {
+            Ok((t, (a, b))) => {
+                if !f(StreamItem::ModuleNonansiHeader(Box::new(a), b)) {
+                    return Ok((t, ()));
+                }
+                stream_items(t, &mut f, module_item, StreamItem::ModuleItem)?
+            }
+            Err(Err::Error(e)) => match pair(module_ansi_header, opt(timeunits_declaration))(s) {
+                Ok((t, (a, b))) => {
+                    if !f(StreamItem::ModuleAnsiHeader(Box::new(a), b)) {
+                        return Ok((t, ()));
+                    }
+                    stream_items(
+                        t,
+                        &mut f,
+                        non_port_module_item,
+                        StreamItem::NonPortModuleItem,
+                    )?
+                }
+                Err(Err::Error(e2)) => match description(s) {
+                    Ok((t, a)) => (t, f(StreamItem::Description(a))),
+                    Err(Err::Error(e3)) => return Err(Err::Error(e.or(e2).or(e3))),
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+        s = t;
+        if !ret {
+            return Ok((s, ()));
+        }
+    }
+}
+
+/// Parses the items of a module until `endmodule`, and returns the rest of the text and
+/// whether `f` continues the parse.
+///
+/// If `endmodule` doesn't follow the items, the furthest error of the last item and `endmodule`
+/// is returned.
+fn stream_items<'a, F, G, T, H>(
+    mut s: Span<'a>,
+    f: &mut F,
+    item: G,
+    wrap: H,
+) -> Result<(Span<'a>, bool), Err<GreedyError<Span<'a>>>>
+where
+    F: FnMut(StreamItem) -> bool,
+    G: Fn(Span<'a>) -> IResult<Span<'a>, T>,
+    H: Fn(T) -> StreamItem,
+{
+    let e = loop {
+        match item(s) {
+            Ok((t, x)) => {
+                s = t;
+                if !f(wrap(x)) {
+                    return Ok((s, false));
+                }
+            }
+            Err(Err::Error(e)) => break e,
+            Err(e) => return Err(e),
+        }
+    };
+    let (s, a) = match keyword("endmodule")(s) {
+        Ok(x) => x,
+        Err(Err::Error(e2)) => return Err(Err::Error(e.or(e2))),
+        Err(e) => return Err(e),
+    };
+    let (s, b) = opt(pair(symbol(":"), module_identifier))(s)?;
+    Ok((s, f(StreamItem::Endmodule(a, b))))
+}
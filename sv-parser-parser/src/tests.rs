@@ -4,8 +4,14 @@ use crate::*;
 
 macro_rules! test {
     ( $x:expr, $y:expr, $z:pat ) => {
-        nom_packrat::init!();
-        let info = SpanInfo::default();
+        test!($x, $y, $z, None);
+    };
+    ( $x:expr, $y:expr, $z:pat, $version:expr ) => {
+        let state = ParserState::new();
+        let info = SpanInfo {
+            default_version: $version,
+            ..SpanInfo::new(&state)
+        };
         #[cfg(feature = "trace")]
         let info = info.set_tracable_info(
             info.get_tracable_info()
@@ -15,6 +21,9 @@ macro_rules! test {
                 .fold("unary_operator"),
         );
         let ret = all_consuming($x)(Span::new_extra($y, info));
+        if let Ok((_, ref x)) = ret {
+            assert_eq!(source(x, $y), $y, "source round-trip");
+        }
         if let $z = ret {
         } else {
             assert!(false, "{:?}", ret)
@@ -22,6 +31,17 @@ macro_rules! test {
     };
 }
 
+/// Regenerates the source text from the tokens and white spaces of `nodes`.
+fn source<'a, T: Into<RefNodes<'a>>>(nodes: T, text: &str) -> String {
+    let mut ret = String::new();
+    for x in Iter::new(nodes.into()) {
+        if let RefNode::Locate(x) = x {
+            ret.push_str(x.str(text));
+        }
+    }
+    ret
+}
+
 mod unit {
     use super::*;
 
@@ -429,6 +449,953 @@ mod unit {
             Ok((_, _))
         );
     }
+
+    #[test]
+    fn test_default_version() {
+        let version = Some(Version::Ieee1364_2005);
+        test!(
+            source_text,
+            r##"module a; reg [63:0] logic; wire bit; endmodule"##,
+            Ok((_, _)),
+            version
+        );
+        test!(
+            source_text,
+            r##"module a; reg [63:0] logic; endmodule"##,
+            Err(_)
+        );
+        test!(
+            source_text,
+            r##"`begin_keywords "1800-2017"
+                module a; logic b; endmodule
+                `end_keywords"##,
+            Ok((_, _)),
+            version
+        );
+    }
+
+    #[test]
+    fn test_parser_state() {
+        let state = ParserState::new();
+        let text = r##"`begin_keywords "1364-2005"
+            module a; reg [63:0] logic; endmodule"##;
+        let ret = all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state)));
+        assert!(ret.is_ok());
+        assert_eq!(state.versions.borrow().len(), 1);
+
+        let text = r##"module a; logic b; endmodule"##;
+        let ret = all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state)));
+        assert!(ret.is_ok());
+
+        let other = ParserState::new();
+        let ret = all_consuming(source_text)(Span::new_extra(text, SpanInfo::new(&other)));
+        assert!(ret.is_ok());
+        assert!(state.packrat.borrow().len() > 0);
+        assert!(other.packrat.borrow().len() > 0);
+    }
+
+    #[test]
+    fn test_parse_production() {
+        use core::convert::TryInto;
+
+        let state = ParserState::new();
+        let text = r##"  a + b[0] "##;
+        let (_, x) =
+            all_consuming(Expression::parse)(Span::new_extra(text, SpanInfo::new(&state))).unwrap();
+        let locate: Locate = (&x).try_into().unwrap();
+        assert_eq!((locate.offset, locate.len), (2, 9));
+
+        let text = r##"if (a) b = c; else d = e;"##;
+        let ret = all_consuming(Statement::parse)(Span::new_extra(text, SpanInfo::new(&state)));
+        assert!(matches!(ret, Ok((_, Statement { .. }))));
+
+        let text = r##" a + "##;
+        let ret = all_consuming(Expression::parse)(Span::new_extra(text, SpanInfo::new(&state)));
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_keep_macros() {
+        let parse = |text: &str, keep_macros: bool| {
+            let state = ParserState::new();
+            let info = SpanInfo {
+                keep_macros,
+                ..SpanInfo::new(&state)
+            };
+            let ret = all_consuming(source_text)(Span::new_extra(text, info));
+            ret.ok().map(|(_, x)| x)
+        };
+        let text = r##"module a; logic [`W-1:0] b; initial begin b = `F(1, 2); `INFO("c") end `ITEM endmodule"##;
+        let x = parse(text, true).unwrap();
+        let mut macros = Vec::new();
+        for node in &x {
+            match node {
+                RefNode::ConstantPrimary(ConstantPrimary::TextMacroUsage(_)) => {
+                    macros.push("constant")
+                }
+                RefNode::Primary(Primary::TextMacroUsage(_)) => macros.push("primary"),
+                RefNode::StatementItem(StatementItem::TextMacroUsage(_)) => {
+                    macros.push("statement")
+                }
+                RefNode::ModuleOrGenerateItem(ModuleOrGenerateItem::TextMacroUsage(_)) => {
+                    macros.push("item")
+                }
+                RefNode::WhiteSpace(WhiteSpace::CompilerDirective(_)) => macros.push("white_space"),
+                _ => (),
+            }
+        }
+        assert_eq!(macros, vec!["constant", "primary", "statement", "item"]);
+
+        let x = parse(text, false);
+        assert!(x.is_none());
+        let text = r##"module a; `ITEM endmodule"##;
+        let x = parse(text, false).unwrap();
+        assert!((&x)
+            .into_iter()
+            .any(|x| matches!(x, RefNode::WhiteSpace(WhiteSpace::CompilerDirective(_)))));
+
+        let text = r##"module a; initial $display(`__FILE__, `__LINE__); endmodule"##;
+        let x = parse(text, true).unwrap();
+        let macros = (&x)
+            .into_iter()
+            .filter(|x| matches!(x, RefNode::Primary(Primary::TextMacroUsage(_))))
+            .count();
+        assert_eq!(macros, 2);
+    }
+
+    #[test]
+    fn test_strict_version() {
+        let parse = |text: &str, version: Option<Version>, strict_version: bool| {
+            let state = ParserState::new();
+            let info = SpanInfo {
+                default_version: version,
+                strict_version,
+                ..SpanInfo::new(&state)
+            };
+            let ret = all_consuming(source_text)(Span::new_extra(text, info));
+            ret.ok().map(|(_, x)| x)
+        };
+        let check = |text: &str, version: Option<Version>| {
+            let x = parse(text, version, false).unwrap();
+            let violations = lint::lint_version((&x).into(), text, version);
+            // The restricted grammar rejects what the lint reports
+            assert_eq!(parse(text, version, true).is_some(), violations.is_empty());
+            violations
+                .into_iter()
+                .map(|x| x.construct)
+                .collect::<Vec<_>>()
+        };
+        let text = r##"module a; always_ff @(posedge clk) q <= d; endmodule"##;
+        assert!(check(text, None).is_empty());
+        assert_eq!(check(text, Some(Version::Ieee1364_2005)), vec!["always_ff"]);
+        let text = r##"module a; initial i++; endmodule"##;
+        assert_eq!(check(text, Some(Version::Ieee1364_2001)), vec!["++"]);
+        let text = r##"module a; reg [63:0] logic; endmodule"##;
+        assert!(check(text, Some(Version::Ieee1364_2005)).is_empty());
+        let text = r##"module a; initial b = signed' (c); endmodule"##;
+        assert_eq!(
+            check(text, Some(Version::Ieee1364_2005)),
+            vec!["signed' (c)"]
+        );
+        let text = r##"module a; initial b = 8'hff; endmodule"##;
+        assert!(check(text, Some(Version::Ieee1364_1995)).is_empty());
+        let text = r##"module a; initial begin : b end endmodule : a"##;
+        assert_eq!(
+            check(text, Some(Version::Ieee1364_2005)),
+            vec!["endmodule : a"]
+        );
+        let text = r##"module a; always @(c) case (c) 0: begin end 1: d = 0; endcase endmodule"##;
+        assert!(check(text, Some(Version::Ieee1364_2005)).is_empty());
+        let text = r##"module a; t b; initial begin c = '0; #1ns; end endmodule"##;
+        assert_eq!(
+            check(text, Some(Version::Ieee1364_2005)),
+            vec!["t b;", "'0", "1ns"]
+        );
+        let text = r##"`begin_keywords "1800-2017"
+            module a; logic b; endmodule
+            `end_keywords"##;
+        assert!(check(text, Some(Version::Ieee1364_2005)).is_empty());
+        let text = r##"`begin_keywords "1364-2005"
+            module a; logic b; endmodule
+            `end_keywords
+            module c; logic d; endmodule"##;
+        assert_eq!(check(text, None), vec!["logic b;"]);
+    }
+
+    #[test]
+    fn test_version_specifier_1800_2023() {
+        test!(
+            source_text,
+            r##"`begin_keywords "1800-2023"
+                module a; logic b; endmodule
+                `end_keywords"##,
+            Ok((_, _))
+        );
+        test!(
+            source_text,
+            r##"module a; logic b; endmodule"##,
+            Ok((_, _)),
+            Some(Version::Ieee1800_2023)
+        );
+
+        // The keywords of 1800-2023 are reserved in its region
+        assert_eq!("1800-2023".parse(), Ok(Version::Ieee1800_2023));
+        assert_eq!(Version::Ieee1800_2023.keywords(), KEYWORDS_1800_2023);
+        test!(
+            source_text,
+            r##"`begin_keywords "1800-2023"
+                module a; reg [63:0] logic; endmodule
+                `end_keywords"##,
+            Err(_),
+            Some(Version::Ieee1364_2005)
+        );
+        test!(
+            source_text,
+            r##"`begin_keywords "1364-2005"
+                module a; reg [63:0] logic; endmodule
+                `end_keywords"##,
+            Ok((_, _)),
+            Some(Version::Ieee1800_2023)
+        );
+    }
+
+    #[test]
+    fn test_position_compiler_directive() {
+        test!(position_compiler_directive, r##"`__FILE__"##, Ok((_, _)));
+        test!(position_compiler_directive, r##"`__LINE__"##, Ok((_, _)));
+        test!(
+            text_macro_definition,
+            r##"`define a $display(`__FILE__, `__LINE__);"##,
+            Ok((_, _))
+        );
+        test!(
+            source_text,
+            r##"module a; initial $display(`__FILE__, `__LINE__); endmodule"##,
+            Ok((_, _))
+        );
+    }
+
+    #[test]
+    fn test_ifdef_macro_expression() {
+        test!(ifdef_directive, r##"`ifdef (A && B) `endif"##, Ok((_, _)));
+        test!(
+            ifdef_directive,
+            r##"`ifdef (!A || (B -> C)) `elsif (A <-> B) `else `endif"##,
+            Ok((_, _))
+        );
+        test!(ifndef_directive, r##"`ifndef (A || B) `endif"##, Ok((_, _)));
+        test!(ifdef_macro_expression, r##"A && B || C -> D"##, Ok((_, _)));
+    }
+
+    #[test]
+    fn test_dynamic_override_specifiers() {
+        test!(dynamic_override_specifiers, r##":initial"##, Ok((_, _)));
+        test!(
+            dynamic_override_specifiers,
+            r##":extends :final"##,
+            Ok((_, _))
+        );
+        test!(dynamic_override_specifiers, r##":final"##, Ok((_, _)));
+        test!(
+            class_declaration,
+            r##"class :final C extends B;
+                  virtual function :extends :final void f(); endfunction
+                  virtual task :initial t(); endtask
+                  pure virtual function :initial void g();
+                endclass"##,
+            Ok((_, _))
+        );
+    }
+
+    #[test]
+    fn test_type_parameter_forward_type() {
+        test!(
+            module_declaration,
+            r##"module a; parameter type enum E = logic; endmodule"##,
+            Ok((_, _))
+        );
+        test!(
+            module_declaration,
+            r##"module a; localparam type interface class I = J; endmodule"##,
+            Ok((_, _))
+        );
+        test!(
+            module_declaration,
+            r##"module a #(type class T = C, type struct S = s_t) (); endmodule"##,
+            Ok((_, _))
+        );
+    }
+
+    #[test]
+    fn test_array_map_method() {
+        test!(
+            array_manipulation_call,
+            r##"map(x) with (x + 1)"##,
+            Ok((
+                _,
+                ArrayManipulationCall {
+                    nodes: (ArrayMethodName::Map(_), _, Some(_), Some(_))
+                }
+            ))
+        );
+        test!(
+            source_text,
+            r##"module a; initial b = c.map(x) with (x + 1); endmodule"##,
+            Ok((_, _))
+        );
+    }
+
+    #[test]
+    fn test_tokenizer() {
+        use tokenizer::TokenKind::*;
+        fn tokens(text: &str, version: Option<Version>) -> Vec<(tokenizer::TokenKind, &str)> {
+            tokenizer::tokenize(text, version)
+                .into_iter()
+                .map(|x| (x.kind, x.locate.str(text)))
+                .filter(|x| x.0 != WhiteSpace)
+                .collect::<Vec<_>>()
+        }
+        let text = r##"`define W 8 // width
+            logic [`W-1:0] a = 8'hFF; initial $display("\"b\"", a <<= 1.5e3, 10ns);"##;
+        assert_eq!(
+            tokens(text, None),
+            vec![
+                (Directive, "`define"),
+                (Identifier, "W"),
+                (Number, "8"),
+                (Comment, "// width"),
+                (Keyword, "logic"),
+                (Operator, "["),
+                (Directive, "`W"),
+                (Operator, "-"),
+                (Number, "1"),
+                (Operator, ":"),
+                (Number, "0"),
+                (Operator, "]"),
+                (Identifier, "a"),
+                (Operator, "="),
+                (Number, "8'hFF"),
+                (Operator, ";"),
+                (Keyword, "initial"),
+                (Identifier, "$display"),
+                (Operator, "("),
+                (String, r##""\"b\"""##),
+                (Operator, ","),
+                (Identifier, "a"),
+                (Operator, "<<="),
+                (Number, "1.5e3"),
+                (Operator, ","),
+                (Number, "10ns"),
+                (Operator, ")"),
+                (Operator, ";"),
+            ]
+        );
+        let text = r##"logic `begin_keywords "1364-2005" logic `end_keywords logic"##;
+        let kinds = tokens(text, None);
+        assert_eq!(kinds[0], (Keyword, "logic"));
+        assert_eq!(kinds[3], (Identifier, "logic"));
+        assert_eq!(kinds[5], (Keyword, "logic"));
+        assert_eq!(
+            tokens("logic", Some(Version::Ieee1364_1995)),
+            vec![(Identifier, "logic")]
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let state = ParserState::new();
+        let parse = |text: &str, max_depth| {
+            let info = SpanInfo {
+                max_depth,
+                ..SpanInfo::new(&state)
+            };
+            all_consuming(sv_parser)(Span::new_extra(text, info))
+                .ok()
+                .map(|_| ())
+        };
+        let paren = |n: usize| {
+            format!(
+                "module a; initial b = {}1{}; endmodule",
+                "(".repeat(n),
+                ")".repeat(n)
+            )
+        };
+        let concat = |n: usize| {
+            format!(
+                "module a; initial b = {}1{}; endmodule",
+                "{".repeat(n),
+                "}".repeat(n)
+            )
+        };
+        let begin = |n: usize| {
+            format!(
+                "module a; initial {}b = 1;{} endmodule",
+                "begin ".repeat(n),
+                " end".repeat(n)
+            )
+        };
+        let generate = |n: usize| {
+            format!(
+                "module a; {}endmodule",
+                "if (1) begin ".repeat(n) + &"end ".repeat(n)
+            )
+        };
+        let binary = |n: usize| {
+            let x: Vec<_> = (0..n).map(|i| format!("c{}", i)).collect();
+            format!("module a; assign b = {}; endmodule", x.join(" + "))
+        };
+        let conditional = |n: usize| {
+            let x: String = (0..n).map(|i| format!("c{} ? {} : ", i, i)).collect();
+            format!("module a; assign b = {}0; endmodule", x)
+        };
+
+        // Parse on the test thread, whose stack is smaller than the nesting needs
+        for text in &[paren(40), concat(40), begin(100), generate(40)] {
+            assert!(parse(text, Some(DEFAULT_MAX_DEPTH)).is_some());
+            assert_eq!(state.exceeded_depth(), None);
+        }
+        for text in &[paren(20), concat(20), begin(20), generate(20)] {
+            assert!(parse(text, Some(16)).is_none());
+            assert!(state.exceeded_depth().is_some());
+        }
+        assert!(parse(&begin(300), Some(DEFAULT_MAX_DEPTH)).is_none());
+        assert!(state.exceeded_depth().is_some());
+        assert!(parse(&begin(300), None).is_some());
+
+        // Chains of operators aren't nested
+        for text in &[binary(1000), conditional(1000)] {
+            assert!(parse(text, Some(4)).is_some());
+            assert_eq!(state.exceeded_depth(), None);
+        }
+    }
+
+    #[test]
+    fn test_parse_control() {
+        let text = "module a; endmodule\n".repeat(10000);
+        let reported = Cell::new(0);
+        let progress = |x| reported.set(x);
+        let cancel = CancellationToken::new();
+        let state = ParserState::new();
+        let info = SpanInfo {
+            control: ParseControl {
+                cancel: Some(&cancel),
+                progress: Some(&progress),
+            },
+            ..SpanInfo::new(&state)
+        };
+
+        assert!(all_consuming(sv_parser)(Span::new_extra(&text, info)).is_ok());
+        assert!(reported.get() > text.len() / 2);
+        assert!(!state.cancelled());
+
+        cancel.cancel();
+        assert!(all_consuming(sv_parser)(Span::new_extra(&text, info)).is_err());
+        assert!(state.cancelled());
+    }
+
+    #[test]
+    fn test_packrat_config() {
+        let text = "module a; initial begin b = c + d; e <= f ? g : h; end endmodule";
+        let parse = |config| {
+            let state = ParserState::with_packrat(config);
+            let ok = all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state))).is_ok();
+            let len = state.packrat.borrow().len();
+            (ok, len, state.packrat_stats())
+        };
+
+        let (ok, len, stats) = parse(PackratConfig {
+            capacity: 16,
+            eviction: PackratEviction::Lru,
+            stats: true,
+        });
+        assert!(ok);
+        assert!(len <= 16);
+        assert!(!stats.productions.is_empty());
+        assert!(stats.productions.values().all(|x| x.hits <= x.calls));
+
+        let (ok, len, stats) = parse(PackratConfig {
+            capacity: 0,
+            ..PackratConfig::default()
+        });
+        assert!(ok);
+        assert_eq!(len, 0);
+        assert!(stats.productions.is_empty());
+    }
+
+    #[test]
+    fn test_packrat_storage() {
+        let mut storage = PackratStorage::new(PackratConfig {
+            capacity: 2,
+            ..PackratConfig::default()
+        });
+        let entry = || Err((Vec::new(), false));
+        storage.insert(("a", 0, false), entry());
+        storage.insert(("b", 0, false), entry());
+        // Re-inserting an existing key doesn't evict others
+        storage.insert(("a", 0, false), entry());
+        assert_eq!(storage.len(), 2);
+        assert!(storage.get(&("b", 0, false)).is_some());
+
+        storage.insert(("c", 0, false), entry());
+        assert_eq!(storage.len(), 2);
+        assert!(storage.get(&("a", 0, false)).is_some());
+        assert!(storage.get(&("b", 0, false)).is_none());
+        assert!(storage.get(&("c", 0, false)).is_some());
+    }
+
+    #[test]
+    fn test_packrat_stats() {
+        let text = "module a; initial b = c + d; endmodule";
+        let state = ParserState::with_packrat(PackratConfig {
+            stats: true,
+            ..PackratConfig::default()
+        });
+        let calls = |state: &ParserState| {
+            let stats = state.packrat_stats();
+            stats.productions.values().map(|x| x.calls).sum::<usize>()
+        };
+
+        let ret = all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state)));
+        assert!(ret.is_ok());
+        let first = calls(&state);
+        assert!(first > 0);
+
+        let ret = all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state)));
+        assert!(ret.is_ok());
+        assert_eq!(calls(&state), 2 * first);
+
+        state.clear();
+        assert_eq!(state.packrat.borrow().len(), 0);
+        assert_eq!(calls(&state), 2 * first);
+    }
+
+    #[test]
+    fn test_stream_parser() {
+        use stream::{stream_parser, StreamItem};
+
+        let text = "module a(b); input b; c d(b); endmodule module e; wire f; endmodule package g; endpackage";
+        let state = ParserState::new();
+        let mut items = Vec::new();
+        let ret = stream_parser(Span::new_extra(text, SpanInfo::new(&state)), |x| {
+            items.push(match x {
+                StreamItem::SourceText(_, _) => "source_text",
+                StreamItem::Description(_) => "description",
+                StreamItem::ModuleNonansiHeader(_, _) => "nonansi_header",
+                StreamItem::ModuleAnsiHeader(_, _) => "ansi_header",
+                StreamItem::ModuleItem(_) => "module_item",
+                StreamItem::NonPortModuleItem(_) => "non_port_module_item",
+                StreamItem::Endmodule(_, _) => "endmodule",
+            });
+            true
+        });
+        assert!(ret.is_ok());
+        assert_eq!(
+            items,
+            [
+                "source_text",
+                "nonansi_header",
+                "module_item",
+                "module_item",
+                "endmodule",
+                "ansi_header",
+                "non_port_module_item",
+                "endmodule",
+                "description",
+            ]
+        );
+
+        let mut count = 0;
+        let ret = stream_parser(Span::new_extra(text, SpanInfo::new(&state)), |_| {
+            count += 1;
+            count < 3
+        });
+        assert_eq!(count, 3);
+        assert!(ret.unwrap().0.fragment().starts_with("c d(b);"));
+
+        let text = "module a(input b); wire c; endmodule";
+        let mut items = Vec::new();
+        let ret = stream_parser(Span::new_extra(text, SpanInfo::new(&state)), |x| {
+            items.push(matches!(x, StreamItem::ModuleAnsiHeader(_, _)));
+            true
+        });
+        assert!(ret.is_ok());
+        assert_eq!(items, [false, true, false, false]);
+
+        // The error of the item is further than `endmodule`
+        let text = "module a; wire b; wire = c; endmodule";
+        let ret = stream_parser(Span::new_extra(text, SpanInfo::new(&state)), |_| true);
+        let e = match ret {
+            Err(Err::Error(e)) => e,
+            _ => panic!(),
+        };
+        assert_eq!(nom_greedyerror::error_position(&e), Some(23));
+    }
+
+    #[test]
+    fn test_tree_index() {
+        let text = "module a; always @* b = c; endmodule module d; endmodule";
+        let state = ParserState::new();
+        let (_, x) =
+            all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state))).unwrap();
+        let index = TreeIndex::new(&x);
+        let root = RefNode::from(&x);
+        assert_eq!(index.parent(&root), None);
+        assert_eq!(index.depth(&root), Some(0));
+
+        let c = (&x)
+            .into_iter()
+            .filter(|x| matches!(x, RefNode::Identifier(_)))
+            .nth(2)
+            .unwrap();
+        assert!(index
+            .ancestors(&c)
+            .any(|x| matches!(x, RefNode::AlwaysConstruct(_))));
+        assert!(matches!(
+            index.ancestors(&c).last(),
+            Some(RefNode::SourceText(_))
+        ));
+        assert_eq!(index.depth(&c), Some(index.ancestors(&c).count()));
+
+        let parent = index.parent(&c).unwrap();
+        assert!(index.children(&parent).any(|x| x == c));
+        for x in index.children(&root) {
+            assert_eq!(index.parent(&x), Some(root.clone()));
+        }
+        let first = index.children(&root).next().unwrap();
+        let second = index.next_sibling(&first).unwrap();
+        assert_eq!(index.prev_sibling(&second), Some(first));
+
+        let pos = text.find("c;").unwrap();
+        let nodes = index.node_at(pos);
+        assert!(matches!(nodes[0], RefNode::Locate(_)));
+        assert_eq!(
+            nodes[1..],
+            index.ancestors(&nodes[0]).collect::<Vec<_>>()[..]
+        );
+        assert!(nodes.contains(&c));
+        assert!(index.node_at(text.len()).is_empty());
+
+        let begin = text.find("b =").unwrap();
+        let nodes = index.nodes_covering(begin..pos + 1);
+        assert!(matches!(nodes[0], RefNode::OperatorAssignment(_)));
+        assert!(matches!(nodes[1], RefNode::BlockingAssignment(_)));
+    }
+
+    #[test]
+    fn test_visitor() {
+        struct Identifiers<'a>(Vec<&'a Identifier>);
+
+        impl<'a> Visitor<'a> for Identifiers<'a> {
+            fn visit_identifier(&mut self, x: &'a Identifier) {
+                self.0.push(x);
+            }
+
+            fn visit_attribute_instance(&mut self, _x: &'a AttributeInstance) {}
+        }
+
+        struct Shift(usize);
+
+        impl VisitorMut for Shift {
+            fn visit_locate_mut(&mut self, x: &mut Locate) {
+                x.offset += self.0;
+            }
+        }
+
+        let text = "module a; (* b *) c d(e); endmodule";
+        let state = ParserState::new();
+        let (_, mut x) =
+            all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state))).unwrap();
+
+        let mut identifiers = Identifiers(Vec::new());
+        identifiers.visit((&x).into());
+        let names: Vec<_> = identifiers
+            .0
+            .iter()
+            .filter_map(|x| match x {
+                Identifier::SimpleIdentifier(x) => Some(x.nodes.0.str(text)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["a", "c", "d", "e"]);
+
+        let before: Vec<_> = (&x)
+            .into_iter()
+            .filter_map(|x| match x {
+                RefNode::Locate(x) => Some(*x),
+                _ => None,
+            })
+            .collect();
+        Shift(10).visit_mut((&mut x).into());
+        let after: Vec<_> = (&x)
+            .into_iter()
+            .filter_map(|x| match x {
+                RefNode::Locate(x) => Some(*x),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(before.len(), after.len());
+        assert!(before
+            .iter()
+            .zip(&after)
+            .all(|(x, y)| x.offset + 10 == y.offset));
+    }
+
+    #[test]
+    fn test_compact_node() {
+        let text = "module a; endmodule";
+        let state = ParserState::new();
+        let (_, x) =
+            all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state))).unwrap();
+        let x = CompactNode::new((&x).into(), text);
+        assert_eq!(x.kind, "SourceText");
+        assert_eq!(x.span, Some((0, text.len())));
+
+        let mut tokens = Vec::new();
+        let mut stack = vec![&x];
+        while let Some(x) = stack.pop() {
+            if let Some(t) = &x.text {
+                assert_eq!(x.kind, "Locate");
+                tokens.push(t.as_str());
+            }
+            stack.extend(x.children.iter().rev());
+        }
+        assert_eq!(tokens.concat(), text);
+    }
+
+    #[test]
+    fn test_query() {
+        let text = r##"module a;
+  always_ff @(posedge clk) b <= c;
+  always_ff @(posedge clk or negedge rst_n) d <= e;
+  always_comb f = g;
+endmodule"##;
+        let state = ParserState::new();
+        let (_, x) =
+            all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state))).unwrap();
+        let index = TreeIndex::new(&x);
+        let query = |query: &str| Query::new(query).unwrap().matches(&index, text);
+        let matches = |x: &str| {
+            query(x)
+                .into_iter()
+                .map(|x| &text[x.span.unwrap()])
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matches(
+                "AlwaysConstruct[keyword=always_ff]:not(:has(EventExpression[identifier^=rst]))"
+            ),
+            ["always_ff @(posedge clk) b <= c;"]
+        );
+        assert_eq!(
+            matches("AlwaysConstruct[keyword^=always_c] VariableLvalue"),
+            ["f"]
+        );
+        assert_eq!(matches("ModuleAnsiHeader > ModuleIdentifier"), ["a"]);
+        assert_eq!(
+            matches("ModuleDeclarationAnsi > VariableLvalue"),
+            [] as [&str; 0]
+        );
+        assert_eq!(
+            matches("VariableLvalue, Expression[text=e]"),
+            ["b", "d", "e", "f"]
+        );
+        assert_eq!(
+            matches("*[text*=\"<= e\"]:not(:has(*[text*=\"<= e\"]))"),
+            ["d <= e"]
+        );
+        assert_eq!(
+            matches("AlwaysConstruct:has(AlwaysKeyword[text=always_comb])"),
+            ["always_comb f = g;"]
+        );
+
+        // `:has` matches the compounds below the node
+        assert_eq!(
+            matches("ModuleItem:has(ModuleItem VariableLvalue)"),
+            [] as [&str; 0]
+        );
+
+        // Captures are the nearest ancestors matching the compounds
+        let m = query("AlwaysConstruct@a Statement@b VariableLvalue[text=d]");
+        assert_eq!(m.len(), 1);
+        assert_eq!(m[0].captures[0].0, "a");
+        assert_eq!(m[0].captures[1].0, "b");
+        assert_eq!(
+            index.span(&m[0].captures[1].1).map(|x| &text[x]),
+            Some("d <= e;")
+        );
+
+        // Descendant combinators aren't backtracked for each ancestor
+        let deep = "* ".repeat(20) + "Locate";
+        let expected = (&x)
+            .into_iter()
+            .filter(|x| matches!(x, RefNode::Locate(_)) && index.depth(x).unwrap() >= 20)
+            .count();
+        assert_eq!(query(&deep).len(), expected);
+
+        assert_eq!(
+            Query::new("AlwaysConstruct[keyword").unwrap_err().offset,
+            23
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let b = Builder::new();
+        let msb = b.constant_binary(b.constant_name("W"), BinaryOp::Sub, b.constant_number(1));
+        let data_type = b.logic(Some((msb, b.constant_number(0))));
+        let parameters = vec![b.parameter("W", b.constant_number(8))];
+        let ports = vec![
+            b.port(Direction::Input, Some(b.logic(None)), "clk"),
+            b.port(Direction::Input, Some(data_type.clone()), "d"),
+            b.port(Direction::Output, Some(data_type.clone()), "q"),
+        ];
+        let sum = b.binary(b.name("r"), BinaryOp::Add, b.name("d"));
+        let next = b.if_else(
+            b.binary(b.name("r"), BinaryOp::Eq, b.hex(8, 255)),
+            b.blocking("s", b.number(0)),
+            Some(b.blocking("s", b.unary(UnaryOp::BitNot, sum))),
+        );
+        let items = vec![
+            b.variable(data_type, &["r", "s"]),
+            b.always_ff("clk", b.seq_block(vec![b.nonblocking("r", b.name("d"))])),
+            b.always_comb(next),
+            b.assign("q", b.name("s")),
+            b.instance(
+                "sub",
+                vec![("W", b.number(8))],
+                "u+1",
+                vec![("clk", b.name("clk")), ("x", b.name("s"))],
+            ),
+        ];
+        let module = b.module("top", parameters, ports, items);
+        let (module, text) = b.finish(module);
+        assert_eq!(
+            text,
+            concat!(
+                "module top #(parameter W = 8) (\n",
+                "  input logic clk,\n",
+                "  input logic [W - 1:0] d,\n",
+                "  output logic [W - 1:0] q\n",
+                ");\n",
+                "  logic [W - 1:0] r, s;\n",
+                "  always_ff @(posedge clk) begin\n",
+                "    r <= d;\n",
+                "  end\n",
+                "  always_comb\n",
+                "    if (r == 8'hff) s = 0;\n",
+                "    else s = ~(r + d);\n",
+                "  assign q = s;\n",
+                "  sub #(.W(8)) \\u+1 (\n",
+                "    .clk(clk),\n",
+                "    .x(s)\n",
+                "  );\n",
+                "endmodule\n",
+            )
+        );
+
+        // The generated text is parsed to the same tokens and white spaces
+        fn locates<'a, T: Into<RefNodes<'a>>>(nodes: T) -> Vec<Locate> {
+            Iter::new(nodes.into())
+                .filter_map(|x| match x {
+                    RefNode::Locate(x) => Some(*x),
+                    _ => None,
+                })
+                .collect()
+        }
+        let state = ParserState::new();
+        let (_, x) =
+            all_consuming(sv_parser)(Span::new_extra(text.as_str(), SpanInfo::new(&state)))
+                .unwrap();
+        assert_eq!(locates(&x), locates(&module));
+        assert_eq!(Printer::default().print(&x, &text), text);
+
+        // Reserved keywords become escaped identifiers
+        let b = Builder::new();
+        let module = b.module("logic", vec![], vec![], vec![]);
+        let (_, text) = b.finish(module);
+        assert_eq!(text, "module \\logic ();\nendmodule\n");
+
+        // Names can't be empty or contain white spaces, which end escaped identifiers
+        for name in &["", "a b", "a\tb"] {
+            let ret = std::panic::catch_unwind(|| Builder::new().identifier(name));
+            assert!(ret.is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_accessors() {
+        let text = "module a #(parameter B = 1, C = 2) (input d, output [1:0] e); \
+                    f #(.G(B)) h(d), i(e); function int j(int k, int l); endfunction endmodule \
+                    module m(n, .o(p)); input n; input p; task q(input int r); endtask endmodule";
+        let state = ParserState::new();
+        let (_, x) =
+            all_consuming(sv_parser)(Span::new_extra(text, SpanInfo::new(&state))).unwrap();
+        let modules: Vec<_> = (&x)
+            .into_iter()
+            .filter_map(|x| match x {
+                RefNode::ModuleDeclaration(x) => Some(x),
+                _ => None,
+            })
+            .collect();
+
+        let a = modules[0];
+        assert_eq!(a.name().str(text), "a");
+        let names: Vec<_> = a.parameters().iter().map(|x| x.nodes.0.str(text)).collect();
+        assert_eq!(names, ["B", "C"]);
+        let names: Vec<_> = a.ports().iter().map(|x| x.str(text)).collect();
+        assert_eq!(names, ["d", "e"]);
+        assert!(a.port_declarations().is_empty());
+        assert_eq!(a.items().len(), 2);
+
+        let f = (&x)
+            .into_iter()
+            .find_map(|x| match x {
+                RefNode::ModuleInstantiation(x) => Some(x),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(f.module_name().str(text), "f");
+        let names: Vec<_> = f.instances().iter().map(|x| x.name().str(text)).collect();
+        assert_eq!(names, ["h", "i"]);
+
+        let j = (&x)
+            .into_iter()
+            .find_map(|x| match x {
+                RefNode::FunctionDeclaration(x) => Some(x),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(j.name().str(text), "j");
+        assert!(matches!(
+            j.return_type(),
+            FunctionDataTypeOrImplicit::DataTypeOrVoid(_)
+        ));
+        let names: Vec<_> = j
+            .ports()
+            .iter()
+            .filter_map(|x| x.name())
+            .map(|x| x.str(text))
+            .collect();
+        assert_eq!(names, ["k", "l"]);
+
+        let m = modules[1];
+        let names: Vec<_> = m.ports().iter().map(|x| x.str(text)).collect();
+        assert_eq!(names, ["n", "o"]);
+        assert_eq!(m.port_declarations().len(), 2);
+        assert_eq!(m.items().len(), 1);
+        assert!(m.parameters().is_empty());
+
+        let q = (&x)
+            .into_iter()
+            .find_map(|x| match x {
+                RefNode::TaskDeclaration(x) => Some(x),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(q.name().str(text), "q");
+        assert_eq!(q.ports().len(), 1);
+    }
 }
 
 mod spec {
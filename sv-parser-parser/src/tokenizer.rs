@@ -0,0 +1,255 @@
+use crate::*;
+use core::str::FromStr;
+
+// -----------------------------------------------------------------------------
+
+const OPERATORS: &[&str] = &[
+    "<<<=", ">>>=", "===", "!==", "==?", "!=?", "<<<", ">>>", "<<=", ">>=", "&&&", "->>", "|->",
+    "|=>", "#-#", "#=#", "<->", "==", "!=", "<=", ">=", "&&", "||", "**", "<<", ">>", "->", "++",
+    "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "~&", "~|", "~^", "^~", "::", "##", ".*",
+    "'{", "+:", "-:", ":=", "=>", "*>", "@@",
+];
+
+const TIME_UNITS: &[&str] = &["s", "ms", "us", "ns", "ps", "fs"];
+
+/// Kind of a token returned by `Tokenizer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Keyword,
+    /// Operators and punctuations
+    Operator,
+    /// Simple, escaped and system identifiers
+    Identifier,
+    /// Integral, real, time and unbased unsized literals
+    Number,
+    String,
+    /// Compiler directives and text macro usages without their arguments
+    Directive,
+    Comment,
+    WhiteSpace,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub locate: Locate,
+}
+
+/// Iterator of the tokens of a source text.
+///
+/// The text is split without parsing or preprocessing, so every byte belongs to exactly one token.
+/// Keywords are selected by `` `begin_keywords `` directives in the text, or `default_version`
+/// like the parser.
+#[derive(Clone, Debug)]
+pub struct Tokenizer<'a> {
+    text: &'a str,
+    offset: usize,
+    line: u32,
+    default_version: Option<Version>,
+    versions: Vec<Version>,
+    begin_keywords: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(text: &'a str, default_version: Option<Version>) -> Self {
+        Tokenizer {
+            text,
+            offset: 0,
+            line: 1,
+            default_version,
+            versions: Vec::new(),
+            begin_keywords: false,
+        }
+    }
+
+    fn is_keyword(&self, s: &str) -> bool {
+        let version = self.versions.last().copied().or(self.default_version);
+        let keywords = version.map_or(KEYWORDS_1800_2017, |x| x.keywords());
+        keywords.contains(&s)
+    }
+
+    /// Returns the kind and length of the token at the current offset.
+    fn token(&self) -> (TokenKind, usize) {
+        let s = &self.text[self.offset..];
+        let c = s.chars().next().unwrap();
+
+        if c.is_ascii_whitespace() {
+            return (
+                TokenKind::WhiteSpace,
+                len_while(s, |x| x.is_ascii_whitespace()),
+            );
+        }
+        if s.starts_with("//") {
+            return (TokenKind::Comment, s.find('\n').unwrap_or(s.len()));
+        }
+        if let Some(x) = s.strip_prefix("/*") {
+            return (TokenKind::Comment, x.find("*/").map_or(s.len(), |x| x + 4));
+        }
+        match c {
+            '`' => {
+                let len = 1 + len_while(&s[1..], is_identifier_char);
+                let len = if len == 1 {
+                    // `" and `` in macro texts
+                    match s[1..].chars().next() {
+                        Some('"') | Some('`') => 2,
+                        _ if s[1..].starts_with("\\`\"") => 4,
+                        _ => 1,
+                    }
+                } else {
+                    len
+                };
+                (TokenKind::Directive, len)
+            }
+            '"' => (TokenKind::String, string_len(s)),
+            '\\' => (
+                TokenKind::Identifier,
+                len_while(s, |x| !x.is_ascii_whitespace()),
+            ),
+            '$' if s[1..].starts_with(is_identifier_char) => (
+                TokenKind::Identifier,
+                1 + len_while(&s[1..], is_identifier_char),
+            ),
+            '0'..='9' => (TokenKind::Number, number_len(s)),
+            '\'' => match based_len(s) {
+                Some(len) => (TokenKind::Number, len),
+                None if s.starts_with("'{") => (TokenKind::Operator, 2),
+                None => (TokenKind::Operator, 1),
+            },
+            'a'..='z' | 'A'..='Z' | '_' => {
+                let len = len_while(s, is_identifier_char);
+                if self.is_keyword(&s[..len]) {
+                    (TokenKind::Keyword, len)
+                } else {
+                    (TokenKind::Identifier, len)
+                }
+            }
+            _ => match OPERATORS.iter().find(|x| s.starts_with(*x)) {
+                Some(x) => (TokenKind::Operator, x.len()),
+                None => (TokenKind::Operator, c.len_utf8()),
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.offset >= self.text.len() {
+            return None;
+        }
+
+        let (kind, len) = self.token();
+        let locate = Locate {
+            offset: self.offset,
+            line: self.line,
+            len,
+        };
+        let s = locate.str(self.text);
+
+        match kind {
+            TokenKind::WhiteSpace | TokenKind::Comment => (),
+            TokenKind::Directive if s == "`begin_keywords" => self.begin_keywords = true,
+            TokenKind::Directive if s == "`end_keywords" => {
+                self.versions.pop();
+                self.begin_keywords = false;
+            }
+            TokenKind::String if self.begin_keywords => {
+                if let Ok(x) = Version::from_str(s[1..].trim_end_matches('"')) {
+                    self.versions.push(x);
+                }
+                self.begin_keywords = false;
+            }
+            _ => self.begin_keywords = false,
+        }
+
+        self.offset += len;
+        self.line += s.matches('\n').count() as u32;
+        Some(Token { kind, locate })
+    }
+}
+
+/// Returns the tokens of `text`.
+pub fn tokenize(text: &str, default_version: Option<Version>) -> Vec<Token> {
+    Tokenizer::new(text, default_version).collect()
+}
+
+// -----------------------------------------------------------------------------
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+fn len_while<F: Fn(char) -> bool>(s: &str, f: F) -> usize {
+    s.find(|x| !f(x)).unwrap_or(s.len())
+}
+
+fn string_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i + 1,
+            _ => (),
+        }
+    }
+    s.len()
+}
+
+fn number_len(s: &str) -> usize {
+    let mut len = len_while(s, |x| x.is_ascii_digit() || x == '_');
+    if let Some(x) = based_len(&s[len..]) {
+        return len + x;
+    }
+    if s[len..].starts_with('.') && s[len + 1..].starts_with(|x: char| x.is_ascii_digit()) {
+        len += 1 + len_while(&s[len + 1..], |x| x.is_ascii_digit() || x == '_');
+    }
+    if s[len..].starts_with(&['e', 'E'][..]) {
+        let sign = s[len + 1..].starts_with(&['+', '-'][..]) as usize;
+        if s[len + 1 + sign..].starts_with(|x: char| x.is_ascii_digit()) {
+            len += 1 + sign + len_while(&s[len + 1 + sign..], |x| x.is_ascii_digit() || x == '_');
+        }
+    }
+    let unit = len_while(&s[len..], is_identifier_char);
+    if TIME_UNITS.contains(&&s[len..len + unit]) {
+        len += unit;
+    }
+    len
+}
+
+/// Returns the length of a based value or an unbased unsized literal starting with `'`.
+fn based_len(s: &str) -> Option<usize> {
+    let mut chars = s.chars();
+    if chars.next() != Some('\'') {
+        return None;
+    }
+    let mut len = 1;
+    let mut c = chars.next()?;
+    if c == 's' || c == 'S' {
+        len += 1;
+        c = chars.next()?;
+    }
+    match c {
+        'b' | 'B' | 'o' | 'O' | 'd' | 'D' | 'h' | 'H' => {
+            len += 1;
+            let space = len_while(&s[len..], |x| x == ' ' || x == '\t');
+            let value = len_while(&s[len + space..], |x| {
+                x.is_ascii_hexdigit() || "xXzZ?_".contains(x)
+            });
+            if value > 0 {
+                Some(len + space + value)
+            } else {
+                Some(len)
+            }
+        }
+        '0' | '1' | 'x' | 'X' | 'z' | 'Z' if len == 1 => {
+            if s[2..].starts_with(is_identifier_char) {
+                None
+            } else {
+                Some(2)
+            }
+        }
+        _ => None,
+    }
+}
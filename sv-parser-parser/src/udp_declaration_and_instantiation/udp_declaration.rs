@@ -54,7 +54,7 @@ pub(crate) fn udp_declaration_nonansi(s: Span) -> IResult<Span, UdpDeclaration>
     let (s, c) = many0(udp_port_declaration)(s)?;
     let (s, d) = udp_body(s)?;
     let (s, e) = keyword("endprimitive")(s)?;
-    let (s, f) = opt(pair(symbol(":"), udp_identifier))(s)?;
+    let (s, f) = end_label(udp_identifier)(s)?;
     Ok((
         s,
         UdpDeclaration::Nonansi(Box::new(UdpDeclarationNonansi {
@@ -69,7 +69,7 @@ pub(crate) fn udp_declaration_ansi(s: Span) -> IResult<Span, UdpDeclaration> {
     let (s, a) = udp_ansi_declaration(s)?;
     let (s, b) = udp_body(s)?;
     let (s, c) = keyword("endprimitive")(s)?;
-    let (s, d) = opt(pair(symbol(":"), udp_identifier))(s)?;
+    let (s, d) = end_label(udp_identifier)(s)?;
     Ok((
         s,
         UdpDeclaration::Ansi(Box::new(UdpDeclarationAnsi {
@@ -111,7 +111,7 @@ pub(crate) fn udp_declaration_wildcard(s: Span) -> IResult<Span, UdpDeclaration>
     let (s, f) = many0(udp_port_declaration)(s)?;
     let (s, g) = udp_body(s)?;
     let (s, h) = keyword("endprimitive")(s)?;
-    let (s, i) = opt(pair(symbol(":"), udp_identifier))(s)?;
+    let (s, i) = end_label(udp_identifier)(s)?;
     Ok((
         s,
         UdpDeclaration::Wildcard(Box::new(UdpDeclarationWildcard {
@@ -1,4 +1,10 @@
 use crate::*;
+use core::cell::Cell;
+use core::convert::TryFrom;
+use nom::Slice;
+use nom_greedyerror::GreedyErrorKind;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 // -----------------------------------------------------------------------------
 
@@ -26,6 +32,9 @@ where
 #[cfg(not(feature = "trace"))]
 pub(crate) fn symbol<'a>(t: &'a str) -> impl Fn(Span<'a>) -> IResult<Span<'a>, Symbol> {
     move |s: Span<'a>| {
+        if sv_only_symbol(&s, t) {
+            return Err(Err::Error(make_error(s, ErrorKind::Tag)));
+        }
         let (s, x) = map(ws(map(tag(t), into_locate)), |x| Symbol { nodes: x })(s)?;
         Ok((s, x))
     }
@@ -36,6 +45,9 @@ pub(crate) fn symbol<'a>(t: &'a str) -> impl Fn(Span<'a>) -> IResult<Span<'a>, S
     move |s: Span<'a>| {
         let (depth, s) = nom_tracable::forward_trace(s, &format!("symbol(\"{}\")", t));
         let body = || {
+            if sv_only_symbol(&s, t) {
+                return Err(Err::Error(make_error(s, ErrorKind::Tag)));
+            }
             let (s, x) = map(ws(map(tag(t), into_locate)), |x| Symbol { nodes: x })(s)?;
             Ok((s, x))
         };
@@ -68,6 +80,9 @@ pub(crate) fn symbol_exact<'a>(t: &'a str) -> impl Fn(Span<'a>) -> IResult<Span<
 #[cfg(not(feature = "trace"))]
 pub(crate) fn keyword<'a>(t: &'a str) -> impl Fn(Span<'a>) -> IResult<Span<'a>, Keyword> {
     move |s: Span<'a>| {
+        if sv_only_keyword(&s, t) {
+            return Err(Err::Error(make_error(s, ErrorKind::Tag)));
+        }
         let (s, x) = map(
             ws(alt((
                 all_consuming(map(tag(t), into_locate)),
@@ -84,6 +99,9 @@ pub(crate) fn keyword<'a>(t: &'a str) -> impl Fn(Span<'a>) -> IResult<Span<'a>,
     move |s: Span<'a>| {
         let (depth, s) = nom_tracable::forward_trace(s, &format!("keyword(\"{}\")", t));
         let body = || {
+            if sv_only_keyword(&s, t) {
+                return Err(Err::Error(make_error(s, ErrorKind::Tag)));
+            }
             let (s, x) = map(
                 ws(alt((
                     all_consuming(map(tag(t), into_locate)),
@@ -105,6 +123,7 @@ where
 {
     move |s: Span<'a>| {
         let (s, a) = symbol("(")(s)?;
+        let _nest = nest(&s)?;
         let (s, b) = f(s)?;
         let (s, c) = symbol(")")(s)?;
         Ok((s, Paren { nodes: (a, b, c) }))
@@ -120,6 +139,7 @@ where
         let (depth, s) = nom_tracable::forward_trace(s, "paren");
         let body = || {
             let (s, a) = symbol("(")(s)?;
+            let _nest = nest(&s)?;
             let (s, b) = f(s)?;
             let (s, c) = symbol(")")(s)?;
             Ok((s, Paren { nodes: (a, b, c) }))
@@ -136,6 +156,7 @@ where
 {
     move |s: Span<'a>| {
         let (s, a) = symbol("(")(s)?;
+        let _nest = nest(&s)?;
         let (s, b) = f(s)?;
         let (s, c) = symbol_exact(")")(s)?;
         Ok((s, Paren { nodes: (a, b, c) }))
@@ -151,6 +172,7 @@ where
         let (depth, s) = nom_tracable::forward_trace(s, "paren");
         let body = || {
             let (s, a) = symbol("(")(s)?;
+            let _nest = nest(&s)?;
             let (s, b) = f(s)?;
             let (s, c) = symbol_exact(")")(s)?;
             Ok((s, Paren { nodes: (a, b, c) }))
@@ -167,6 +189,7 @@ where
 {
     move |s: Span<'a>| {
         let (s, a) = symbol("[")(s)?;
+        let _nest = nest(&s)?;
         let (s, b) = f(s)?;
         let (s, c) = symbol("]")(s)?;
         Ok((s, Bracket { nodes: (a, b, c) }))
@@ -182,6 +205,7 @@ where
         let (depth, s) = nom_tracable::forward_trace(s, "bracket");
         let body = || {
             let (s, a) = symbol("[")(s)?;
+            let _nest = nest(&s)?;
             let (s, b) = f(s)?;
             let (s, c) = symbol("]")(s)?;
             Ok((s, Bracket { nodes: (a, b, c) }))
@@ -198,6 +222,7 @@ where
 {
     move |s: Span<'a>| {
         let (s, a) = symbol("{")(s)?;
+        let _nest = nest(&s)?;
         let (s, b) = f(s)?;
         let (s, c) = symbol("}")(s)?;
         Ok((s, Brace { nodes: (a, b, c) }))
@@ -213,6 +238,7 @@ where
         let (depth, s) = nom_tracable::forward_trace(s, "brace");
         let body = || {
             let (s, a) = symbol("{")(s)?;
+            let _nest = nest(&s)?;
             let (s, b) = f(s)?;
             let (s, c) = symbol("}")(s)?;
             Ok((s, Brace { nodes: (a, b, c) }))
@@ -231,6 +257,7 @@ where
 {
     move |s: Span<'a>| {
         let (s, a) = symbol("'{")(s)?;
+        let _nest = nest(&s)?;
         let (s, b) = f(s)?;
         let (s, c) = symbol("}")(s)?;
         Ok((s, ApostropheBrace { nodes: (a, b, c) }))
@@ -248,6 +275,7 @@ where
         let (depth, s) = nom_tracable::forward_trace(s, "apostrophe_brace");
         let body = || {
             let (s, a) = symbol("'{")(s)?;
+            let _nest = nest(&s)?;
             let (s, b) = f(s)?;
             let (s, c) = symbol("}")(s)?;
             Ok((s, ApostropheBrace { nodes: (a, b, c) }))
@@ -314,7 +342,8 @@ pub(crate) fn eof(s: Span) -> IResult<Span, Span> {
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn white_space(s: Span) -> IResult<Span, WhiteSpace> {
-    if in_directive() {
+    let keep_macros = s.extra.keep_macros;
+    if in_directive(&s) {
         alt((
             map(multispace1, |x: Span| {
                 WhiteSpace::Space(Box::new(into_locate(x)))
@@ -332,35 +361,102 @@ pub(crate) fn white_space(s: Span) -> IResult<Span, WhiteSpace> {
                 WhiteSpace::Comment(Box::new(x))
             }),
             map(
-                preceded(peek(char('`')), compiler_directive_without_resetall),
+                preceded(
+                    peek(char('`')),
+                    verify(compiler_directive_without_resetall, |x| {
+                        !(keep_macros
+                            && matches!(
+                                x,
+                                CompilerDirective::TextMacroUsage(_)
+                                    | CompilerDirective::PositionCompilerDirective(_)
+                            ))
+                    }),
+                ),
                 |x| WhiteSpace::CompilerDirective(Box::new(x)),
             ),
         ))(s)
     }
 }
 
-thread_local!(
-    static IN_DIRECTIVE: core::cell::RefCell<Vec<()>> = {
-        core::cell::RefCell::new(Vec::new())
-    }
-);
+pub(crate) fn in_directive(s: &Span) -> bool {
+    s.extra.state.in_directive.get() > 0
+}
 
-pub(crate) fn in_directive() -> bool {
-    IN_DIRECTIVE.with(|x| x.borrow().last().is_some())
+pub(crate) fn begin_directive(s: &Span) {
+    let x = &s.extra.state.in_directive;
+    x.set(x.get() + 1);
 }
 
-pub(crate) fn begin_directive() {
-    IN_DIRECTIVE.with(|x| x.borrow_mut().push(()));
+pub(crate) fn end_directive(s: &Span) {
+    let x = &s.extra.state.in_directive;
+    x.set(x.get().saturating_sub(1));
 }
 
-pub(crate) fn end_directive() {
-    IN_DIRECTIVE.with(|x| x.borrow_mut().pop());
+// -----------------------------------------------------------------------------
+
+/// Guard of a nesting level counted by `nest`
+pub(crate) struct Nest<'a>(&'a Cell<usize>);
+
+impl Drop for Nest<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+/// Enters a nesting level until the returned guard is dropped.
+///
+/// Levels are entered by bracket pairs and by productions nesting statements and items,
+/// so the depth is the nesting depth of the source text.
+/// It fails with `Err::Failure` if the depth exceeds `SpanInfo::max_depth`.
+pub(crate) fn nest<'a>(s: &Span<'a>) -> Result<Nest<'a>, Err<GreedyError<Span<'a>>>> {
+    let state = s.extra.state;
+    if let Some(max_depth) = s.extra.max_depth {
+        if state.depth.get() >= max_depth {
+            state.exceeded_depth.set(Some(s.location_offset()));
+            return Err(Err::Failure(make_error(*s, ErrorKind::TooLarge)));
+        }
+    }
+    state.depth.set(state.depth.get() + 1);
+    Ok(Nest(&state.depth))
+}
+
+/// Remaining stack below which `grow` switches to a new stack segment
+const RED_ZONE: usize = 2 * 1024 * 1024;
+
+/// Size of the stack segments allocated by `grow`
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+/// Runs `f` on a new stack segment allocated on the heap if the stack is nearly exhausted.
+///
+/// Every packrat parser runs through it, so deeply nested input doesn't overflow
+/// the stack of the calling thread.
+/// `RED_ZONE` is enough for the parsers between two packrat parsers,
+/// and for cloning results of `DEFAULT_MAX_DEPTH` in debug builds.
+pub(crate) fn grow<T, F: FnOnce() -> T>(f: F) -> T {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, f)
+}
+
+/// Marks the `#[recursive_parser]` named `name` as entered at `s`,
+/// so it fails there as a left recursion.
+///
+/// It's used to parse an operand of a chain, which mustn't be the chain itself.
+pub(crate) fn left_recursion<'a>(s: Span<'a>, name: &'static str) -> Span<'a> {
+    let id = nom_recursive::RECURSIVE_STORAGE.with(|x| x.borrow_mut().get(name));
+    let mut info = s.get_recursive_info();
+    let ptr = s.fragment().as_ptr();
+    if ptr != info.get_ptr() {
+        info.clear_flags();
+        info.set_ptr(ptr);
+    }
+    info.set_flag(id);
+    s.set_recursive_info(info)
 }
 
 // -----------------------------------------------------------------------------
 
-#[derive(Clone, Copy)]
-pub(crate) enum VersionSpecifier {
+/// IEEE standard which selects the reserved keywords.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Version {
     Ieee1364_1995,
     Ieee1364_2001,
     Ieee1364_2001Noconfig,
@@ -369,59 +465,385 @@ pub(crate) enum VersionSpecifier {
     Ieee1800_2009,
     Ieee1800_2012,
     Ieee1800_2017,
+    Ieee1800_2023,
+    /// Reserved words of compiler directive arguments.
+    /// It's selected by the parser itself and can't be given by `` `begin_keywords ``.
     Directive,
 }
 
-thread_local!(
-    static CURRENT_VERSION: core::cell::RefCell<Vec<VersionSpecifier>> = {
-        core::cell::RefCell::new(Vec::new())
-    }
-);
-
-pub(crate) fn begin_keywords(version: &str) {
-    CURRENT_VERSION.with(|current_version| match version {
-        "1364-1995" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1364_1995),
-        "1364-2001" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1364_2001),
-        "1364-2001-noconfig" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1364_2001Noconfig),
-        "1364-2005" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1364_2005),
-        "1800-2005" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1800_2005),
-        "1800-2009" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1800_2009),
-        "1800-2012" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1800_2012),
-        "1800-2017" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Ieee1800_2017),
-        "directive" => current_version
-            .borrow_mut()
-            .push(VersionSpecifier::Directive),
-        _ => (),
-    });
-}
-
-pub(crate) fn end_keywords() {
-    CURRENT_VERSION.with(|current_version| {
-        current_version.borrow_mut().pop();
-    });
-}
-
-pub(crate) fn current_version() -> Option<VersionSpecifier> {
-    CURRENT_VERSION.with(|current_version| match current_version.borrow().last() {
-        Some(x) => Some(*x),
+impl Version {
+    /// Returns the version string used by `` `begin_keywords ``.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Version::Ieee1364_1995 => "1364-1995",
+            Version::Ieee1364_2001 => "1364-2001",
+            Version::Ieee1364_2001Noconfig => "1364-2001-noconfig",
+            Version::Ieee1364_2005 => "1364-2005",
+            Version::Ieee1800_2005 => "1800-2005",
+            Version::Ieee1800_2009 => "1800-2009",
+            Version::Ieee1800_2012 => "1800-2012",
+            Version::Ieee1800_2017 => "1800-2017",
+            Version::Ieee1800_2023 => "1800-2023",
+            Version::Directive => "directive",
+        }
+    }
+
+    /// Returns whether the version is an IEEE 1364 standard, which has no SystemVerilog constructs.
+    pub fn is_ieee1364(&self) -> bool {
+        matches!(
+            self,
+            Version::Ieee1364_1995
+                | Version::Ieee1364_2001
+                | Version::Ieee1364_2001Noconfig
+                | Version::Ieee1364_2005
+        )
+    }
+
+    /// Returns the reserved keywords of the version.
+    pub fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Version::Ieee1364_1995 => KEYWORDS_1364_1995,
+            Version::Ieee1364_2001 => KEYWORDS_1364_2001,
+            Version::Ieee1364_2001Noconfig => KEYWORDS_1364_2001_NOCONFIG,
+            Version::Ieee1364_2005 => KEYWORDS_1364_2005,
+            Version::Ieee1800_2005 => KEYWORDS_1800_2005,
+            Version::Ieee1800_2009 => KEYWORDS_1800_2009,
+            Version::Ieee1800_2012 => KEYWORDS_1800_2012,
+            Version::Ieee1800_2017 => KEYWORDS_1800_2017,
+            Version::Ieee1800_2023 => KEYWORDS_1800_2023,
+            Version::Directive => KEYWORDS_DIRECTIVE,
+        }
+    }
+}
+
+impl core::str::FromStr for Version {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1364-1995" => Ok(Version::Ieee1364_1995),
+            "1364-2001" => Ok(Version::Ieee1364_2001),
+            "1364-2001-noconfig" => Ok(Version::Ieee1364_2001Noconfig),
+            "1364-2005" => Ok(Version::Ieee1364_2005),
+            "1800-2005" => Ok(Version::Ieee1800_2005),
+            "1800-2009" => Ok(Version::Ieee1800_2009),
+            "1800-2012" => Ok(Version::Ieee1800_2012),
+            "1800-2017" => Ok(Version::Ieee1800_2017),
+            "1800-2023" => Ok(Version::Ieee1800_2023),
+            _ => Err(()),
+        }
+    }
+}
+
+impl core::fmt::Display for Version {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+pub(crate) fn begin_keywords(s: &Span, version: Version) {
+    s.extra.state.versions.borrow_mut().push(version);
+}
+
+pub(crate) fn end_keywords(s: &Span) {
+    s.extra.state.versions.borrow_mut().pop();
+}
+
+pub(crate) fn current_version(s: &Span) -> Option<Version> {
+    s.extra.state.versions.borrow().last().copied()
+}
+
+/// Returns the IEEE 1364 version restricting the grammar at `s` by `SpanInfo::strict_version`.
+///
+/// Compiler directives are not restricted.
+pub(crate) fn strict_version(s: &Span) -> Option<Version> {
+    if !s.extra.strict_version || in_directive(s) {
+        return None;
+    }
+    current_version(s)
+        .or(s.extra.default_version)
+        .filter(|x| x.is_ieee1364())
+}
+
+fn sv_only_keyword(s: &Span, t: &str) -> bool {
+    match strict_version(s) {
+        Some(x) => KEYWORDS_1800_2017.contains(&t) && !x.keywords().contains(&t),
+        None => false,
+    }
+}
+
+fn sv_only_symbol(s: &Span, t: &str) -> bool {
+    SYMBOLS_1800.contains(&t) && strict_version(s).is_some()
+}
+
+/// Fails at a SystemVerilog-only production where the grammar is restricted by `strict_version`.
+pub(crate) fn sv_only<'a, O, F>(f: F) -> impl Fn(Span<'a>) -> IResult<Span<'a>, O>
+where
+    F: Fn(Span<'a>) -> IResult<Span<'a>, O>,
+{
+    move |s: Span<'a>| {
+        if strict_version(&s).is_some() {
+            return Err(Err::Error(make_error(s, ErrorKind::Verify)));
+        }
+        f(s)
+    }
+}
+
+/// Parses the label after the end keyword of a block, which is SystemVerilog-only.
+pub(crate) fn end_label<'a, O, F>(
+    f: F,
+) -> impl Fn(Span<'a>) -> IResult<Span<'a>, Option<(Symbol, O)>>
+where
+    F: Fn(Span<'a>) -> IResult<Span<'a>, O>,
+{
+    opt(sv_only(pair(symbol(":"), f)))
+}
+
+// -----------------------------------------------------------------------------
+
+/// Eviction policy of the packrat memoization when it's full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackratEviction {
+    /// Evicts the oldest result.
+    Fifo,
+    /// Evicts the least recently used result.
+    Lru,
+}
+
+/// Configuration of the packrat memoization of a parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackratConfig {
+    /// Maximum number of memoized results. `0` disables the memoization.
+    pub capacity: usize,
+    pub eviction: PackratEviction,
+    /// Collect `PackratStats`.
+    pub stats: bool,
+}
+
+impl std::default::Default for PackratConfig {
+    fn default() -> Self {
+        PackratConfig {
+            capacity: 1024,
+            eviction: PackratEviction::Fifo,
+            stats: false,
+        }
+    }
+}
+
+/// Statistics of a memoized production.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProductionStats {
+    /// Number of calls including hits
+    pub calls: usize,
+    /// Number of calls answered by the memoization
+    pub hits: usize,
+    /// Bytes consumed by successful calls
+    pub bytes: usize,
+    /// Time spent by calls which are not hits, including nested productions
+    pub time: Duration,
+}
+
+/// Statistics of the packrat memoization by production name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackratStats {
+    pub productions: HashMap<&'static str, ProductionStats>,
+}
+
+impl PackratStats {
+    /// Adds the statistics of another parse.
+    pub fn merge(&mut self, other: &PackratStats) {
+        for (name, x) in &other.productions {
+            let y = self.productions.entry(name).or_default();
+            y.calls += x.calls;
+            y.hits += x.hits;
+            y.bytes += x.bytes;
+            y.time += x.time;
+        }
+    }
+
+    fn record(&mut self, name: &'static str, hit: bool, bytes: usize, time: Duration) {
+        let x = self.productions.entry(name).or_default();
+        x.calls += 1;
+        if hit {
+            x.hits += 1;
+        }
+        x.bytes += bytes;
+        x.time += time;
+    }
+}
+
+type PackratKey = (&'static str, usize, bool);
+
+// A successful result is stored as the node and the consumed length,
+// a failure as the error positions and kinds and whether it is `Err::Failure`.
+type PackratEntry = Result<(AnyNode, usize), (Vec<(usize, GreedyErrorKind)>, bool)>;
+
+// Each entry has the stamp of its last insertion, or its last use in LRU eviction.
+// `order` may have stale keys whose stamp doesn't match the entry, and they are skipped by eviction.
+#[derive(Debug, Default)]
+pub(crate) struct PackratStorage {
+    pub(crate) config: PackratConfig,
+    pub(crate) stats: PackratStats,
+    entries: HashMap<PackratKey, (PackratEntry, u64)>,
+    order: VecDeque<(PackratKey, u64)>,
+    stamp: u64,
+}
+
+impl PackratStorage {
+    pub(crate) fn new(config: PackratConfig) -> Self {
+        PackratStorage {
+            config,
+            ..std::default::Default::default()
+        }
+    }
+
+    // The statistics are kept to accumulate over the parses with the state.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn get(&mut self, key: &PackratKey) -> Option<PackratEntry> {
+        let stamp = self.stamp + 1;
+        let (entry, last) = self.entries.get_mut(key)?;
+        let entry = entry.clone();
+        if self.config.eviction == PackratEviction::Lru {
+            *last = stamp;
+            self.stamp = stamp;
+            self.order.push_back((*key, stamp));
+            if self.order.len() > 2 * self.config.capacity {
+                let entries = &self.entries;
+                self.order
+                    .retain(|(k, s)| entries.get(k).is_some_and(|x| x.1 == *s));
+            }
+        }
+        Some(entry)
+    }
+
+    pub(crate) fn insert(&mut self, key: PackratKey, entry: PackratEntry) {
+        if self.config.capacity == 0 {
+            return;
+        }
+        while !self.entries.contains_key(&key) && self.len() >= self.config.capacity {
+            match self.order.pop_front() {
+                Some((k, s)) => {
+                    if self.entries.get(&k).is_some_and(|x| x.1 == s) {
+                        self.entries.remove(&k);
+                    }
+                }
+                None => break,
+            }
+        }
+        self.stamp += 1;
+        self.entries.insert(key, (entry, self.stamp));
+        self.order.push_back((key, self.stamp));
+    }
+}
+
+pub(crate) fn packrat<'a, T, F>(name: &'static str, s: Span<'a>, f: F) -> IResult<Span<'a>, T>
+where
+    T: Clone + Into<AnyNode> + TryFrom<AnyNode>,
+    F: Fn(Span<'a>) -> IResult<Span<'a>, T>,
+{
+    control(&s)?;
+    // Cloning and dropping results recurses as deep as the results,
+    // so they are on the grown stack together with the parse.
+    grow(|| memoize(name, s, f))
+}
+
+fn memoize<'a, T, F>(name: &'static str, s: Span<'a>, f: F) -> IResult<Span<'a>, T>
+where
+    T: Clone + Into<AnyNode> + TryFrom<AnyNode>,
+    F: Fn(Span<'a>) -> IResult<Span<'a>, T>,
+{
+    let packrat = &s.extra.state.packrat;
+    let key = (name, s.location_offset(), in_directive(&s));
+    let entry = packrat.borrow_mut().get(&key);
+    let cached = match entry {
+        Some(Ok((x, len))) => T::try_from(x).ok().map(|x| Ok((s.slice(len..), x))),
+        Some(Err((errors, failure))) => {
+            let errors = errors
+                .into_iter()
+                .map(|(pos, kind)| {
+                    let e = if pos >= s.location_offset() {
+                        s.slice(pos - s.location_offset()..)
+                    } else {
+                        s
+                    };
+                    (e, kind)
+                })
+                .collect();
+            let e = GreedyError { errors };
+            if failure {
+                Some(Err(Err::Failure(e)))
+            } else {
+                Some(Err(Err::Error(e)))
+            }
+        }
         None => None,
-    })
+    };
+    let stats = packrat.borrow().config.stats;
+    let consumed = |ret: &IResult<Span<'a>, T>| match ret {
+        Ok((t, _)) => t.location_offset() - s.location_offset(),
+        Err(_) => 0,
+    };
+    if let Some(ret) = cached {
+        if stats {
+            let bytes = consumed(&ret);
+            let mut packrat = packrat.borrow_mut();
+            packrat.stats.record(name, true, bytes, Duration::default());
+        }
+        return ret;
+    }
+
+    let start = if stats { Some(Instant::now()) } else { None };
+    let ret = f(s);
+    if let Some(start) = start {
+        let bytes = consumed(&ret);
+        let mut packrat = packrat.borrow_mut();
+        packrat.stats.record(name, false, bytes, start.elapsed());
+    }
+    let entry = match &ret {
+        Ok((t, x)) => Ok((x.clone().into(), t.location_offset() - s.location_offset())),
+        Err(Err::Error(e)) => Err((error_kinds(e), false)),
+        Err(Err::Failure(e)) => Err((error_kinds(e), true)),
+        Err(Err::Incomplete(_)) => return ret,
+    };
+    packrat.borrow_mut().insert(key, entry);
+    ret
+}
+
+fn error_kinds(e: &GreedyError<Span>) -> Vec<(usize, GreedyErrorKind)> {
+    e.errors
+        .iter()
+        .map(|(x, kind)| (x.location_offset(), kind.clone()))
+        .collect()
+}
+
+// -----------------------------------------------------------------------------
+
+const PROGRESS_INTERVAL: usize = 1 << 16;
+
+/// Reports the progress, and fails with `Err::Failure` if the parse is cancelled.
+pub(crate) fn control<'a>(s: &Span<'a>) -> Result<(), Err<GreedyError<Span<'a>>>> {
+    let control = s.extra.control;
+    let state = s.extra.state;
+    if control.is_cancelled() {
+        state.cancelled.set(true);
+        return Err(Err::Failure(make_error(*s, ErrorKind::Fix)));
+    }
+    if let Some(progress) = control.progress {
+        let offset = s.location_offset();
+        if offset >= state.progress.get() + PROGRESS_INTERVAL {
+            state.progress.set(offset);
+            progress(offset);
+        }
+    }
+    Ok(())
 }
 
 // -----------------------------------------------------------------------------
@@ -439,18 +861,8 @@ pub(crate) fn concat<'a>(a: Span<'a>, b: Span<'a>) -> Option<Span<'a>> {
 }
 
 pub(crate) fn is_keyword(s: &Span) -> bool {
-    let keywords = match current_version() {
-        Some(VersionSpecifier::Ieee1364_1995) => KEYWORDS_1364_1995,
-        Some(VersionSpecifier::Ieee1364_2001) => KEYWORDS_1364_2001,
-        Some(VersionSpecifier::Ieee1364_2001Noconfig) => KEYWORDS_1364_2001_NOCONFIG,
-        Some(VersionSpecifier::Ieee1364_2005) => KEYWORDS_1364_2005,
-        Some(VersionSpecifier::Ieee1800_2005) => KEYWORDS_1800_2005,
-        Some(VersionSpecifier::Ieee1800_2009) => KEYWORDS_1800_2009,
-        Some(VersionSpecifier::Ieee1800_2012) => KEYWORDS_1800_2012,
-        Some(VersionSpecifier::Ieee1800_2017) => KEYWORDS_1800_2017,
-        Some(VersionSpecifier::Directive) => KEYWORDS_DIRECTIVE,
-        None => KEYWORDS_1800_2017,
-    };
+    let version = current_version(s).or(s.extra.default_version);
+    let keywords = version.map_or(KEYWORDS_1800_2017, |x| x.keywords());
     for k in keywords {
         if s.fragment() == k {
             return true;
@@ -7,11 +7,12 @@ use std::fs::File;
 use std::hash::BuildHasher;
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use sv_parser_error::Error;
-use sv_parser_parser::{pp_parser, Span, SpanInfo};
+use sv_parser_parser::{pp_parser, ParseControl, ParserState, Span, SpanInfo};
 use sv_parser_syntaxtree::{
-    IncludeCompilerDirective, Locate, NodeEvent, RefNode, SourceDescription, TextMacroUsage,
-    WhiteSpace,
+    IfdefMacroExpression, IncludeCompilerDirective, Locate, NodeEvent, RefNode, SourceDescription,
+    TextMacroUsage, WhiteSpace,
 };
 
 const RECURSIVE_LIMIT: usize = 128;
@@ -20,8 +21,15 @@ const RECURSIVE_LIMIT: usize = 128;
 pub struct PreprocessedText {
     text: String,
     origins: BTreeMap<Range, Origin>,
+    path: Option<PathBuf>,
+    /// Disjoint ranges of each source file and their first verbatim copies,
+    /// built by the first `position` after a change of `origins`
+    positions: OnceLock<Box<Positions>>,
 }
 
+/// Ranges of the source files and their offsets in the preprocessed text
+type Positions = HashMap<PathBuf, BTreeMap<Range, usize>>;
+
 #[derive(Debug)]
 pub struct Origin {
     range: Range,
@@ -33,6 +41,8 @@ impl PreprocessedText {
         PreprocessedText {
             text: String::new(),
             origins: BTreeMap::new(),
+            path: None,
+            positions: OnceLock::new(),
         }
     }
 
@@ -50,6 +60,7 @@ impl PreprocessedText {
         let range = Range::new(base, base + s.len());
         let origin = Origin { range, origin };
         self.origins.insert(range, origin);
+        self.positions = OnceLock::new();
     }
 
     fn merge(&mut self, other: PreprocessedText) {
@@ -60,12 +71,230 @@ impl PreprocessedText {
             origin.range.offset(base);
             self.origins.insert(range, origin);
         }
+        self.positions = OnceLock::new();
+    }
+
+    /// Returns the source text `s` of `path` as is, without preprocessing.
+    pub fn verbatim<T: AsRef<Path>>(s: &str, path: T) -> Self {
+        let mut ret = PreprocessedText::new();
+        ret.push(s, Some((path.as_ref(), Range::new(0, s.len()))));
+        ret.path = Some(PathBuf::from(path.as_ref()));
+        ret
+    }
+
+    /// Returns the text `s` generated without source files.
+    pub fn generated(s: &str) -> Self {
+        let mut ret = PreprocessedText::new();
+        ret.push::<PathBuf>(s, None);
+        ret
     }
 
     pub fn text(&self) -> &str {
         &self.text
     }
 
+    /// Replaces the text by `text` of the same length, which keeps the origins of the offsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `text` differs.
+    pub fn replace_text(&mut self, text: String) {
+        assert_eq!(self.text.len(), text.len());
+        self.text = text;
+    }
+
+    /// Returns the path of the file which the text is preprocessed from.
+    /// Included files are not returned.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Applies an edit replacing `range` of the source file `path` by `text`.
+    ///
+    /// Returns the replaced range of the preprocessed text. If the range isn't copied verbatim
+    /// from `path`, the preprocessed text is unchanged and `None` is returned.
+    pub fn apply_edit<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+        range: std::ops::Range<usize>,
+        text: &str,
+    ) -> Option<std::ops::Range<usize>> {
+        let path = path.as_ref();
+        let verbatim = |x: &Origin| match x.origin {
+            Some((ref origin_path, ref origin_range)) => {
+                origin_path == path
+                    && origin_range.end - origin_range.begin == x.range.end - x.range.begin
+            }
+            None => false,
+        };
+
+        // Find the consecutive segments covering the range
+        let mut first: Option<(usize, &Origin)> = None;
+        let mut last: Option<(usize, &Origin)> = None;
+        for (i, x) in self.origins.values().enumerate() {
+            let origin_range = match x.origin {
+                Some((_, ref origin_range)) => origin_range,
+                None => &x.range,
+            };
+            if let Some((_, prev)) = last {
+                let (_, prev_range) = prev.origin.as_ref().unwrap();
+                if !verbatim(x)
+                    || x.range.begin != prev.range.end
+                    || origin_range.begin != prev_range.end
+                {
+                    return None;
+                }
+                last = Some((i, x));
+            } else if verbatim(x)
+                && origin_range.begin <= range.start
+                && range.start <= origin_range.end
+            {
+                first = Some((i, x));
+                last = Some((i, x));
+            } else {
+                continue;
+            }
+            if range.end <= origin_range.end {
+                break;
+            }
+        }
+        let (first_index, first) = first?;
+        let (last_index, last) = last?;
+        let (_, first_range) = first.origin.as_ref().unwrap();
+        let (_, last_range) = last.origin.as_ref().unwrap();
+        if last_range.end < range.end {
+            return None;
+        }
+
+        let begin = first.range.begin + range.start - first_range.begin;
+        let end = last.range.begin + range.end - last_range.begin;
+        let mut merged = Some(Origin {
+            range: Range::new(first.range.begin, last.range.end + text.len() + begin - end),
+            origin: Some((
+                PathBuf::from(path),
+                Range::new(
+                    first_range.begin,
+                    last_range.end + text.len() + range.start - range.end,
+                ),
+            )),
+        });
+
+        let shift = |x: usize, from: usize, to: usize| {
+            if x >= from {
+                x + to - from
+            } else {
+                x
+            }
+        };
+        let origins = std::mem::take(&mut self.origins);
+        for (i, mut x) in origins.into_values().enumerate() {
+            if i == first_index {
+                let merged = merged.take().unwrap();
+                self.origins.insert(merged.range, merged);
+                continue;
+            }
+            if first_index < i && i <= last_index {
+                continue;
+            }
+            if i > last_index {
+                x.range = Range::new(
+                    shift(x.range.begin, end, begin + text.len()),
+                    shift(x.range.end, end, begin + text.len()),
+                );
+            }
+            if let Some((ref origin_path, ref mut origin_range)) = x.origin {
+                if origin_path == path && origin_range.begin >= range.end {
+                    *origin_range = Range::new(
+                        shift(origin_range.begin, range.end, range.start + text.len()),
+                        shift(origin_range.end, range.end, range.start + text.len()),
+                    );
+                }
+            }
+            self.origins.insert(x.range, x);
+        }
+
+        self.text.replace_range(begin..end, text);
+        self.positions = OnceLock::new();
+        Some(begin..end)
+    }
+
+    /// Returns the offset in the preprocessed text where `pos` of the source file `path` is copied.
+    ///
+    /// The first copy is returned if the file is included several times.
+    pub fn position<T: AsRef<Path>>(&self, path: T, pos: usize) -> Option<usize> {
+        let positions = self
+            .positions
+            .get_or_init(|| Box::new(self.build_positions()));
+        let (range, begin) = positions
+            .get(path.as_ref())?
+            .get_key_value(&Range::new(pos, pos + 1))?;
+        Some(begin + pos - range.begin)
+    }
+
+    fn build_positions(&self) -> Positions {
+        let mut ret = Positions::new();
+        for x in self.origins.values() {
+            let (path, range) = match x.origin {
+                Some((ref path, ref range))
+                    if range.end - range.begin == x.range.end - x.range.begin =>
+                {
+                    (path, range)
+                }
+                _ => continue,
+            };
+
+            // Add the parts of `range` which aren't copied before
+            let ranges = ret.entry(path.clone()).or_default();
+            let mut pos = range.begin;
+            while pos < range.end {
+                let key = Range::new(pos, pos + 1);
+                if let Some((copied, _)) = ranges.get_key_value(&key) {
+                    pos = copied.end;
+                    continue;
+                }
+                let end = ranges
+                    .range(key..)
+                    .next()
+                    .map_or(range.end, |(x, _)| x.begin.min(range.end));
+                ranges.insert(Range::new(pos, end), x.range.begin + pos - range.begin);
+                pos = end;
+            }
+        }
+        ret
+    }
+
+    /// Returns the range of the source file copied verbatim to `range` of the preprocessed text.
+    ///
+    /// An empty range is mapped to the position after the preceding character if the position
+    /// itself has no origin. `None` is returned if the range isn't copied verbatim from one file.
+    pub fn origin_range(
+        &self,
+        range: std::ops::Range<usize>,
+    ) -> Option<(&PathBuf, std::ops::Range<usize>)> {
+        let (path, begin) = match self.origin(range.start) {
+            Some(x) => x,
+            None if range.is_empty() && range.start > 0 => {
+                let (path, pos) = self.origin(range.start - 1)?;
+                (path, pos + 1)
+            }
+            None => return None,
+        };
+
+        let mut pos = range.start;
+        while pos < range.end {
+            let x = self.origins.get(&Range::new(pos, pos + 1))?;
+            let (origin_path, origin_range) = x.origin.as_ref()?;
+            if origin_path != path
+                || origin_range.end - origin_range.begin != x.range.end - x.range.begin
+                || origin_range.begin + pos - x.range.begin != begin + pos - range.start
+            {
+                return None;
+            }
+            pos = x.range.end;
+        }
+        Some((path, begin..begin + range.len()))
+    }
+
     pub fn origin(&self, pos: usize) -> Option<(&PathBuf, usize)> {
         let origin = self.origins.get(&Range::new(pos, pos + 1));
         if let Some(origin) = origin {
@@ -122,6 +351,26 @@ pub fn preprocess<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     include_paths: &[U],
     strip_comments: bool,
     ignore_include: bool,
+) -> Result<(PreprocessedText, Defines), Error> {
+    preprocess_with_control(
+        path,
+        pre_defines,
+        include_paths,
+        strip_comments,
+        ignore_include,
+        Default::default(),
+    )
+}
+
+/// `control` is applied to the parse of the file and included files.
+/// A cancelled preprocess fails with `Error::Cancelled`.
+pub fn preprocess_with_control<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    strip_comments: bool,
+    ignore_include: bool,
+    control: ParseControl,
 ) -> Result<(PreprocessedText, Defines), Error> {
     let f = File::open(path.as_ref()).map_err(|x| Error::File {
         source: x,
@@ -131,14 +380,17 @@ pub fn preprocess<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     let mut s = String::new();
     reader.read_to_string(&mut s)?;
 
-    preprocess_str(
+    preprocess_str_with_control(
         &s,
         path,
         pre_defines,
         include_paths,
         ignore_include,
-        strip_comments,
-        0,
+        PreprocessOptions {
+            strip_comments,
+            resolve_depth: 0,
+            control,
+        },
     )
 }
 
@@ -150,6 +402,73 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     ignore_include: bool,
     strip_comments: bool,
     resolve_depth: usize,
+) -> Result<(PreprocessedText, Defines), Error> {
+    preprocess_str_with_control(
+        s,
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        PreprocessOptions {
+            strip_comments,
+            resolve_depth,
+            ..Default::default()
+        },
+    )
+}
+
+/// Options of `preprocess_str_with_control`.
+#[derive(Clone, Copy, Default)]
+pub struct PreprocessOptions<'a> {
+    pub strip_comments: bool,
+    /// Nesting depth of macro usages where the text is expanded
+    pub resolve_depth: usize,
+    /// Applied to the parse of the text and included files.
+    /// A cancelled preprocess fails with `Error::Cancelled`.
+    pub control: ParseControl<'a>,
+}
+
+pub fn preprocess_str_with_control<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: PreprocessOptions,
+) -> Result<(PreprocessedText, Defines), Error> {
+    preprocess_str_inner(
+        s,
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options.strip_comments,
+        Expansion {
+            resolve_depth: options.resolve_depth,
+            macro_line: None,
+            control: options.control,
+        },
+    )
+}
+
+/// State of the macro expansion passed through the recursion of the preprocessor.
+#[derive(Clone, Copy)]
+struct Expansion<'a> {
+    resolve_depth: usize,
+    /// Line of the outermost macro usage while expanding a macro.
+    /// `__LINE__` is replaced by it instead of the line in the macro text.
+    macro_line: Option<usize>,
+    control: ParseControl<'a>,
+}
+
+fn preprocess_str_inner<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    strip_comments: bool,
+    expansion: Expansion,
 ) -> Result<(PreprocessedText, Defines), Error> {
     let mut skip = false;
     let mut skip_nodes = vec![];
@@ -162,8 +481,14 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
         defines.insert(k.clone(), (*v).clone());
     }
 
-    let span = Span::new_extra(&s, SpanInfo::default());
+    let state = ParserState::new();
+    let info = SpanInfo {
+        control: expansion.control,
+        ..SpanInfo::new(&state)
+    };
+    let span = Span::new_extra(&s, info);
     let (_, pp_text) = all_consuming(pp_parser)(span).map_err(|x| match x {
+        _ if state.cancelled() => Error::Cancelled,
         nom::Err::Incomplete(_) => Error::Parse(None),
         nom::Err::Error(e) => {
             if let Some(pos) = error_position(&e) {
@@ -182,6 +507,7 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     })?;
 
     let mut ret = PreprocessedText::new();
+    ret.path = Some(PathBuf::from(path.as_ref()));
 
     for n in pp_text.into_iter().event() {
         match n.clone() {
@@ -268,9 +594,8 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
             }
             NodeEvent::Enter(RefNode::IfdefDirective(x)) if !skip => {
                 let (_, _, ref ifid, ref ifbody, ref elsif, ref elsebody, _, _) = x.nodes;
-                let ifid = identifier(ifid.into(), &s).unwrap();
                 let mut hit = false;
-                if defines.contains_key(&ifid) {
+                if ifdef_condition(ifid, &s, &defines) {
                     hit = true;
                 } else {
                     skip_nodes.push(ifbody.into());
@@ -278,10 +603,9 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
 
                 for x in elsif {
                     let (_, _, ref elsifid, ref elsifbody) = x;
-                    let elsifid = identifier(elsifid.into(), &s).unwrap();
                     if hit {
                         skip_nodes.push(elsifbody.into());
-                    } else if defines.contains_key(&elsifid) {
+                    } else if ifdef_condition(elsifid, &s, &defines) {
                         hit = true;
                     } else {
                         skip_nodes.push(elsifbody.into());
@@ -309,9 +633,8 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
             }
             NodeEvent::Enter(RefNode::IfndefDirective(x)) if !skip => {
                 let (_, _, ref ifid, ref ifbody, ref elsif, ref elsebody, _, _) = x.nodes;
-                let ifid = identifier(ifid.into(), &s).unwrap();
                 let mut hit = false;
-                if !defines.contains_key(&ifid) {
+                if !ifdef_condition(ifid, &s, &defines) {
                     hit = true;
                 } else {
                     skip_nodes.push(ifbody.into());
@@ -319,10 +642,9 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
 
                 for x in elsif {
                     let (_, _, ref elsifid, ref elsifbody) = x;
-                    let elsifid = identifier(elsifid.into(), &s).unwrap();
                     if hit {
                         skip_nodes.push(elsifbody.into());
-                    } else if defines.contains_key(&elsifid) {
+                    } else if ifdef_condition(elsifid, &s, &defines) {
                         hit = true;
                     } else {
                         skip_nodes.push(elsifbody.into());
@@ -415,7 +737,10 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                             &defines,
                             include_paths,
                             strip_comments,
-                            resolve_depth + 1,
+                            Expansion {
+                                resolve_depth: expansion.resolve_depth + 1,
+                                ..expansion
+                            },
                         )? {
                             let p = p.trim().trim_matches('"');
                             PathBuf::from(p)
@@ -433,12 +758,20 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                         }
                     }
                 }
-                let (include, new_defines) =
-                    preprocess(path, &defines, include_paths, strip_comments, false).map_err(
-                        |x| Error::Include {
-                            source: Box::new(x),
-                        },
-                    )?;
+                let (include, new_defines) = preprocess_with_control(
+                    path,
+                    &defines,
+                    include_paths,
+                    strip_comments,
+                    false,
+                    expansion.control,
+                )
+                .map_err(|x| match x {
+                    Error::Cancelled => Error::Cancelled,
+                    x => Error::Include {
+                        source: Box::new(x),
+                    },
+                })?;
                 defines = new_defines;
                 ret.merge(include);
             }
@@ -450,7 +783,10 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                     &defines,
                     include_paths,
                     strip_comments,
-                    resolve_depth + 1,
+                    Expansion {
+                        resolve_depth: expansion.resolve_depth + 1,
+                        ..expansion
+                    },
                 )? {
                     ret.push(&text, origin);
                     defines = new_defines;
@@ -469,7 +805,8 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                         None,
                     );
                 } else if x.starts_with("__LINE__") {
-                    ret.push::<PathBuf>(&x.replace("__LINE__", &format!("{}", locate.line)), None);
+                    let line = expansion.macro_line.unwrap_or(locate.line as usize);
+                    ret.push::<PathBuf>(&x.replace("__LINE__", &format!("{}", line)), None);
                 }
             }
             _ => (),
@@ -496,6 +833,35 @@ fn identifier(node: RefNode, s: &str) -> Option<String> {
     None
 }
 
+fn ifdef_condition(node: &IfdefMacroExpression, s: &str, defines: &Defines) -> bool {
+    match node {
+        IfdefMacroExpression::TextMacroIdentifier(x) => {
+            let id = identifier((&**x).into(), s).unwrap();
+            defines.contains_key(&id)
+        }
+        IfdefMacroExpression::Paren(x) => {
+            let (ref x,) = x.nodes;
+            let (_, ref x, _) = x.nodes;
+            ifdef_condition(x, s, defines)
+        }
+        IfdefMacroExpression::Not(x) => {
+            let (_, ref x) = x.nodes;
+            !ifdef_condition(x, s, defines)
+        }
+        IfdefMacroExpression::Binary(x) => {
+            let (ref a, ref op, ref b) = x.nodes;
+            let a = ifdef_condition(a, s, defines);
+            let b = ifdef_condition(b, s, defines);
+            match op.nodes.0.str(s) {
+                "&&" => a && b,
+                "||" => a || b,
+                "->" => !a || b,
+                _ => a == b,
+            }
+        }
+    }
+}
+
 fn split_text(s: &str) -> Vec<String> {
     let mut is_string = false;
     let mut is_ident = false;
@@ -544,15 +910,64 @@ fn resolve_text_macro_usage<T: AsRef<Path>, U: AsRef<Path>>(
     defines: &Defines,
     include_paths: &[U],
     strip_comments: bool,
-    resolve_depth: usize,
+    expansion: Expansion,
 ) -> Result<Option<(String, Option<(PathBuf, Range)>, Defines)>, Error> {
-    let (_, ref name, ref args) = x.nodes;
+    let (_, ref name, _) = x.nodes;
     let id = identifier((&name.nodes.0).into(), &s).unwrap();
 
-    if resolve_depth > RECURSIVE_LIMIT {
+    if expansion.resolve_depth > RECURSIVE_LIMIT {
         return Err(Error::ExceedRecursiveLimit);
     }
 
+    let define = defines.get(&id);
+    if let Some(Some(define)) = define {
+        // The replacement is made by another function to keep the frame of this recursion small
+        if let Some(replaced) = replace_text_macro_usage(x, s, define)? {
+            let line = match expansion.macro_line {
+                Some(x) => x,
+                None => {
+                    let locate: Locate = x.try_into().unwrap();
+                    locate.line as usize
+                }
+            };
+            let (replaced, new_defines) = preprocess_str_inner(
+                &replaced,
+                path.as_ref(),
+                &defines,
+                include_paths,
+                false,
+                strip_comments,
+                Expansion {
+                    macro_line: Some(line),
+                    // The progress is reported only for files
+                    control: ParseControl {
+                        progress: None,
+                        ..expansion.control
+                    },
+                    ..expansion
+                },
+            )?;
+            Ok(Some((
+                String::from(replaced.text()),
+                define.text.as_ref().unwrap().origin.clone(),
+                new_defines,
+            )))
+        } else {
+            Ok(None)
+        }
+    } else if define.is_some() {
+        Ok(None)
+    } else {
+        Err(Error::DefineNotFound(id))
+    }
+}
+
+fn replace_text_macro_usage(
+    x: &TextMacroUsage,
+    s: &str,
+    define: &Define,
+) -> Result<Option<String>, Error> {
+    let (_, _, ref args) = x.nodes;
     let mut actual_args = Vec::new();
     let no_args = args.is_none();
     if let Some(args) = args {
@@ -569,77 +984,57 @@ fn resolve_text_macro_usage<T: AsRef<Path>, U: AsRef<Path>>(
         }
     }
 
-    let define = defines.get(&id);
-    if let Some(Some(define)) = define {
-        let mut arg_map = HashMap::new();
+    let mut arg_map = HashMap::new();
 
-        if !define.arguments.is_empty() && no_args {
-            return Err(Error::DefineNoArgs);
-        }
+    if !define.arguments.is_empty() && no_args {
+        return Err(Error::DefineNoArgs);
+    }
 
-        for (i, (arg, default)) in define.arguments.iter().enumerate() {
-            let value = match actual_args.get(i) {
-                Some(Some(actual_arg)) => *actual_arg,
-                Some(None) => {
-                    if let Some(default) = default {
-                        default
-                    } else {
-                        ""
-                    }
-                }
-                None => {
-                    if let Some(default) = default {
-                        default
-                    } else {
-                        return Err(Error::DefineArgNotFound(String::from(arg)));
-                    }
+    for (i, (arg, default)) in define.arguments.iter().enumerate() {
+        let value = match actual_args.get(i) {
+            Some(Some(actual_arg)) => *actual_arg,
+            Some(None) => {
+                if let Some(default) = default {
+                    default
+                } else {
+                    ""
                 }
-            };
-            arg_map.insert(String::from(arg), value);
-        }
-
-        if let Some(ref text) = define.text {
-            let mut replaced = String::from("");
-            for text in split_text(&text.text) {
-                if let Some(value) = arg_map.get(&text) {
-                    replaced.push_str(*value);
+            }
+            None => {
+                if let Some(default) = default {
+                    default
                 } else {
-                    replaced.push_str(
-                        &text
-                            .replace("``", "")
-                            .replace("`\\`\"", "\\\"")
-                            .replace("`\"", "\"")
-                            .replace("\\\n", "\n")
-                            .replace("\\\r\n", "\r\n")
-                            .replace("\\\r", "\r"),
-                    );
+                    return Err(Error::DefineArgNotFound(String::from(arg)));
                 }
             }
-            // separator is required
-            replaced.push_str(" ");
-            // remove leading whitespace
-            replaced = String::from(replaced.trim_start());
-            let (replaced, new_defines) = preprocess_str(
-                &replaced,
-                path.as_ref(),
-                &defines,
-                include_paths,
-                false,
-                strip_comments,
-                resolve_depth,
-            )?;
-            Ok(Some((
-                String::from(replaced.text()),
-                text.origin.clone(),
-                new_defines,
-            )))
-        } else {
-            Ok(None)
+        };
+        arg_map.insert(String::from(arg), value);
+    }
+
+    if let Some(ref text) = define.text {
+        let mut replaced = String::from("");
+        for text in split_text(&text.text) {
+            if let Some(value) = arg_map.get(&text) {
+                replaced.push_str(*value);
+            } else {
+                replaced.push_str(
+                    &text
+                        .replace("``", "")
+                        .replace("`\\`\"", "\\\"")
+                        .replace("`\"", "\"")
+                        .replace("\\\n", "\n")
+                        .replace("\\\r\n", "\r\n")
+                        .replace("\\\r", "\r"),
+                );
+            }
         }
-    } else if define.is_some() {
-        Ok(None)
+        // separator is required
+        replaced.push_str(" ");
+        // remove leading whitespace
+        replaced = String::from(replaced.trim_start());
+        Ok(Some(replaced))
     } else {
-        Err(Error::DefineNotFound(id))
+        Ok(None)
     }
 }
 
@@ -955,4 +1350,159 @@ endmodule
 "##
         );
     }
+
+    #[test]
+    fn test13() {
+        let (ret, _) = preprocess(
+            get_testcase("test13.sv"),
+            &HashMap::new(),
+            &[] as &[String],
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            ret.text(),
+            r##"
+
+module a;
+initial $display(5 );
+wire x;
+endmodule
+"##
+        );
+    }
+
+    #[test]
+    fn test14() {
+        let include_paths = [get_testcase("")];
+        let (ret, _) = preprocess(
+            get_testcase("test14.sv"),
+            &HashMap::new(),
+            &include_paths,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            ret.text(),
+            format!(
+                " \n \nmodule a;\ninitial\n$display(\"{}\", 4); \nendmodule\n",
+                get_testcase("test14.sv")
+            )
+        );
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let path = get_testcase("test1.sv");
+        let (mut ret, _) =
+            preprocess(&path, &HashMap::new(), &[] as &[String], true, false).unwrap();
+        let pos = ret.text().find("and a1").unwrap();
+        let (_, origin) = ret.origin(pos).unwrap();
+
+        assert_eq!(ret.apply_edit(&path, 44..45, "cc"), Some(44..45));
+        assert_eq!(
+            ret.text(),
+            r##"module and_op (a, b, c);
+output a;
+input b, cc;
+
+and a1 (a,b,c);
+endmodule
+"##
+        );
+        assert_eq!(ret.origin(pos + 1).unwrap().1, origin + 1);
+
+        // `ifdef directive isn't copied verbatim
+        assert_eq!(ret.apply_edit(&path, 56..57, "x"), None);
+    }
+
+    #[test]
+    fn test_origin_range() {
+        let path = get_testcase("test1.sv");
+        let (ret, _) = preprocess(&path, &HashMap::new(), &[] as &[String], true, false).unwrap();
+        let source = std::fs::read_to_string(&path).unwrap();
+
+        let pos = ret.text().find("and a1").unwrap();
+        let (origin_path, range) = ret.origin_range(pos..pos + 6).unwrap();
+        assert_eq!(origin_path, &PathBuf::from(&path));
+        assert_eq!(&source[range], "and a1");
+
+        let (_, range) = ret.origin_range(pos..pos).unwrap();
+        assert_eq!(range.start, source.find("and a1").unwrap());
+
+        // `ifdef directive between them isn't copied verbatim
+        let begin = ret.text().find("input").unwrap();
+        assert_eq!(ret.origin_range(begin..pos + 6), None);
+
+        assert_eq!(ret.position(&path, range.start), Some(pos));
+    }
+
+    #[test]
+    fn test_file_in_macro() {
+        // `__FILE__` and `__LINE__` in a macro of an included file are those of the usage
+        let text = "`include \"test14.svh\"\n\n`HERE\n";
+        let (ret, _) = preprocess_str(
+            text,
+            "a.sv",
+            &HashMap::new(),
+            &[get_testcase("")],
+            false,
+            false,
+            0,
+        )
+        .unwrap();
+        assert_eq!(ret.text().trim(), "$display(\"a.sv\", 3);");
+    }
+
+    #[test]
+    fn test_position() {
+        let text = "module a;\n`include \"test2.svh\"\n`include \"test2.svh\"\nendmodule\n";
+        let (ret, _) = preprocess_str(
+            text,
+            "a.sv",
+            &HashMap::new(),
+            &[get_testcase("")],
+            false,
+            false,
+            0,
+        )
+        .unwrap();
+        let header = get_testcase("test2.svh");
+        let source = std::fs::read_to_string(&header).unwrap();
+
+        // The first copy of the included file
+        let pos = source.find("and a1").unwrap();
+        let first = ret.text().find("and a1").unwrap();
+        assert!(ret.text()[first + 1..].contains("and a1"));
+        assert_eq!(ret.position(&header, pos), Some(first));
+        assert_eq!(ret.position(&header, pos + 4), Some(first + 4));
+        assert_eq!(ret.position("a.sv", 0), Some(0));
+        let end = ret.text().rfind("endmodule").unwrap();
+        assert_eq!(
+            ret.position("a.sv", text.find("endmodule").unwrap()),
+            Some(end)
+        );
+
+        // Directives aren't copied
+        assert_eq!(ret.position(&header, source.find("`ifdef").unwrap()), None);
+        assert_eq!(ret.position("a.sv", text.find("`include").unwrap()), None);
+        assert_eq!(ret.position("b.sv", 0), None);
+        assert_eq!(ret.position("a.sv", text.len()), None);
+    }
+
+    #[test]
+    fn test_position_after_edit() {
+        let path = get_testcase("test1.sv");
+        let (mut ret, _) =
+            preprocess(&path, &HashMap::new(), &[] as &[String], true, false).unwrap();
+        let source = std::fs::read_to_string(&path).unwrap();
+        let pos = source.find("and a1").unwrap();
+        let before = ret.position(&path, pos).unwrap();
+
+        ret.apply_edit(&path, 44..45, "cc").unwrap();
+        assert_eq!(ret.position(&path, pos + 1), Some(before + 1));
+        assert_eq!(&ret.text()[before + 1..before + 7], "and a1");
+    }
 }
@@ -0,0 +1,12 @@
+`define A
+`define LINE `__LINE__
+module a;
+`ifdef (A && !B)
+initial $display(`LINE);
+`endif
+`ifdef (B || (A -> B))
+wire w;
+`elsif (!B <-> A)
+wire x;
+`endif
+endmodule
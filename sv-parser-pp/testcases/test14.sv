@@ -0,0 +1,5 @@
+`include "test14.svh"
+module a;
+initial
+`HERE
+endmodule
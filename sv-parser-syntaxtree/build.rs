@@ -15,7 +15,18 @@ static REF_NODE_FOOTER: &str = r##"
 }
 "##;
 
+static REF_NODE_MUT_HEADER: &str = r##"
+#[derive(Debug, PartialEq, RefNodeMut)]
+pub enum RefNodeMut<'a> {
+    Locate(&'a mut Locate),
+"##;
+
+static REF_NODE_MUT_FOOTER: &str = r##"
+}
+"##;
+
 static ANY_NODE_HEADER: &str = r##"
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, AnyNode)]
 pub enum AnyNode {
     Locate(Locate),
@@ -25,17 +36,24 @@ static ANY_NODE_FOOTER: &str = r##"
 }
 "##;
 
-static REF_NODE_DISPLAY_HEADER: &str = r##"
-impl<'a> std::fmt::Display for RefNode<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+static REF_NODE_KIND_HEADER: &str = r##"
+impl<'a> RefNode<'a> {
+    /// Type name of the node like `ModuleDeclaration`, which is also shown by `Display`.
+    pub fn kind(&self) -> &'static str {
         match self {
-            RefNode::Locate(_) => write!(f, "Locate"),
+            RefNode::Locate(_) => "Locate",
 "##;
 
-static REF_NODE_DISPLAY_FOOTER: &str = r##"
+static REF_NODE_KIND_FOOTER: &str = r##"
         }
     }
 }
+
+impl<'a> std::fmt::Display for RefNode<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.kind())
+    }
+}
 "##;
 
 static ANY_NODE_DISPLAY_HEADER: &str = r##"
@@ -51,15 +69,92 @@ static ANY_NODE_DISPLAY_FOOTER: &str = r##"
 }
 "##;
 
+static VISITOR_HEADER: &str = r##"
+/// Visitor of syntax trees with a method per node type.
+///
+/// Each `visit_*` method visits the children of the node by default.
+/// Overriding methods can call `visit_children` to continue into the children.
+pub trait Visitor<'a> {
+    fn visit(&mut self, node: RefNode<'a>) {
+        match node {
+            RefNode::Locate(x) => self.visit_locate(x),
+"##;
+
+static VISITOR_MIDDLE: &str = r##"
+        }
+    }
+
+    fn visit_children(&mut self, nodes: RefNodes<'a>) {
+        for x in nodes.0 {
+            self.visit(x);
+        }
+    }
+
+    fn visit_locate(&mut self, _x: &'a Locate) {}
+"##;
+
+static VISITOR_FOOTER: &str = r##"
+}
+"##;
+
+static VISITOR_MUT_HEADER: &str = r##"
+/// Mutable visitor of syntax trees with a method per node type.
+///
+/// Each `visit_*_mut` method visits the children of the node by default.
+/// Overriding methods can call `visit_children_mut` to continue into the children.
+pub trait VisitorMut {
+    fn visit_mut(&mut self, node: RefNodeMut) {
+        match node {
+            RefNodeMut::Locate(x) => self.visit_locate_mut(x),
+"##;
+
+static VISITOR_MUT_MIDDLE: &str = r##"
+        }
+    }
+
+    fn visit_children_mut(&mut self, nodes: RefNodesMut) {
+        for x in nodes.0 {
+            self.visit_mut(x);
+        }
+    }
+
+    fn visit_locate_mut(&mut self, _x: &mut Locate) {}
+"##;
+
+static VISITOR_MUT_FOOTER: &str = r##"
+}
+"##;
+
+/// Converts `ModuleDeclaration` to `module_declaration`.
+fn snake_case(name: &str) -> String {
+    let mut ret = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i != 0 {
+                ret.push('_');
+            }
+            ret.push(c.to_ascii_lowercase());
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let dest = Path::new(&out_dir).join("any_node.rs");
     let mut out = File::create(&dest).unwrap();
 
     let mut ref_node = String::from(REF_NODE_HEADER);
+    let mut ref_node_mut = String::from(REF_NODE_MUT_HEADER);
     let mut any_node = String::from(ANY_NODE_HEADER);
-    let mut ref_node_display = String::from(REF_NODE_DISPLAY_HEADER);
+    let mut ref_node_kind = String::from(REF_NODE_KIND_HEADER);
     let mut any_node_display = String::from(ANY_NODE_DISPLAY_HEADER);
+    let mut visitor = String::from(VISITOR_HEADER);
+    let mut visitor_methods = String::new();
+    let mut visitor_mut = String::from(VISITOR_MUT_HEADER);
+    let mut visitor_mut_methods = String::new();
 
     let re_node = Regex::new(r"#\[derive.*Node.*\]").unwrap();
 
@@ -74,15 +169,33 @@ fn main() {
                 if hit_node {
                     let name = line.split_whitespace().nth(2).unwrap().replace("<'a>", "");
                     ref_node = format!("{}    {}(&'a {}),\n", ref_node, name, name);
+                    ref_node_mut = format!("{}    {}(&'a mut {}),\n", ref_node_mut, name, name);
                     any_node = format!("{}    {}({}),\n", any_node, name, name);
-                    ref_node_display = format!(
-                        "{}            RefNode::{}(_) => write!(f, \"{}\"),\n",
-                        ref_node_display, name, name
+                    ref_node_kind = format!(
+                        "{}            RefNode::{}(_) => \"{}\",\n",
+                        ref_node_kind, name, name
                     );
                     any_node_display = format!(
                         "{}            AnyNode::{}(_) => write!(f, \"{}\"),\n",
                         any_node_display, name, name
                     );
+                    let method = snake_case(&name);
+                    visitor = format!(
+                        "{}            RefNode::{}(x) => self.visit_{}(x),\n",
+                        visitor, name, method
+                    );
+                    visitor_methods = format!(
+                        "{}\n    fn visit_{}(&mut self, x: &'a {}) {{\n        self.visit_children(x.next());\n    }}\n",
+                        visitor_methods, method, name
+                    );
+                    visitor_mut = format!(
+                        "{}            RefNodeMut::{}(x) => self.visit_{}_mut(x),\n",
+                        visitor_mut, name, method
+                    );
+                    visitor_mut_methods = format!(
+                        "{}\n    fn visit_{}_mut(&mut self, x: &mut {}) {{\n        self.visit_children_mut(x.next_mut());\n    }}\n",
+                        visitor_mut_methods, method, name
+                    );
                     hit_node = false;
                 }
                 if re_node.is_match(&line) {
@@ -93,11 +206,26 @@ fn main() {
     }
 
     ref_node = format!("{}{}\n", ref_node, REF_NODE_FOOTER);
+    ref_node_mut = format!("{}{}\n", ref_node_mut, REF_NODE_MUT_FOOTER);
     any_node = format!("{}{}\n", any_node, ANY_NODE_FOOTER);
-    ref_node_display = format!("{}{}\n", ref_node_display, REF_NODE_DISPLAY_FOOTER);
+    ref_node_kind = format!("{}{}\n", ref_node_kind, REF_NODE_KIND_FOOTER);
     any_node_display = format!("{}{}\n", any_node_display, ANY_NODE_DISPLAY_FOOTER);
     let _ = write!(out, "{}", ref_node);
+    let _ = write!(out, "{}", ref_node_mut);
     let _ = write!(out, "{}", any_node);
-    let _ = write!(out, "{}", ref_node_display);
+    let _ = write!(out, "{}", ref_node_kind);
     let _ = write!(out, "{}", any_node_display);
+
+    visitor = format!(
+        "{}{}{}{}\n",
+        visitor, VISITOR_MIDDLE, visitor_methods, VISITOR_FOOTER
+    );
+    visitor_mut = format!(
+        "{}{}{}{}\n",
+        visitor_mut, VISITOR_MUT_MIDDLE, visitor_mut_methods, VISITOR_MUT_FOOTER
+    );
+    let dest = Path::new(&out_dir).join("visitor.rs");
+    let mut out = File::create(&dest).unwrap();
+    let _ = write!(out, "{}", visitor);
+    let _ = write!(out, "{}", visitor_mut);
 }
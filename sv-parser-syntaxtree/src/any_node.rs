@@ -58,13 +58,11 @@ impl<'a> Iterator for EventIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let ret = self.next.0.pop();
-        if let Some(x) = ret.clone() {
-            if let NodeEvent::Enter(x) = x {
-                self.next.0.push(NodeEvent::Leave(x.clone()));
-                let mut x: NodeEvents = x.next().into();
-                x.0.reverse();
-                self.next.0.append(&mut x.0);
-            }
+        if let Some(NodeEvent::Enter(x)) = ret.clone() {
+            self.next.0.push(NodeEvent::Leave(x.clone()));
+            let mut x: NodeEvents = x.next().into();
+            x.0.reverse();
+            self.next.0.append(&mut x.0);
         }
         ret
     }
@@ -392,6 +390,54 @@ where
     }
 }
 
+impl<
+        'a,
+        T0: 'a,
+        T1: 'a,
+        T2: 'a,
+        T3: 'a,
+        T4: 'a,
+        T5: 'a,
+        T6: 'a,
+        T7: 'a,
+        T8: 'a,
+        T9: 'a,
+        T10: 'a,
+        T11: 'a,
+    > From<&'a (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)> for RefNodes<'a>
+where
+    &'a T0: Into<RefNodes<'a>>,
+    &'a T1: Into<RefNodes<'a>>,
+    &'a T2: Into<RefNodes<'a>>,
+    &'a T3: Into<RefNodes<'a>>,
+    &'a T4: Into<RefNodes<'a>>,
+    &'a T5: Into<RefNodes<'a>>,
+    &'a T6: Into<RefNodes<'a>>,
+    &'a T7: Into<RefNodes<'a>>,
+    &'a T8: Into<RefNodes<'a>>,
+    &'a T9: Into<RefNodes<'a>>,
+    &'a T10: Into<RefNodes<'a>>,
+    &'a T11: Into<RefNodes<'a>>,
+{
+    fn from(x: &'a (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7, t8, t9, t10, t11) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.append(&mut t8.into().0);
+        ret.append(&mut t9.into().0);
+        ret.append(&mut t10.into().0);
+        ret.append(&mut t11.into().0);
+        ret.into()
+    }
+}
+
 impl<'a, T> From<&'a Paren<T>> for RefNodes<'a>
 where
     &'a T: Into<RefNodes<'a>>,
@@ -482,3 +528,458 @@ where
         ret.into()
     }
 }
+
+// -----------------------------------------------------------------------------
+
+pub struct RefNodesMut<'a>(pub Vec<RefNodeMut<'a>>);
+
+impl<'a> RefNodeMut<'a> {
+    /// Visits this node and its descendants in depth-first order.
+    /// The descendants of a node are skipped if `f` returns `false`.
+    pub fn walk<F: FnMut(&mut RefNodeMut) -> bool>(self, f: &mut F) {
+        let mut node = self;
+        if f(&mut node) {
+            for x in node.next_mut().0 {
+                x.walk(f);
+            }
+        }
+    }
+}
+
+impl<'a> From<Vec<RefNodeMut<'a>>> for RefNodesMut<'a> {
+    fn from(x: Vec<RefNodeMut<'a>>) -> Self {
+        RefNodesMut(x)
+    }
+}
+
+impl<'a> From<&'a mut Locate> for RefNodesMut<'a> {
+    fn from(x: &'a mut Locate) -> Self {
+        vec![RefNodeMut::Locate(x)].into()
+    }
+}
+
+impl<'a, T: 'a> From<&'a mut Vec<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut Vec<T>) -> Self {
+        let mut ret = Vec::new();
+        for x in x {
+            ret.append(&mut x.into().0);
+        }
+        ret.into()
+    }
+}
+
+impl<'a, T: 'a> From<&'a mut Option<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut Option<T>) -> Self {
+        let mut ret = Vec::new();
+        if let Some(x) = x {
+            ret.append(&mut x.into().0);
+        }
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a> From<&'a mut (T0,)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0,)) -> Self {
+        let mut ret = Vec::new();
+        let (t0,) = x;
+        ret.append(&mut t0.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a> From<&'a mut (T0, T1)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a> From<&'a mut (T0, T1, T2)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a> From<&'a mut (T0, T1, T2, T3)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a, T4: 'a> From<&'a mut (T0, T1, T2, T3, T4)>
+    for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a, T4: 'a, T5: 'a> From<&'a mut (T0, T1, T2, T3, T4, T5)>
+    for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a, T4: 'a, T5: 'a, T6: 'a>
+    From<&'a mut (T0, T1, T2, T3, T4, T5, T6)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+    &'a mut T6: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5, T6)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a, T4: 'a, T5: 'a, T6: 'a, T7: 'a>
+    From<&'a mut (T0, T1, T2, T3, T4, T5, T6, T7)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+    &'a mut T6: Into<RefNodesMut<'a>>,
+    &'a mut T7: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5, T6, T7)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a, T4: 'a, T5: 'a, T6: 'a, T7: 'a, T8: 'a>
+    From<&'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+    &'a mut T6: Into<RefNodesMut<'a>>,
+    &'a mut T7: Into<RefNodesMut<'a>>,
+    &'a mut T8: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7, t8) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.append(&mut t8.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T0: 'a, T1: 'a, T2: 'a, T3: 'a, T4: 'a, T5: 'a, T6: 'a, T7: 'a, T8: 'a, T9: 'a>
+    From<&'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+    &'a mut T6: Into<RefNodesMut<'a>>,
+    &'a mut T7: Into<RefNodesMut<'a>>,
+    &'a mut T8: Into<RefNodesMut<'a>>,
+    &'a mut T9: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7, t8, t9) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.append(&mut t8.into().0);
+        ret.append(&mut t9.into().0);
+        ret.into()
+    }
+}
+
+impl<
+        'a,
+        T0: 'a,
+        T1: 'a,
+        T2: 'a,
+        T3: 'a,
+        T4: 'a,
+        T5: 'a,
+        T6: 'a,
+        T7: 'a,
+        T8: 'a,
+        T9: 'a,
+        T10: 'a,
+    > From<&'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+    &'a mut T6: Into<RefNodesMut<'a>>,
+    &'a mut T7: Into<RefNodesMut<'a>>,
+    &'a mut T8: Into<RefNodesMut<'a>>,
+    &'a mut T9: Into<RefNodesMut<'a>>,
+    &'a mut T10: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7, t8, t9, t10) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.append(&mut t8.into().0);
+        ret.append(&mut t9.into().0);
+        ret.append(&mut t10.into().0);
+        ret.into()
+    }
+}
+
+impl<
+        'a,
+        T0: 'a,
+        T1: 'a,
+        T2: 'a,
+        T3: 'a,
+        T4: 'a,
+        T5: 'a,
+        T6: 'a,
+        T7: 'a,
+        T8: 'a,
+        T9: 'a,
+        T10: 'a,
+        T11: 'a,
+    > From<&'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)> for RefNodesMut<'a>
+where
+    &'a mut T0: Into<RefNodesMut<'a>>,
+    &'a mut T1: Into<RefNodesMut<'a>>,
+    &'a mut T2: Into<RefNodesMut<'a>>,
+    &'a mut T3: Into<RefNodesMut<'a>>,
+    &'a mut T4: Into<RefNodesMut<'a>>,
+    &'a mut T5: Into<RefNodesMut<'a>>,
+    &'a mut T6: Into<RefNodesMut<'a>>,
+    &'a mut T7: Into<RefNodesMut<'a>>,
+    &'a mut T8: Into<RefNodesMut<'a>>,
+    &'a mut T9: Into<RefNodesMut<'a>>,
+    &'a mut T10: Into<RefNodesMut<'a>>,
+    &'a mut T11: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7, t8, t9, t10, t11) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.append(&mut t8.into().0);
+        ret.append(&mut t9.into().0);
+        ret.append(&mut t10.into().0);
+        ret.append(&mut t11.into().0);
+        ret.into()
+    }
+}
+
+impl<'a, T> From<&'a mut Paren<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut Paren<T>) -> Self {
+        let mut ret = Vec::new();
+        let (a, b, c) = &mut x.nodes;
+        let mut a: RefNodesMut<'a> = a.into();
+        let mut c: RefNodesMut<'a> = c.into();
+        ret.append(&mut a.0);
+        ret.append(&mut b.into().0);
+        ret.append(&mut c.0);
+        ret.into()
+    }
+}
+
+impl<'a, T> From<&'a mut Brace<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut Brace<T>) -> Self {
+        let mut ret = Vec::new();
+        let (a, b, c) = &mut x.nodes;
+        let mut a: RefNodesMut<'a> = a.into();
+        let mut c: RefNodesMut<'a> = c.into();
+        ret.append(&mut a.0);
+        ret.append(&mut b.into().0);
+        ret.append(&mut c.0);
+        ret.into()
+    }
+}
+
+impl<'a, T> From<&'a mut Bracket<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut Bracket<T>) -> Self {
+        let mut ret = Vec::new();
+        let (a, b, c) = &mut x.nodes;
+        let mut a: RefNodesMut<'a> = a.into();
+        let mut c: RefNodesMut<'a> = c.into();
+        ret.append(&mut a.0);
+        ret.append(&mut b.into().0);
+        ret.append(&mut c.0);
+        ret.into()
+    }
+}
+
+impl<'a, T> From<&'a mut ApostropheBrace<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut ApostropheBrace<T>) -> Self {
+        let mut ret = Vec::new();
+        let (a, b, c) = &mut x.nodes;
+        let mut a: RefNodesMut<'a> = a.into();
+        let mut c: RefNodesMut<'a> = c.into();
+        ret.append(&mut a.0);
+        ret.append(&mut b.into().0);
+        ret.append(&mut c.0);
+        ret.into()
+    }
+}
+
+impl<'a, T, U> From<&'a mut List<T, U>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+    &'a mut U: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut List<T, U>) -> Self {
+        let mut ret = Vec::new();
+        let (t, u) = &mut x.nodes;
+        let mut u: RefNodesMut<'a> = u.into();
+        ret.append(&mut t.into().0);
+        ret.append(&mut u.0);
+        ret.into()
+    }
+}
+
+impl<'a, T: 'a> From<&'a mut Box<T>> for RefNodesMut<'a>
+where
+    &'a mut T: Into<RefNodesMut<'a>>,
+{
+    fn from(x: &'a mut Box<T>) -> Self {
+        let mut ret = Vec::new();
+        let mut x: RefNodesMut<'a> = (&mut **x).into();
+        ret.append(&mut x.0);
+        ret.into()
+    }
+}
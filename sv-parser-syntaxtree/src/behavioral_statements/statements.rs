@@ -44,6 +44,7 @@ pub enum StatementItem {
     RandsequenceStatement(Box<RandsequenceStatement>),
     RandcaseStatement(Box<RandcaseStatement>),
     ExpectPropertyStatement(Box<ExpectPropertyStatement>),
+    TextMacroUsage(Box<(TextMacroUsage, Option<Symbol>)>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
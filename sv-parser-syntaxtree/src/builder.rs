@@ -0,0 +1,959 @@
+use crate::keywords::*;
+use crate::*;
+use std::cell::RefCell;
+
+// -----------------------------------------------------------------------------
+
+/// Keyword tables of all versions, whose keywords are escaped in names
+const KEYWORDS: &[&[&str]] = &[
+    KEYWORDS_1364_1995,
+    KEYWORDS_1364_2001,
+    KEYWORDS_1364_2001_NOCONFIG,
+    KEYWORDS_1364_2005,
+    KEYWORDS_1800_2005,
+    KEYWORDS_1800_2009,
+    KEYWORDS_1800_2012,
+    KEYWORDS_1800_2017,
+    KEYWORDS_1800_2023,
+];
+
+/// Builder of syntax trees without source text.
+///
+/// Nodes are constructed by the methods of the builder, and `finish` lays out the completed tree
+/// by `Printer` and returns it with its generated text.
+/// Operands are parenthesized where needed, so the text has the same meaning as the tree.
+///
+/// Names are given as strings. A name which isn't a simple identifier or is a reserved keyword
+/// of any version of IEEE 1364 or IEEE 1800 becomes an escaped identifier.
+#[derive(Clone, Debug, Default)]
+pub struct Builder {
+    text: RefCell<String>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder {
+            text: RefCell::new(String::new()),
+        }
+    }
+
+    /// Lays out `node` and returns it with the generated text which its tokens are located in.
+    pub fn finish<T>(self, mut node: T) -> (T, String)
+    where
+        for<'b> &'b T: Into<RefNode<'b>>,
+        for<'b> &'b mut T: Into<RefNodeMut<'b>>,
+    {
+        let (text, ranges) =
+            Printer::default().layout(vec![(&node).into()].into(), &self.text.borrow());
+
+        let mut i = 0;
+        let mut line = 1;
+        let mut pos = 0;
+        let node_mut: RefNodeMut = (&mut node).into();
+        node_mut.walk(&mut |x| {
+            let nodes = match x {
+                RefNodeMut::Symbol(x) => &mut x.nodes,
+                RefNodeMut::Keyword(x) => &mut x.nodes,
+                RefNodeMut::NonZeroUnsignedNumber(x) => &mut x.nodes,
+                RefNodeMut::UnsignedNumber(x) => &mut x.nodes,
+                RefNodeMut::BinaryValue(x) => &mut x.nodes,
+                RefNodeMut::OctalValue(x) => &mut x.nodes,
+                RefNodeMut::HexValue(x) => &mut x.nodes,
+                RefNodeMut::DecimalBase(x) => &mut x.nodes,
+                RefNodeMut::BinaryBase(x) => &mut x.nodes,
+                RefNodeMut::OctalBase(x) => &mut x.nodes,
+                RefNodeMut::HexBase(x) => &mut x.nodes,
+                RefNodeMut::XNumber(x) => &mut x.nodes,
+                RefNodeMut::ZNumber(x) => &mut x.nodes,
+                RefNodeMut::StringLiteral(x) => &mut x.nodes,
+                RefNodeMut::AngleBracketLiteral(x) => &mut x.nodes,
+                RefNodeMut::CIdentifier(x) => &mut x.nodes,
+                RefNodeMut::EscapedIdentifier(x) => &mut x.nodes,
+                RefNodeMut::SimpleIdentifier(x) => &mut x.nodes,
+                RefNodeMut::SystemTfIdentifier(x) => &mut x.nodes,
+                RefNodeMut::FilePathSpecNonLiteral(x) => &mut x.nodes,
+                RefNodeMut::WhiteSpace(_) => return false,
+                _ => return true,
+            };
+            let range = ranges[i].clone();
+            let next = ranges.get(i + 1).map_or(text.len(), |x| x.start);
+            line += text[pos..range.start].matches('\n').count() as u32;
+            pos = range.start;
+            nodes.0 = Locate {
+                offset: range.start,
+                line,
+                len: range.len(),
+            };
+            nodes.1 = if next > range.end {
+                let space = Locate {
+                    offset: range.end,
+                    line: line + text[range.clone()].matches('\n').count() as u32,
+                    len: next - range.end,
+                };
+                vec![WhiteSpace::Space(Box::new(space))]
+            } else {
+                vec![]
+            };
+            i += 1;
+            false
+        });
+        (node, text)
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn token(&self, s: &str) -> (Locate, Vec<WhiteSpace>) {
+        let mut text = self.text.borrow_mut();
+        let offset = text.len();
+        text.push_str(s);
+        let locate = Locate {
+            offset,
+            line: 1,
+            len: s.len(),
+        };
+        (locate, vec![])
+    }
+
+    pub fn keyword(&self, s: &str) -> Keyword {
+        Keyword {
+            nodes: self.token(s),
+        }
+    }
+
+    pub fn symbol(&self, s: &str) -> Symbol {
+        Symbol {
+            nodes: self.token(s),
+        }
+    }
+
+    /// Returns an identifier named `s`. `s` must be non-empty and contain no white spaces.
+    pub fn identifier(&self, s: &str) -> Identifier {
+        assert!(!s.is_empty(), "name must be non-empty");
+        assert!(
+            !s.contains(char::is_whitespace),
+            "name must contain no white spaces"
+        );
+        let mut chars = s.chars();
+        let simple = chars
+            .next()
+            .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+            && chars.all(|x| x.is_ascii_alphanumeric() || x == '_' || x == '$');
+        if simple && !KEYWORDS.iter().any(|x| x.contains(&s)) {
+            Identifier::SimpleIdentifier(Box::new(SimpleIdentifier {
+                nodes: self.token(s),
+            }))
+        } else {
+            Identifier::EscapedIdentifier(Box::new(EscapedIdentifier {
+                nodes: self.token(&format!("\\{}", s)),
+            }))
+        }
+    }
+
+    fn hierarchical_identifier(&self, path: &str) -> HierarchicalIdentifier {
+        let mut names: Vec<_> = path.split('.').collect();
+        let last = names.pop().unwrap();
+        let mut scopes = Vec::new();
+        for x in names {
+            let select = ConstantBitSelect { nodes: (vec![],) };
+            scopes.push((self.identifier(x), select, self.symbol(".")));
+        }
+        HierarchicalIdentifier {
+            nodes: (None, scopes, self.identifier(last)),
+        }
+    }
+
+    fn list<T>(&self, separator: &str, items: Vec<T>) -> Option<List<Symbol, T>> {
+        let mut items = items.into_iter();
+        let first = items.next()?;
+        let rest = items.map(|x| (self.symbol(separator), x)).collect();
+        Some(List {
+            nodes: (first, rest),
+        })
+    }
+
+    fn parens<T>(&self, x: T) -> Paren<T> {
+        Paren {
+            nodes: (self.symbol("("), x, self.symbol(")")),
+        }
+    }
+
+    // -------------------------------------------------------------------------
+
+    /// Returns a reference to the variable or net of the hierarchical `path` like `a.b`.
+    pub fn name(&self, path: &str) -> Expression {
+        let select = Select {
+            nodes: (None, BitSelect { nodes: (vec![],) }, None),
+        };
+        let x = PrimaryHierarchical {
+            nodes: (None, self.hierarchical_identifier(path), select),
+        };
+        Expression::Primary(Box::new(Primary::Hierarchical(Box::new(x))))
+    }
+
+    /// Returns an unsized decimal number.
+    pub fn number(&self, x: u64) -> Expression {
+        let x = self.number_literal(x);
+        Expression::Primary(Box::new(Primary::PrimaryLiteral(Box::new(x))))
+    }
+
+    fn number_literal(&self, x: u64) -> PrimaryLiteral {
+        let x = UnsignedNumber {
+            nodes: self.token(&x.to_string()),
+        };
+        let x = DecimalNumber::UnsignedNumber(Box::new(x));
+        let x = Number::IntegralNumber(Box::new(IntegralNumber::DecimalNumber(Box::new(x))));
+        PrimaryLiteral::Number(Box::new(x))
+    }
+
+    /// Returns a hexadecimal number of `width` bits like `8'hff`. `width` must be positive.
+    pub fn hex(&self, width: u32, x: u64) -> Expression {
+        assert!(width > 0, "width of a number must be positive");
+        let size = Size {
+            nodes: (NonZeroUnsignedNumber {
+                nodes: self.token(&width.to_string()),
+            },),
+        };
+        let base = HexBase {
+            nodes: self.token("'h"),
+        };
+        let value = HexValue {
+            nodes: self.token(&format!("{:x}", x)),
+        };
+        let x = HexNumber {
+            nodes: (Some(size), base, value),
+        };
+        let x = Number::IntegralNumber(Box::new(IntegralNumber::HexNumber(Box::new(x))));
+        let x = PrimaryLiteral::Number(Box::new(x));
+        Expression::Primary(Box::new(Primary::PrimaryLiteral(Box::new(x))))
+    }
+
+    /// Returns `(x)`.
+    pub fn paren(&self, x: Expression) -> Expression {
+        let x = self.parens(MintypmaxExpression::Expression(Box::new(x)));
+        let x = PrimaryMintypmaxExpression { nodes: (x,) };
+        Expression::Primary(Box::new(Primary::MintypmaxExpression(Box::new(x))))
+    }
+
+    /// Parenthesizes `x` unless it's a primary or unary expression.
+    fn operand(&self, x: Expression) -> Expression {
+        match x {
+            Expression::Primary(_) | Expression::Unary(_) => x,
+            _ => self.paren(x),
+        }
+    }
+
+    /// Returns a unary operation like `~x`.
+    pub fn unary(&self, op: UnaryOp, x: Expression) -> Expression {
+        let op = UnaryOperator {
+            nodes: (self.symbol(op.as_str()),),
+        };
+        let x = match x {
+            Expression::Primary(x) => *x,
+            x => match self.paren(x) {
+                Expression::Primary(x) => *x,
+                _ => unreachable!(),
+            },
+        };
+        Expression::Unary(Box::new(ExpressionUnary {
+            nodes: (op, vec![], x),
+        }))
+    }
+
+    /// Returns a binary operation like `x + y`.
+    pub fn binary(&self, x: Expression, op: BinaryOp, y: Expression) -> Expression {
+        let x = self.operand(x);
+        let op = BinaryOperator {
+            nodes: (self.symbol(op.as_str()),),
+        };
+        let y = self.operand(y);
+        Expression::Binary(Box::new(ExpressionBinary {
+            nodes: (x, op, vec![], y),
+        }))
+    }
+
+    /// Returns `cond ? x : y`.
+    pub fn conditional(&self, cond: Expression, x: Expression, y: Expression) -> Expression {
+        let cond = self.operand(cond);
+        let cond = self.cond_predicate(cond);
+        let question = self.symbol("?");
+        let x = self.operand(x);
+        let colon = self.symbol(":");
+        let y = self.operand(y);
+        Expression::ConditionalExpression(Box::new(ConditionalExpression {
+            nodes: (cond, question, vec![], x, colon, y),
+        }))
+    }
+
+    fn cond_predicate(&self, x: Expression) -> CondPredicate {
+        let x = ExpressionOrCondPattern::Expression(Box::new(x));
+        CondPredicate {
+            nodes: (self.list("&&&", vec![x]).unwrap(),),
+        }
+    }
+
+    // -------------------------------------------------------------------------
+
+    /// Returns a reference to the parameter `name` in a constant expression.
+    pub fn constant_name(&self, name: &str) -> ConstantExpression {
+        let x = PsParameterIdentifierScope {
+            nodes: (
+                None,
+                ParameterIdentifier {
+                    nodes: (self.identifier(name),),
+                },
+            ),
+        };
+        let select = ConstantSelect {
+            nodes: (None, ConstantBitSelect { nodes: (vec![],) }, None),
+        };
+        let x = ConstantPrimaryPsParameter {
+            nodes: (PsParameterIdentifier::Scope(Box::new(x)), select),
+        };
+        ConstantExpression::ConstantPrimary(Box::new(ConstantPrimary::PsParameter(Box::new(x))))
+    }
+
+    /// Returns an unsized decimal number in a constant expression.
+    pub fn constant_number(&self, x: u64) -> ConstantExpression {
+        let x = self.number_literal(x);
+        ConstantExpression::ConstantPrimary(Box::new(ConstantPrimary::PrimaryLiteral(Box::new(x))))
+    }
+
+    /// Returns a binary operation like `x - 1` in a constant expression.
+    pub fn constant_binary(
+        &self,
+        x: ConstantExpression,
+        op: BinaryOp,
+        y: ConstantExpression,
+    ) -> ConstantExpression {
+        let x = self.constant_operand(x);
+        let op = BinaryOperator {
+            nodes: (self.symbol(op.as_str()),),
+        };
+        let y = self.constant_operand(y);
+        ConstantExpression::Binary(Box::new(ConstantExpressionBinary {
+            nodes: (x, op, vec![], y),
+        }))
+    }
+
+    fn constant_operand(&self, x: ConstantExpression) -> ConstantExpression {
+        match x {
+            ConstantExpression::ConstantPrimary(_) | ConstantExpression::Unary(_) => x,
+            _ => {
+                let x = self.parens(ConstantMintypmaxExpression::Unary(Box::new(x)));
+                let x = ConstantPrimaryMintypmaxExpression { nodes: (x,) };
+                ConstantExpression::ConstantPrimary(Box::new(ConstantPrimary::MintypmaxExpression(
+                    Box::new(x),
+                )))
+            }
+        }
+    }
+
+    /// Returns `logic`, or `logic [msb:lsb]` if `range` is given.
+    pub fn logic(&self, range: Option<(ConstantExpression, ConstantExpression)>) -> DataType {
+        let logic = IntegerVectorType::Logic(Box::new(self.keyword("logic")));
+        let mut dimensions = Vec::new();
+        if let Some((msb, lsb)) = range {
+            let x = ConstantRange {
+                nodes: (msb, self.symbol(":"), lsb),
+            };
+            let x = Bracket {
+                nodes: (self.symbol("["), x, self.symbol("]")),
+            };
+            dimensions.push(PackedDimension::Range(Box::new(PackedDimensionRange {
+                nodes: (x,),
+            })));
+        }
+        DataType::Vector(Box::new(DataTypeVector {
+            nodes: (logic, None, dimensions),
+        }))
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn statement(item: StatementItem) -> Statement {
+        Statement {
+            nodes: (None, vec![], item),
+        }
+    }
+
+    fn variable_lvalue(&self, path: &str) -> VariableLvalue {
+        let x = HierarchicalVariableIdentifier {
+            nodes: (self.hierarchical_identifier(path),),
+        };
+        let select = Select {
+            nodes: (None, BitSelect { nodes: (vec![],) }, None),
+        };
+        VariableLvalue::Identifier(Box::new(VariableLvalueIdentifier {
+            nodes: (None, x, select),
+        }))
+    }
+
+    /// Returns a blocking assignment `lhs = rhs;`.
+    pub fn blocking(&self, lhs: &str, rhs: Expression) -> Statement {
+        let lhs = self.variable_lvalue(lhs);
+        let op = AssignmentOperator {
+            nodes: (self.symbol("="),),
+        };
+        let x = OperatorAssignment {
+            nodes: (lhs, op, rhs),
+        };
+        let x = BlockingAssignment::OperatorAssignment(Box::new(x));
+        let item = StatementItem::BlockingAssignment(Box::new((x, self.symbol(";"))));
+        Builder::statement(item)
+    }
+
+    /// Returns a nonblocking assignment `lhs <= rhs;`.
+    pub fn nonblocking(&self, lhs: &str, rhs: Expression) -> Statement {
+        let lhs = self.variable_lvalue(lhs);
+        let x = NonblockingAssignment {
+            nodes: (lhs, self.symbol("<="), None, rhs),
+        };
+        let item = StatementItem::NonblockingAssignment(Box::new((x, self.symbol(";"))));
+        Builder::statement(item)
+    }
+
+    /// Returns `begin ... end` of `statements`.
+    pub fn seq_block(&self, statements: Vec<Statement>) -> Statement {
+        let statements = statements
+            .into_iter()
+            .map(|x| StatementOrNull::Statement(Box::new(x)))
+            .collect();
+        let x = SeqBlock {
+            nodes: (
+                self.keyword("begin"),
+                None,
+                vec![],
+                statements,
+                self.keyword("end"),
+                None,
+            ),
+        };
+        Builder::statement(StatementItem::SeqBlock(Box::new(x)))
+    }
+
+    /// Returns `if (cond) then`, or `if (cond) then else otherwise` if `otherwise` is given.
+    ///
+    /// An `if` statement as `otherwise` continues the chain as `else if`.
+    pub fn if_else(
+        &self,
+        cond: Expression,
+        then: Statement,
+        otherwise: Option<Statement>,
+    ) -> Statement {
+        // `then` is enclosed by `begin ... end` so that `else` isn't taken by a nested `if`
+        let then = match then.nodes.2 {
+            StatementItem::ConditionalStatement(_) if otherwise.is_some() => {
+                self.seq_block(vec![then])
+            }
+            _ => then,
+        };
+        let cond = self.cond_predicate(cond);
+        let cond = self.parens(cond);
+        let if_keyword = self.keyword("if");
+        let then = StatementOrNull::Statement(Box::new(then));
+
+        let mut chain = Vec::new();
+        let mut last = None;
+        if let Some(x) = otherwise {
+            let else_keyword = self.keyword("else");
+            match x.nodes {
+                (None, ref attrs, StatementItem::ConditionalStatement(ref y))
+                    if attrs.is_empty() && y.nodes.0.is_none() =>
+                {
+                    let y = match x.nodes.2 {
+                        StatementItem::ConditionalStatement(y) => *y,
+                        _ => unreachable!(),
+                    };
+                    let (_, y_if, y_cond, y_then, rest, y_last) = y.nodes;
+                    chain.push((else_keyword, y_if, y_cond, y_then));
+                    chain.extend(rest);
+                    last = y_last;
+                }
+                _ => last = Some((else_keyword, StatementOrNull::Statement(Box::new(x)))),
+            }
+        }
+
+        let x = ConditionalStatement {
+            nodes: (None, if_keyword, cond, then, chain, last),
+        };
+        Builder::statement(StatementItem::ConditionalStatement(Box::new(x)))
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn module_common_item(x: ModuleCommonItem) -> NonPortModuleItem {
+        let x = ModuleOrGenerateItemModuleItem { nodes: (vec![], x) };
+        let x = ModuleOrGenerateItem::ModuleItem(Box::new(x));
+        NonPortModuleItem::ModuleOrGenerateItem(Box::new(x))
+    }
+
+    /// Returns `always_comb statement`.
+    pub fn always_comb(&self, statement: Statement) -> NonPortModuleItem {
+        let keyword = AlwaysKeyword::AlwaysComb(Box::new(self.keyword("always_comb")));
+        let x = AlwaysConstruct {
+            nodes: (keyword, statement),
+        };
+        Builder::module_common_item(ModuleCommonItem::AlwaysConstruct(Box::new(x)))
+    }
+
+    /// Returns `always_ff @(posedge clock) statement`.
+    pub fn always_ff(&self, clock: &str, statement: Statement) -> NonPortModuleItem {
+        let keyword = AlwaysKeyword::AlwaysFf(Box::new(self.keyword("always_ff")));
+        let edge = EdgeIdentifier::Posedge(Box::new(self.keyword("posedge")));
+        let x = EventExpressionExpression {
+            nodes: (Some(edge), self.name(clock), None),
+        };
+        let x = self.parens(EventExpression::Expression(Box::new(x)));
+        let x = EventControlEventExpression {
+            nodes: (self.symbol("@"), x),
+        };
+        let x = ProceduralTimingControl::EventControl(Box::new(EventControl::EventExpression(
+            Box::new(x),
+        )));
+        let x = ProceduralTimingControlStatement {
+            nodes: (x, StatementOrNull::Statement(Box::new(statement))),
+        };
+        let statement =
+            Builder::statement(StatementItem::ProceduralTimingControlStatement(Box::new(x)));
+        let x = AlwaysConstruct {
+            nodes: (keyword, statement),
+        };
+        Builder::module_common_item(ModuleCommonItem::AlwaysConstruct(Box::new(x)))
+    }
+
+    /// Returns a declaration of the variables `names` of `data_type` like `logic a, b;`.
+    pub fn variable(&self, data_type: DataType, names: &[&str]) -> NonPortModuleItem {
+        let names = names
+            .iter()
+            .map(|x| {
+                let x = VariableDeclAssignmentVariable {
+                    nodes: (
+                        VariableIdentifier {
+                            nodes: (self.identifier(x),),
+                        },
+                        vec![],
+                        None,
+                    ),
+                };
+                VariableDeclAssignment::Variable(Box::new(x))
+            })
+            .collect();
+        let names = ListOfVariableDeclAssignments {
+            nodes: (self.list(",", names).expect("no variable names"),),
+        };
+        let x = DataDeclarationVariable {
+            nodes: (
+                None,
+                None,
+                None,
+                DataTypeOrImplicit::DataType(Box::new(data_type)),
+                names,
+                self.symbol(";"),
+            ),
+        };
+        let x = PackageOrGenerateItemDeclaration::DataDeclaration(Box::new(
+            DataDeclaration::Variable(Box::new(x)),
+        ));
+        let x = ModuleOrGenerateItemDeclaration::PackageOrGenerateItemDeclaration(Box::new(x));
+        Builder::module_common_item(ModuleCommonItem::ModuleOrGenerateItemDeclaration(Box::new(
+            x,
+        )))
+    }
+
+    /// Returns a continuous assignment `assign lhs = rhs;`.
+    pub fn assign(&self, lhs: &str, rhs: Expression) -> NonPortModuleItem {
+        let keyword = self.keyword("assign");
+        let lhs = if lhs.contains('.') {
+            let x = HierarchicalNetIdentifier {
+                nodes: (self.hierarchical_identifier(lhs),),
+            };
+            PsOrHierarchicalNetIdentifier::HierarchicalNetIdentifier(Box::new(x))
+        } else {
+            let x = PsOrHierarchicalNetIdentifierPackageScope {
+                nodes: (
+                    None,
+                    NetIdentifier {
+                        nodes: (self.identifier(lhs),),
+                    },
+                ),
+            };
+            PsOrHierarchicalNetIdentifier::PackageScope(Box::new(x))
+        };
+        let select = ConstantSelect {
+            nodes: (None, ConstantBitSelect { nodes: (vec![],) }, None),
+        };
+        let lhs = NetLvalue::Identifier(Box::new(NetLvalueIdentifier {
+            nodes: (lhs, select),
+        }));
+        let x = NetAssignment {
+            nodes: (lhs, self.symbol("="), rhs),
+        };
+        let x = ListOfNetAssignments {
+            nodes: (self.list(",", vec![x]).unwrap(),),
+        };
+        let x = ContinuousAssignNet {
+            nodes: (keyword, None, None, x, self.symbol(";")),
+        };
+        Builder::module_common_item(ModuleCommonItem::ContinuousAssign(Box::new(
+            ContinuousAssign::Net(Box::new(x)),
+        )))
+    }
+
+    /// Returns an instance `name` of `module` with named parameter and port connections.
+    pub fn instance(
+        &self,
+        module: &str,
+        parameters: Vec<(&str, Expression)>,
+        name: &str,
+        ports: Vec<(&str, Expression)>,
+    ) -> NonPortModuleItem {
+        let module = ModuleIdentifier {
+            nodes: (self.identifier(module),),
+        };
+        let parameters = if parameters.is_empty() {
+            None
+        } else {
+            let parameters = parameters
+                .into_iter()
+                .map(|(x, y)| {
+                    let dot = self.symbol(".");
+                    let x = ParameterIdentifier {
+                        nodes: (self.identifier(x),),
+                    };
+                    let y = ParamExpression::MintypmaxExpression(Box::new(
+                        MintypmaxExpression::Expression(Box::new(y)),
+                    ));
+                    NamedParameterAssignment {
+                        nodes: (dot, x, self.parens(Some(y))),
+                    }
+                })
+                .collect();
+            let x = ListOfParameterAssignmentsNamed {
+                nodes: (self.list(",", parameters).unwrap(),),
+            };
+            let x = ListOfParameterAssignments::Named(Box::new(x));
+            let x = self.parens(Some(x));
+            Some(ParameterValueAssignment {
+                nodes: (self.symbol("#"), x),
+            })
+        };
+
+        let name = NameOfInstance {
+            nodes: (
+                InstanceIdentifier {
+                    nodes: (self.identifier(name),),
+                },
+                vec![],
+            ),
+        };
+        let ports = ports
+            .into_iter()
+            .map(|(x, y)| {
+                let dot = self.symbol(".");
+                let x = PortIdentifier {
+                    nodes: (self.identifier(x),),
+                };
+                let x = NamedPortConnectionIdentifier {
+                    nodes: (vec![], dot, x, Some(self.parens(Some(y)))),
+                };
+                NamedPortConnection::Identifier(Box::new(x))
+            })
+            .collect();
+        let ports = self.list(",", ports).map(|x| {
+            ListOfPortConnections::Named(Box::new(ListOfPortConnectionsNamed { nodes: (x,) }))
+        });
+        let x = HierarchicalInstance {
+            nodes: (name, self.parens(ports)),
+        };
+        let x = ModuleInstantiation {
+            nodes: (
+                module,
+                parameters,
+                self.list(",", vec![x]).unwrap(),
+                self.symbol(";"),
+            ),
+        };
+        let x = ModuleOrGenerateItemModule { nodes: (vec![], x) };
+        let x = ModuleOrGenerateItem::Module(Box::new(x));
+        NonPortModuleItem::ModuleOrGenerateItem(Box::new(x))
+    }
+
+    // -------------------------------------------------------------------------
+
+    /// Returns a port declaration like `input logic a`.
+    ///
+    /// The data type is implicit if `data_type` is `None`.
+    pub fn port(
+        &self,
+        direction: Direction,
+        data_type: Option<DataType>,
+        name: &str,
+    ) -> AnsiPortDeclaration {
+        let keyword = Box::new(self.keyword(direction.as_str()));
+        let direction = match direction {
+            Direction::Input => PortDirection::Input(keyword),
+            Direction::Output => PortDirection::Output(keyword),
+            Direction::Inout => PortDirection::Inout(keyword),
+            Direction::Ref => PortDirection::Ref(keyword),
+        };
+        let data_type = match data_type {
+            Some(x) => DataTypeOrImplicit::DataType(Box::new(x)),
+            None => DataTypeOrImplicit::ImplicitDataType(Box::new(ImplicitDataType {
+                nodes: (None, vec![]),
+            })),
+        };
+        let x = NetPortType::DataType(Box::new(NetPortTypeDataType {
+            nodes: (None, data_type),
+        }));
+        let x = NetPortHeader {
+            nodes: (Some(direction), x),
+        };
+        let x = AnsiPortDeclarationNet {
+            nodes: (
+                Some(NetPortHeaderOrInterfacePortHeader::NetPortHeader(Box::new(
+                    x,
+                ))),
+                PortIdentifier {
+                    nodes: (self.identifier(name),),
+                },
+                vec![],
+                None,
+            ),
+        };
+        AnsiPortDeclaration::Net(Box::new(x))
+    }
+
+    /// Returns a parameter declaration `parameter name = value`.
+    pub fn parameter(&self, name: &str, value: ConstantExpression) -> ParameterPortDeclaration {
+        let keyword = self.keyword("parameter");
+        let value = ConstantParamExpression::ConstantMintypmaxExpression(Box::new(
+            ConstantMintypmaxExpression::Unary(Box::new(value)),
+        ));
+        let x = ParamAssignment {
+            nodes: (
+                ParameterIdentifier {
+                    nodes: (self.identifier(name),),
+                },
+                vec![],
+                Some((self.symbol("="), value)),
+            ),
+        };
+        let x = ListOfParamAssignments {
+            nodes: (self.list(",", vec![x]).unwrap(),),
+        };
+        let implicit = ImplicitDataType {
+            nodes: (None, vec![]),
+        };
+        let x = ParameterDeclarationParam {
+            nodes: (
+                keyword,
+                DataTypeOrImplicit::ImplicitDataType(Box::new(implicit)),
+                x,
+            ),
+        };
+        ParameterPortDeclaration::ParameterDeclaration(Box::new(ParameterDeclaration::Param(
+            Box::new(x),
+        )))
+    }
+
+    /// Returns an ANSI style module declaration.
+    pub fn module(
+        &self,
+        name: &str,
+        parameters: Vec<ParameterPortDeclaration>,
+        ports: Vec<AnsiPortDeclaration>,
+        items: Vec<NonPortModuleItem>,
+    ) -> ModuleDeclaration {
+        let keyword = ModuleKeyword::Module(Box::new(self.keyword("module")));
+        let name = ModuleIdentifier {
+            nodes: (self.identifier(name),),
+        };
+        let parameters = self.list(",", parameters).map(|x| {
+            let x = ParameterPortListDeclaration {
+                nodes: (self.symbol("#"), self.parens(x)),
+            };
+            ParameterPortList::Declaration(Box::new(x))
+        });
+        let ports = ports.into_iter().map(|x| (vec![], x)).collect();
+        let ports = self.list(",", ports);
+        let ports = ListOfPortDeclarations {
+            nodes: (self.parens(ports),),
+        };
+        let header = ModuleAnsiHeader {
+            nodes: (
+                vec![],
+                keyword,
+                None,
+                name,
+                vec![],
+                parameters,
+                Some(ports),
+                self.symbol(";"),
+            ),
+        };
+        let x = ModuleDeclarationAnsi {
+            nodes: (header, None, items, self.keyword("endmodule"), None),
+        };
+        ModuleDeclaration::Ansi(Box::new(x))
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// Direction of a port declared by `Builder::port`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    Output,
+    Inout,
+    Ref,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Input => "input",
+            Direction::Output => "output",
+            Direction::Inout => "inout",
+            Direction::Ref => "ref",
+        }
+    }
+}
+
+/// Operator of `Builder::unary`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `!`
+    Not,
+    /// `~`
+    BitNot,
+    /// `&`
+    And,
+    /// `~&`
+    Nand,
+    /// `|`
+    Or,
+    /// `~|`
+    Nor,
+    /// `^`
+    Xor,
+    /// `~^`
+    Xnor,
+}
+
+impl UnaryOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnaryOp::Plus => "+",
+            UnaryOp::Minus => "-",
+            UnaryOp::Not => "!",
+            UnaryOp::BitNot => "~",
+            UnaryOp::And => "&",
+            UnaryOp::Nand => "~&",
+            UnaryOp::Or => "|",
+            UnaryOp::Nor => "~|",
+            UnaryOp::Xor => "^",
+            UnaryOp::Xnor => "~^",
+        }
+    }
+}
+
+/// Operator of `Builder::binary` and `Builder::constant_binary`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Mod,
+    /// `**`
+    Pow,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `===`
+    CaseEq,
+    /// `!==`
+    CaseNe,
+    /// `==?`
+    WildcardEq,
+    /// `!=?`
+    WildcardNe,
+    /// `&&`
+    LogicalAnd,
+    /// `||`
+    LogicalOr,
+    /// `->`
+    Implication,
+    /// `<->`
+    Equivalence,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `&`
+    And,
+    /// `|`
+    Or,
+    /// `^`
+    Xor,
+    /// `~^`
+    Xnor,
+    /// `>>`
+    Shr,
+    /// `<<`
+    Shl,
+    /// `>>>`
+    AShr,
+    /// `<<<`
+    AShl,
+}
+
+impl BinaryOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Pow => "**",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::CaseEq => "===",
+            BinaryOp::CaseNe => "!==",
+            BinaryOp::WildcardEq => "==?",
+            BinaryOp::WildcardNe => "!=?",
+            BinaryOp::LogicalAnd => "&&",
+            BinaryOp::LogicalOr => "||",
+            BinaryOp::Implication => "->",
+            BinaryOp::Equivalence => "<->",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&",
+            BinaryOp::Or => "|",
+            BinaryOp::Xor => "^",
+            BinaryOp::Xnor => "~^",
+            BinaryOp::Shr => ">>",
+            BinaryOp::Shl => "<<",
+            BinaryOp::AShr => ">>>",
+            BinaryOp::AShl => "<<<",
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+/// Compact form of a syntax tree with node kinds instead of Rust types.
+///
+/// It's serialized as `{"kind": .., "span": [begin, end], "text": .., "children": [..]}`
+/// with the `serde` feature.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactNode {
+    /// Type name of the node like `ModuleDeclaration`. Tokens are `Locate`.
+    pub kind: String,
+    /// Range of the text of the node, or `None` if it has no text
+    pub span: Option<(usize, usize)>,
+    /// Text of a token
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub text: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub children: Vec<CompactNode>,
+}
+
+impl CompactNode {
+    /// Converts `node` whose tokens are located in `text`.
+    pub fn new(node: RefNode, text: &str) -> Self {
+        let mut stack: Vec<CompactNode> = Vec::new();
+        for event in node.into_iter().event() {
+            match event {
+                NodeEvent::Enter(x) => {
+                    let (span, token) = match x {
+                        RefNode::Locate(x) => (
+                            Some((x.offset, x.offset + x.len)),
+                            Some(String::from(x.str(text))),
+                        ),
+                        _ => (None, None),
+                    };
+                    stack.push(CompactNode {
+                        kind: x.to_string(),
+                        span,
+                        text: token,
+                        children: Vec::new(),
+                    });
+                }
+                NodeEvent::Leave(_) => {
+                    let x = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => {
+                            parent.span = match (parent.span, x.span) {
+                                (Some((a, b)), Some((c, d))) => Some((a.min(c), b.max(d))),
+                                (a, b) => a.or(b),
+                            };
+                            parent.children.push(x);
+                        }
+                        None => return x,
+                    }
+                }
+            }
+        }
+        unreachable!()
+    }
+}
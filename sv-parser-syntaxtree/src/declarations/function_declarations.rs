@@ -10,7 +10,40 @@ pub enum FunctionDataTypeOrImplicit {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct FunctionDeclaration {
-    pub nodes: (Keyword, Option<Lifetime>, FunctionBodyDeclaration),
+    pub nodes: (
+        Keyword,
+        Option<DynamicOverrideSpecifiers>,
+        Option<Lifetime>,
+        FunctionBodyDeclaration,
+    ),
+}
+
+impl FunctionDeclaration {
+    pub fn name(&self) -> &FunctionIdentifier {
+        match &self.nodes.3 {
+            FunctionBodyDeclaration::WithoutPort(x) => &x.nodes.2,
+            FunctionBodyDeclaration::WithPort(x) => &x.nodes.2,
+        }
+    }
+
+    pub fn return_type(&self) -> &FunctionDataTypeOrImplicit {
+        match &self.nodes.3 {
+            FunctionBodyDeclaration::WithoutPort(x) => &x.nodes.0,
+            FunctionBodyDeclaration::WithPort(x) => &x.nodes.0,
+        }
+    }
+
+    /// Returns the ports in the port list. Ports declared in the body are not included.
+    pub fn ports(&self) -> Vec<&TfPortItem> {
+        match &self.nodes.3 {
+            FunctionBodyDeclaration::WithoutPort(_) => Vec::new(),
+            FunctionBodyDeclaration::WithPort(x) => (x.nodes.3)
+                .nodes
+                .1
+                .as_ref()
+                .map_or_else(Vec::new, |x| x.nodes.0.contents()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -58,6 +91,7 @@ pub enum InterfaceIdentifierOrClassScope {
 pub struct FunctionPrototype {
     pub nodes: (
         Keyword,
+        Option<DynamicOverrideSpecifiers>,
         DataTypeOrVoid,
         FunctionIdentifier,
         Option<Paren<Option<TfPortList>>>,
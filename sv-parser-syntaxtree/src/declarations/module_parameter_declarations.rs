@@ -15,7 +15,12 @@ pub struct LocalParameterDeclarationParam {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct LocalParameterDeclarationType {
-    pub nodes: (Keyword, Keyword, ListOfTypeAssignments),
+    pub nodes: (
+        Keyword,
+        Keyword,
+        Option<TypeDeclarationKeyword>,
+        ListOfTypeAssignments,
+    ),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -31,7 +36,12 @@ pub struct ParameterDeclarationParam {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ParameterDeclarationType {
-    pub nodes: (Keyword, Keyword, ListOfTypeAssignments),
+    pub nodes: (
+        Keyword,
+        Keyword,
+        Option<TypeDeclarationKeyword>,
+        ListOfTypeAssignments,
+    ),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
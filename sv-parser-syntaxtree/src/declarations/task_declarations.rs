@@ -4,7 +4,33 @@ use crate::*;
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct TaskDeclaration {
-    pub nodes: (Keyword, Option<Lifetime>, TaskBodyDeclaration),
+    pub nodes: (
+        Keyword,
+        Option<DynamicOverrideSpecifiers>,
+        Option<Lifetime>,
+        TaskBodyDeclaration,
+    ),
+}
+
+impl TaskDeclaration {
+    pub fn name(&self) -> &TaskIdentifier {
+        match &self.nodes.3 {
+            TaskBodyDeclaration::WithoutPort(x) => &x.nodes.1,
+            TaskBodyDeclaration::WithPort(x) => &x.nodes.1,
+        }
+    }
+
+    /// Returns the ports in the port list. Ports declared in the body are not included.
+    pub fn ports(&self) -> Vec<&TfPortItem> {
+        match &self.nodes.3 {
+            TaskBodyDeclaration::WithoutPort(_) => Vec::new(),
+            TaskBodyDeclaration::WithPort(x) => (x.nodes.2)
+                .nodes
+                .1
+                .as_ref()
+                .map_or_else(Vec::new, |x| x.nodes.0.contents()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -66,6 +92,12 @@ pub struct TfPortItem {
     ),
 }
 
+impl TfPortItem {
+    pub fn name(&self) -> Option<&PortIdentifier> {
+        self.nodes.4.as_ref().map(|x| &x.0)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub enum TfPortDirection {
     PortDirection(Box<PortDirection>),
@@ -86,5 +118,10 @@ pub struct TfPortDeclaration {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct TaskPrototype {
-    pub nodes: (Keyword, TaskIdentifier, Option<Paren<Option<TfPortList>>>),
+    pub nodes: (
+        Keyword,
+        Option<DynamicOverrideSpecifiers>,
+        TaskIdentifier,
+        Option<Paren<Option<TfPortList>>>,
+    ),
 }
@@ -18,7 +18,7 @@ pub struct IncOrDecExpressionSuffix {
     pub nodes: (VariableLvalue, Vec<AttributeInstance>, IncOrDecOperator),
 }
 
-#[derive(Clone, Debug, PartialEq, Node)]
+#[derive(Debug, PartialEq, Node)]
 pub struct ConditionalExpression {
     pub nodes: (
         CondPredicate,
@@ -43,7 +43,7 @@ pub struct ConstantExpressionUnary {
     pub nodes: (UnaryOperator, Vec<AttributeInstance>, ConstantPrimary),
 }
 
-#[derive(Clone, Debug, PartialEq, Node)]
+#[derive(Debug, PartialEq, Node)]
 pub struct ConstantExpressionBinary {
     pub nodes: (
         ConstantExpression,
@@ -53,7 +53,7 @@ pub struct ConstantExpressionBinary {
     ),
 }
 
-#[derive(Clone, Debug, PartialEq, Node)]
+#[derive(Debug, PartialEq, Node)]
 pub struct ConstantExpressionTernary {
     pub nodes: (
         ConstantExpression,
@@ -140,7 +140,7 @@ pub struct ExpressionOperatorAssignment {
     pub nodes: (Paren<OperatorAssignment>,),
 }
 
-#[derive(Clone, Debug, PartialEq, Node)]
+#[derive(Debug, PartialEq, Node)]
 pub struct ExpressionBinary {
     pub nodes: (
         Expression,
@@ -253,3 +253,42 @@ pub struct IndexedRange {
 pub struct GenvarExpression {
     pub nodes: (ConstantExpression,),
 }
+
+// -----------------------------------------------------------------------------
+
+/// Implements `Clone` of a node nested to the right in its last field, such as `a + b + c`.
+///
+/// The chain is cloned by a loop instead of recursion, because the parser builds chains
+/// of any length and clones them by packrat memoization.
+macro_rules! impl_chain_clone {
+    ($x:ident, $y:ident::$z:ident, $($i:tt),*; $last:tt) => {
+        impl Clone for $x {
+            fn clone(&self) -> Self {
+                let mut heads = Vec::new();
+                let mut x = self;
+                let last = loop {
+                    heads.push(($(x.nodes.$i.clone(),)*));
+                    match &x.nodes.$last {
+                        $y::$z(y) => x = y.as_ref(),
+                        y => break y.clone(),
+                    }
+                };
+                let head = heads.pop().unwrap();
+                let mut ret = $x {
+                    nodes: ($(head.$i,)* last),
+                };
+                while let Some(head) = heads.pop() {
+                    ret = $x {
+                        nodes: ($(head.$i,)* $y::$z(Box::new(ret))),
+                    };
+                }
+                ret
+            }
+        }
+    };
+}
+
+impl_chain_clone!(ConditionalExpression, Expression::ConditionalExpression, 0, 1, 2, 3, 4; 5);
+impl_chain_clone!(ConstantExpressionBinary, ConstantExpression::Binary, 0, 1, 2; 3);
+impl_chain_clone!(ConstantExpressionTernary, ConstantExpression::Ternary, 0, 1, 2, 3, 4; 5);
+impl_chain_clone!(ExpressionBinary, Expression::Binary, 0, 1, 2; 3);
@@ -20,6 +20,7 @@ pub enum ConstantPrimary {
     TypeReference(Box<TypeReference>),
     Null(Box<Keyword>),
     Dollar(Box<Keyword>),
+    TextMacroUsage(Box<TextMacroUsage>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -98,6 +99,7 @@ pub enum Primary {
     This(Box<Keyword>),
     Dollar(Box<Keyword>),
     Null(Box<Keyword>),
+    TextMacroUsage(Box<TextMacroUsage>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -158,4 +158,5 @@ pub enum ArrayMethodName {
     And(Box<Keyword>),
     Or(Box<Keyword>),
     Xor(Box<Keyword>),
+    Map(Box<Keyword>),
 }
@@ -11,6 +11,7 @@ pub enum CompilerDirective {
     UndefineCompilerDirective(Box<UndefineCompilerDirective>),
     UndefineallCompilerDirective(Box<UndefineallCompilerDirective>),
     ConditionalCompilerDirective(Box<ConditionalCompilerDirective>),
+    ConditionalDirectiveLine(Box<ConditionalDirectiveLine>),
     TimescaleCompilerDirective(Box<TimescaleCompilerDirective>),
     DefaultNettypeCompilerDirective(Box<DefaultNettypeCompilerDirective>),
     UnconnectedDriveCompilerDirective(Box<UnconnectedDriveCompilerDirective>),
@@ -131,9 +132,9 @@ pub struct IfdefDirective {
     pub nodes: (
         Symbol,
         Keyword,
-        TextMacroIdentifier,
+        IfdefMacroExpression,
         IfdefGroupOfLines,
-        Vec<(Symbol, Keyword, TextMacroIdentifier, ElsifGroupOfLines)>,
+        Vec<(Symbol, Keyword, IfdefMacroExpression, ElsifGroupOfLines)>,
         Option<(Symbol, Keyword, ElseGroupOfLines)>,
         Symbol,
         Keyword,
@@ -145,15 +146,38 @@ pub struct IfndefDirective {
     pub nodes: (
         Symbol,
         Keyword,
-        TextMacroIdentifier,
+        IfdefMacroExpression,
         IfndefGroupOfLines,
-        Vec<(Symbol, Keyword, TextMacroIdentifier, ElsifGroupOfLines)>,
+        Vec<(Symbol, Keyword, IfdefMacroExpression, ElsifGroupOfLines)>,
         Option<(Symbol, Keyword, ElseGroupOfLines)>,
         Symbol,
         Keyword,
     ),
 }
 
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum IfdefMacroExpression {
+    TextMacroIdentifier(Box<TextMacroIdentifier>),
+    Paren(Box<IfdefMacroExpressionParen>),
+    Not(Box<IfdefMacroExpressionNot>),
+    Binary(Box<IfdefMacroExpressionBinary>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct IfdefMacroExpressionParen {
+    pub nodes: (Paren<IfdefMacroExpression>,),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct IfdefMacroExpressionNot {
+    pub nodes: (Symbol, IfdefMacroExpression),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct IfdefMacroExpressionBinary {
+    pub nodes: (IfdefMacroExpression, Symbol, IfdefMacroExpression),
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct IfdefGroupOfLines {
     pub nodes: (Vec<SourceDescription>,),
@@ -174,6 +198,24 @@ pub struct ElseGroupOfLines {
     pub nodes: (Vec<SourceDescription>,),
 }
 
+/// A line of a conditional compilation directive kept by `keep_macros`,
+/// whose branches are parsed as the following source text
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum ConditionalDirectiveLine {
+    Condition(Box<ConditionalDirectiveLineCondition>),
+    Keyword(Box<ConditionalDirectiveLineKeyword>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ConditionalDirectiveLineCondition {
+    pub nodes: (Symbol, Keyword, IfdefMacroExpression),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ConditionalDirectiveLineKeyword {
+    pub nodes: (Symbol, Keyword),
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub enum SourceDescription {
     Comment(Box<Comment>),
@@ -192,6 +192,20 @@ pub enum Identifier {
     EscapedIdentifier(Box<EscapedIdentifier>),
 }
 
+impl Identifier {
+    /// Returns the identifier token without its trailing white spaces.
+    pub fn locate(&self) -> &Locate {
+        match self {
+            Identifier::SimpleIdentifier(x) => &x.nodes.0,
+            Identifier::EscapedIdentifier(x) => &x.nodes.0,
+        }
+    }
+    /// Returns the text of the identifier token in `text`.
+    pub fn str<'a>(&self, text: &'a str) -> &'a str {
+        self.locate().str(text)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct IndexVariableIdentifier {
     pub nodes: (Identifier,),
@@ -527,3 +541,75 @@ pub enum PackageScopeOrClassScope {
     PackageScope(Box<PackageScope>),
     ClassScope(Box<ClassScope>),
 }
+
+// -----------------------------------------------------------------------------
+
+macro_rules! impl_locate {
+    ($($x:ident),* $(,)?) => {
+        $(
+            impl $x {
+                /// Returns the identifier token without its trailing white spaces.
+                pub fn locate(&self) -> &Locate {
+                    self.nodes.0.locate()
+                }
+
+                /// Returns the text of the identifier token in `text`.
+                pub fn str<'a>(&self, text: &'a str) -> &'a str {
+                    self.locate().str(text)
+                }
+            }
+        )*
+    };
+}
+
+impl_locate!(
+    ArrayIdentifier,
+    BlockIdentifier,
+    BinIdentifier,
+    CellIdentifier,
+    CheckerIdentifier,
+    ClassIdentifier,
+    ClockingIdentifier,
+    ConfigIdentifier,
+    ConstIdentifier,
+    ConstraintIdentifier,
+    CovergroupIdentifier,
+    CoverPointIdentifier,
+    CrossIdentifier,
+    EnumIdentifier,
+    FormalIdentifier,
+    FormalPortIdentifier,
+    FunctionIdentifier,
+    GenerateBlockIdentifier,
+    GenvarIdentifier,
+    IndexVariableIdentifier,
+    InterfaceIdentifier,
+    InterfaceInstanceIdentifier,
+    InoutPortIdentifier,
+    InputPortIdentifier,
+    InstanceIdentifier,
+    LibraryIdentifier,
+    MemberIdentifier,
+    MethodIdentifier,
+    ModportIdentifier,
+    ModuleIdentifier,
+    NetIdentifier,
+    NetTypeIdentifier,
+    OutputPortIdentifier,
+    PackageIdentifier,
+    ParameterIdentifier,
+    PortIdentifier,
+    ProductionIdentifier,
+    ProgramIdentifier,
+    PropertyIdentifier,
+    SequenceIdentifier,
+    SignalIdentifier,
+    SpecparamIdentifier,
+    TaskIdentifier,
+    TfIdentifier,
+    TerminalIdentifier,
+    TopmoduleIdentifier,
+    TypeIdentifier,
+    UdpIdentifier,
+    VariableIdentifier,
+);
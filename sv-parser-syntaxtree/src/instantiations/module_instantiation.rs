@@ -12,6 +12,16 @@ pub struct ModuleInstantiation {
     ),
 }
 
+impl ModuleInstantiation {
+    pub fn module_name(&self) -> &ModuleIdentifier {
+        &self.nodes.0
+    }
+
+    pub fn instances(&self) -> Vec<&HierarchicalInstance> {
+        self.nodes.2.contents()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ParameterValueAssignment {
     pub nodes: (Symbol, Paren<Option<ListOfParameterAssignments>>),
@@ -48,6 +58,12 @@ pub struct HierarchicalInstance {
     pub nodes: (NameOfInstance, Paren<Option<ListOfPortConnections>>),
 }
 
+impl HierarchicalInstance {
+    pub fn name(&self) -> &InstanceIdentifier {
+        &(self.nodes.0).nodes.0
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct NameOfInstance {
     pub nodes: (InstanceIdentifier, Vec<UnpackedDimension>),
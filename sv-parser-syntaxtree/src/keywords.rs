@@ -1,4 +1,4 @@
-pub(crate) const KEYWORDS_1364_1995: &[&str] = &[
+pub const KEYWORDS_1364_1995: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -103,7 +103,7 @@ pub(crate) const KEYWORDS_1364_1995: &[&str] = &[
     "xor",
 ];
 
-pub(crate) const KEYWORDS_1364_2001: &[&str] = &[
+pub const KEYWORDS_1364_2001: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -229,7 +229,7 @@ pub(crate) const KEYWORDS_1364_2001: &[&str] = &[
     "use",
 ];
 
-pub(crate) const KEYWORDS_1364_2001_NOCONFIG: &[&str] = &[
+pub const KEYWORDS_1364_2001_NOCONFIG: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -345,7 +345,7 @@ pub(crate) const KEYWORDS_1364_2001_NOCONFIG: &[&str] = &[
     "unsigned",
 ];
 
-pub(crate) const KEYWORDS_1364_2005: &[&str] = &[
+pub const KEYWORDS_1364_2005: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -472,7 +472,7 @@ pub(crate) const KEYWORDS_1364_2005: &[&str] = &[
     "uwire",
 ];
 
-pub(crate) const KEYWORDS_1800_2005: &[&str] = &[
+pub const KEYWORDS_1800_2005: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -696,7 +696,7 @@ pub(crate) const KEYWORDS_1800_2005: &[&str] = &[
     "within",
 ];
 
-pub(crate) const KEYWORDS_1800_2009: &[&str] = &[
+pub const KEYWORDS_1800_2009: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -943,7 +943,7 @@ pub(crate) const KEYWORDS_1800_2009: &[&str] = &[
     "weak",
 ];
 
-pub(crate) const KEYWORDS_1800_2012: &[&str] = &[
+pub const KEYWORDS_1800_2012: &[&str] = &[
     "always",
     "and",
     "assign",
@@ -1194,7 +1194,7 @@ pub(crate) const KEYWORDS_1800_2012: &[&str] = &[
     "soft",
 ];
 
-pub(crate) const KEYWORDS_1800_2017: &[&str] = &[
+pub const KEYWORDS_1800_2017: &[&str] = &[
     "accept_on",
     "alias",
     "always",
@@ -1445,7 +1445,16 @@ pub(crate) const KEYWORDS_1800_2017: &[&str] = &[
     "xor",
 ];
 
-pub(crate) const KEYWORDS_DIRECTIVE: &[&str] = &[
+// IEEE 1800-2023 reserves no new keywords
+pub const KEYWORDS_1800_2023: &[&str] = KEYWORDS_1800_2017;
+
+/// Operators and symbols of IEEE 1800 which are not in IEEE 1364
+pub const SYMBOLS_1800: &[&str] = &[
+    "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>=", "<<<=", ">>>=",
+    "==?", "!=?", "'{", "::", "->>", "##", "|->", "|=>", "#-#", "#=#", "<->", ".*",
+];
+
+pub const KEYWORDS_DIRECTIVE: &[&str] = &[
     "__FILE__",
     "__LINE__",
     "begin_keywords",
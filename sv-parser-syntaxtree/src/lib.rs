@@ -7,33 +7,49 @@
 
 pub mod any_node;
 pub mod behavioral_statements;
+pub mod builder;
+pub mod compact;
 pub mod declarations;
 pub mod expressions;
 pub mod general;
 pub mod instantiations;
+pub mod keywords;
 pub mod preprocessor;
 pub mod primitive_instances;
+pub mod printer;
+pub mod query;
 pub mod source_text;
 pub mod special_node;
 pub mod specify_section;
+pub mod tree_index;
 pub mod udp_declaration_and_instantiation;
+pub mod visitor;
 pub use any_node::*;
 pub use behavioral_statements::*;
+pub use builder::*;
+pub use compact::*;
 pub use declarations::*;
 pub use expressions::*;
 pub use general::*;
 pub use instantiations::*;
 pub use preprocessor::*;
 pub use primitive_instances::*;
+pub use printer::*;
+pub use query::*;
 pub use source_text::*;
 pub use special_node::*;
 pub use specify_section::*;
+pub use tree_index::*;
 pub use udp_declaration_and_instantiation::*;
+pub use visitor::*;
 
+#[cfg(feature = "serde")]
+pub(crate) use serde::{Deserialize, Serialize};
 pub(crate) use sv_parser_macros::*;
 
 // -----------------------------------------------------------------------------
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Locate {
     pub offset: usize,
@@ -42,7 +58,7 @@ pub struct Locate {
 }
 
 impl Locate {
-    pub fn str<'a, 'b>(&'a self, s: &'b str) -> &'b str {
+    pub fn str<'a>(&self, s: &'a str) -> &'a str {
         &s[self.offset..self.offset + self.len]
     }
 }
@@ -59,6 +75,16 @@ impl<'a> Node<'a> for Locate {
     }
 }
 
+pub trait NodeMut<'a> {
+    fn next_mut(&'a mut self) -> RefNodesMut<'a>;
+}
+
+impl<'a> NodeMut<'a> for Locate {
+    fn next_mut(&'a mut self) -> RefNodesMut<'a> {
+        vec![].into()
+    }
+}
+
 impl<'a> IntoIterator for &'a Locate {
     type Item = RefNode<'a>;
     type IntoIter = Iter<'a>;
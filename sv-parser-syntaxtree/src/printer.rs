@@ -0,0 +1,447 @@
+use crate::*;
+use std::ops::Range;
+
+// -----------------------------------------------------------------------------
+
+/// Printer of syntax trees with normalized white spaces and indentation.
+///
+/// Comments and compiler directives are kept, and other white spaces are replaced by the layout of the printer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Printer {
+    /// Number of spaces per indentation level
+    pub indent: usize,
+}
+
+impl std::default::Default for Printer {
+    fn default() -> Self {
+        Printer { indent: 2 }
+    }
+}
+
+impl Printer {
+    /// Prints `nodes` whose tokens are located in `text`.
+    pub fn print<'a, T: Into<RefNodes<'a>>>(&self, nodes: T, text: &str) -> String {
+        self.layout(nodes.into(), text).0
+    }
+
+    /// Returns the printed text and the range of each token in it in the order of the tokens.
+    pub(crate) fn layout(&self, nodes: RefNodes, text: &str) -> (String, Vec<Range<usize>>) {
+        let mut layout = Layout {
+            indent: self.indent,
+            text,
+            out: String::new(),
+            ranges: Vec::new(),
+            level: 0,
+            depth: 0,
+            brk: false,
+            blank: false,
+            label_ok: false,
+            labeled: false,
+            header: false,
+            case_depth: Vec::new(),
+            body: false,
+            bodies: Vec::new(),
+            prev: None,
+            prev_end: 0,
+        };
+
+        let mut parents: Vec<RefNode> = Vec::new();
+        let mut in_white_space = 0;
+        let mut number = None;
+        let mut numbers = 0;
+        for event in Iter::new(nodes).event() {
+            match event {
+                NodeEvent::Enter(RefNode::WhiteSpace(x)) => {
+                    if in_white_space == 0 {
+                        match x {
+                            WhiteSpace::Space(_) => (),
+                            WhiteSpace::Comment(x) => layout.comment(&x.nodes.0),
+                            WhiteSpace::CompilerDirective(_) => layout.directive(x.into()),
+                        }
+                    }
+                    in_white_space += 1;
+                }
+                NodeEvent::Leave(RefNode::WhiteSpace(_)) => in_white_space -= 1,
+                _ if in_white_space > 0 => (),
+                NodeEvent::Enter(RefNode::Locate(x)) => {
+                    let kind = match parents.last() {
+                        Some(RefNode::Keyword(_)) => Kind::Keyword,
+                        Some(RefNode::Symbol(_)) => Kind::Symbol,
+                        Some(RefNode::SimpleIdentifier(_))
+                        | Some(RefNode::SystemTfIdentifier(_))
+                        | Some(RefNode::CIdentifier(_)) => Kind::Identifier,
+                        Some(RefNode::EscapedIdentifier(_)) => Kind::Escaped,
+                        _ => Kind::Literal,
+                    };
+                    let parents = &parents[..parents.len().saturating_sub(1)];
+                    layout.token(x, kind, parents, number);
+                }
+                NodeEvent::Enter(x) => {
+                    match x {
+                        RefNode::Number(_) | RefNode::TimeLiteral(_) if number.is_none() => {
+                            numbers += 1;
+                            number = Some(numbers);
+                        }
+                        RefNode::ModuleDeclarationNonansi(_)
+                        | RefNode::ModuleDeclarationAnsi(_)
+                        | RefNode::ModuleDeclarationWildcard(_)
+                        | RefNode::InterfaceDeclarationNonansi(_)
+                        | RefNode::InterfaceDeclarationAnsi(_)
+                        | RefNode::InterfaceDeclarationWildcard(_)
+                        | RefNode::ProgramDeclarationNonansi(_)
+                        | RefNode::ProgramDeclarationAnsi(_)
+                        | RefNode::ProgramDeclarationWildcard(_)
+                        | RefNode::PackageDeclaration(_)
+                        | RefNode::ClassDeclaration(_)
+                        | RefNode::InterfaceClassDeclaration(_)
+                        | RefNode::FunctionBodyDeclarationWithoutPort(_)
+                        | RefNode::FunctionBodyDeclarationWithPort(_)
+                        | RefNode::TaskBodyDeclarationWithoutPort(_)
+                        | RefNode::TaskBodyDeclarationWithPort(_)
+                        | RefNode::CheckerDeclaration(_)
+                        | RefNode::ClockingDeclarationLocal(_)
+                        | RefNode::ClockingDeclarationGlobal(_)
+                        | RefNode::CovergroupDeclaration(_)
+                        | RefNode::PropertyDeclaration(_)
+                        | RefNode::SequenceDeclaration(_)
+                        | RefNode::UdpDeclarationAnsi(_)
+                        | RefNode::UdpDeclarationNonansi(_)
+                        | RefNode::ConfigDeclaration(_) => layout.header = true,
+                        RefNode::CaseStatementNormal(_)
+                        | RefNode::CaseStatementMatches(_)
+                        | RefNode::CaseStatementInside(_) => layout.case_depth.push(layout.depth),
+                        RefNode::AlwaysConstruct(_)
+                        | RefNode::InitialConstruct(_)
+                        | RefNode::FinalConstruct(_) => layout.body = true,
+                        RefNode::Statement(x) if layout.body => match x.nodes.2 {
+                            StatementItem::ProceduralTimingControlStatement(_) => (),
+                            StatementItem::SeqBlock(_) | StatementItem::ParBlock(_) => {
+                                layout.body = false;
+                            }
+                            _ => {
+                                layout.body = false;
+                                layout.bodies.push(parents.len());
+                                layout.level += 1;
+                                layout.brk = true;
+                            }
+                        },
+                        _ => (),
+                    }
+                    parents.push(x);
+                }
+                NodeEvent::Leave(RefNode::Locate(_)) => (),
+                NodeEvent::Leave(x) => {
+                    parents.pop();
+                    if layout.bodies.last() == Some(&parents.len()) {
+                        layout.bodies.pop();
+                        layout.level = layout.level.saturating_sub(1);
+                        layout.brk = true;
+                    }
+                    if let RefNode::AlwaysConstruct(_)
+                    | RefNode::InitialConstruct(_)
+                    | RefNode::FinalConstruct(_) = x
+                    {
+                        layout.body = false;
+                    }
+                    if let RefNode::Number(_) | RefNode::TimeLiteral(_) = x {
+                        let nested = parents
+                            .iter()
+                            .any(|x| matches!(x, RefNode::Number(_) | RefNode::TimeLiteral(_)));
+                        if !nested {
+                            number = None;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut out = layout.out;
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        (out, layout.ranges)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Keyword,
+    Symbol,
+    Identifier,
+    Escaped,
+    Literal,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    text: &'a str,
+    kind: Kind,
+    number: Option<usize>,
+    glue_before: bool,
+    glue_after: bool,
+}
+
+struct Layout<'a> {
+    indent: usize,
+    text: &'a str,
+    out: String,
+    ranges: Vec<Range<usize>>,
+    /// Indentation level
+    level: usize,
+    /// Nesting depth of parentheses, brackets and braces
+    depth: usize,
+    /// Whether a line break is required before the next token
+    brk: bool,
+    /// Whether the line break is a blank line
+    blank: bool,
+    /// Whether a `: label` can follow on the same line
+    label_ok: bool,
+    /// Whether the next token is a label
+    labeled: bool,
+    /// Whether the next `;` at depth 0 ends a header and opens a body
+    header: bool,
+    /// Depths of case statements whose items aren't opened yet
+    case_depth: Vec<usize>,
+    /// Whether the statement of a procedural block is expected
+    body: bool,
+    /// Nesting depths of indented statements of procedural blocks
+    bodies: Vec<usize>,
+    prev: Option<Token<'a>>,
+    /// End of the previous token in the source text
+    prev_end: usize,
+}
+
+impl<'a> Layout<'a> {
+    fn token(&mut self, x: &Locate, kind: Kind, parents: &[RefNode], number: Option<usize>) {
+        let text = x.str(self.text);
+        let parent = parents.last();
+        let grandparent = parents.len().checked_sub(2).map(|i| &parents[i]);
+
+        let range = match parent {
+            Some(RefNode::ConstantRange(_))
+            | Some(RefNode::IndexedRange(_))
+            | Some(RefNode::ConstantIndexedRange(_))
+            | Some(RefNode::ValueRangeBinary(_)) => kind == Kind::Symbol,
+            _ => false,
+        };
+        let case_item = match parent {
+            Some(RefNode::CaseItemNondefault(_))
+            | Some(RefNode::CaseItemDefault(_))
+            | Some(RefNode::CaseInsideItemNondefault(_))
+            | Some(RefNode::CasePatternItemNondefault(_)) => text == ":",
+            _ => false,
+        };
+        let vertical = kind == Kind::Symbol
+            && match parent {
+                Some(RefNode::ListOfPortDeclarations(_)) => matches!(text, "(" | ")" | ","),
+                Some(RefNode::HierarchicalInstance(_)) => matches!(text, "(" | ")"),
+                Some(RefNode::ListOfPortConnectionsNamed(_)) => text == ",",
+                _ => false,
+            };
+        let spaced_paren = matches!(
+            parent,
+            Some(RefNode::ListOfPortDeclarations(_))
+                | Some(RefNode::ListOfPorts(_))
+                | Some(RefNode::HierarchicalInstance(_))
+        );
+        let (prefix, suffix) = match (parent, grandparent) {
+            (Some(RefNode::UnaryOperator(_)), _)
+            | (Some(RefNode::IncOrDecOperator(_)), Some(RefNode::IncOrDecExpressionPrefix(_))) => {
+                (true, false)
+            }
+            (Some(RefNode::IncOrDecOperator(_)), Some(RefNode::IncOrDecExpressionSuffix(_))) => {
+                (false, true)
+            }
+            _ => (false, false),
+        };
+
+        let symbol = kind == Kind::Symbol;
+        let cur = Token {
+            text,
+            kind,
+            number,
+            glue_before: range
+                || case_item
+                || suffix
+                || symbol && matches!(text, ")" | "]" | "}" | "," | ";" | "::" | "'"),
+            glue_after: range
+                || prefix
+                || symbol
+                    && matches!(
+                        text,
+                        "(" | "[" | "{" | "'{" | "#" | "@" | "." | "::" | "'" | "##"
+                    ),
+        };
+        let opener = kind == Kind::Keyword
+            && matches!(
+                text,
+                "begin" | "fork" | "generate" | "specify" | "table" | "randcase"
+            );
+        let closer = cur.closer();
+
+        if closer {
+            self.level = self.level.saturating_sub(1);
+            self.brk = true;
+        }
+        if vertical && text == ")" {
+            self.level = self.level.saturating_sub(1);
+            self.brk = !matches!(self.prev, Some(x) if x.text == "(");
+        }
+        if kind == Kind::Keyword && text == "else" && self.prev.is_some_and(|x| x.closer()) {
+            self.brk = false;
+            self.blank = false;
+        }
+
+        let mut hold = false;
+        if self.brk {
+            if self.labeled {
+                hold = true;
+                self.labeled = false;
+            } else if self.label_ok && text == ":" {
+                hold = true;
+                self.labeled = true;
+            }
+        }
+        if self.brk && !hold {
+            self.newline(x.offset);
+        }
+
+        if self.at_line_start() {
+            self.out.push_str(&" ".repeat(self.level * self.indent));
+        } else if let Some(prev) = self.prev {
+            if space(&prev, &cur, spaced_paren) {
+                self.out.push(' ');
+            }
+        }
+        let begin = self.out.len();
+        self.out.push_str(text);
+        self.ranges.push(begin..self.out.len());
+        self.prev = Some(cur);
+        self.prev_end = x.offset + x.len;
+
+        if symbol {
+            match text {
+                "(" | "[" | "{" | "'{" => self.depth += 1,
+                ")" | "]" | "}" => self.depth = self.depth.saturating_sub(1),
+                _ => (),
+            }
+        }
+        if symbol && text == ")" && self.case_depth.last() == Some(&self.depth) {
+            self.case_depth.pop();
+            self.level += 1;
+            self.brk = true;
+        }
+        if symbol && text == ";" && self.depth == 0 {
+            self.brk = true;
+            if self.header {
+                self.header = false;
+                self.level += 1;
+            }
+        }
+        if vertical && text == "(" {
+            self.level += 1;
+            self.brk = true;
+        }
+        if vertical && text == "," {
+            self.brk = true;
+        }
+        if opener {
+            self.level += 1;
+            self.brk = true;
+        }
+        if closer {
+            self.brk = true;
+            self.blank = self.level == 0;
+        }
+        if !hold {
+            self.label_ok = opener || closer;
+        }
+    }
+
+    fn comment(&mut self, x: &Locate) {
+        let text = x.str(self.text);
+        let own_line = self.out.is_empty()
+            || self
+                .text
+                .get(self.prev_end..x.offset)
+                .is_some_and(|x| x.contains('\n'));
+        if own_line {
+            self.newline(x.offset);
+            self.out.push_str(&" ".repeat(self.level * self.indent));
+        } else if !self.at_line_start() {
+            self.out.push(' ');
+        }
+        self.out.push_str(text.trim_end());
+        self.prev_end = x.offset + x.len;
+        if own_line || text.starts_with("//") {
+            self.brk = true;
+        }
+    }
+
+    fn directive(&mut self, nodes: RefNodes) {
+        let mut range: Option<Range<usize>> = None;
+        for x in Iter::new(nodes) {
+            if let RefNode::Locate(x) = x {
+                let end = x.offset + x.len;
+                range = Some(match range {
+                    Some(y) => y.start.min(x.offset)..y.end.max(end),
+                    None => x.offset..end,
+                });
+            }
+        }
+        if let Some(range) = range {
+            self.newline(range.start);
+            self.out.push_str(&" ".repeat(self.level * self.indent));
+            self.out.push_str(self.text[range.clone()].trim());
+            self.prev_end = range.end;
+            self.brk = true;
+        }
+    }
+
+    /// Starts a new line before the text at `offset` unless at the start of a line.
+    fn newline(&mut self, offset: usize) {
+        let gap = self.text.get(self.prev_end..offset).unwrap_or("");
+        if !self.at_line_start() {
+            self.out.push('\n');
+            if self.blank || gap.matches('\n').count() > 1 {
+                self.out.push('\n');
+            }
+        }
+        self.brk = false;
+        self.blank = false;
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+}
+
+impl<'a> Token<'a> {
+    fn closer(&self) -> bool {
+        self.kind == Kind::Keyword
+            && (self.text.starts_with("end")
+                || matches!(self.text, "join" | "join_any" | "join_none"))
+    }
+}
+
+/// Returns whether a space is required between `prev` and `cur`.
+fn space(prev: &Token, cur: &Token, spaced_paren: bool) -> bool {
+    if prev.kind == Kind::Escaped {
+        return true;
+    }
+    if prev.glue_after || cur.glue_before {
+        return false;
+    }
+    if prev.number.is_some() && prev.number == cur.number {
+        return false;
+    }
+    if cur.kind != Kind::Symbol {
+        return true;
+    }
+    match cur.text {
+        "(" => spaced_paren || prev.kind != Kind::Identifier,
+        "[" | "." => prev.kind != Kind::Identifier && !matches!(prev.text, ")" | "]"),
+        _ => true,
+    }
+}
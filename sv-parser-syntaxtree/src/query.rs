@@ -0,0 +1,561 @@
+use crate::*;
+use core::str::FromStr;
+use std::ops::Range;
+
+// -----------------------------------------------------------------------------
+
+/// Selector of syntax tree nodes like CSS selectors.
+///
+/// * `ModuleDeclarationAnsi`: nodes of the `RefNode` variant, or `*` for any node
+/// * `A B`: `B` descendant of `A`, `A > B`: `B` child of `A`
+/// * `A, B`: `A` or `B`
+/// * `[text=v]`: the text of the node without surrounding white spaces is `v`
+/// * `[keyword=v]`: a descendant `Keyword` has the text `v`.
+///   Other kinds are given as `snake_case` or variant names, and `[kind]` requires only the kind.
+/// * `=`, `!=`, `^=`, `$=` and `*=` compare the whole, not, the prefix, the suffix and a part
+///   of the text. Values containing spaces or `]` are quoted by `"`.
+/// * `:has(S)`: a descendant matches `S`, `:not(S)`: the node doesn't match `S`
+/// * `A@name`: captures the node matching `A` as `name`
+///
+/// For example, `AlwaysConstruct[keyword=always_ff]:not(:has(EventExpression[identifier^=rst]))`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    selectors: Vec<Selector>,
+}
+
+/// Syntax error of a `Query`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    /// Offset of the error in the query
+    pub offset: usize,
+    pub message: String,
+}
+
+/// A node matched by a `Query`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryMatch<'a> {
+    pub node: RefNode<'a>,
+    /// Range of the text of the node without surrounding white spaces
+    pub span: Option<Range<usize>>,
+    /// Nodes captured by `@name` from the left of the query
+    pub captures: Vec<(String, RefNode<'a>)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Selector {
+    /// Compounds and the combinators from the previous compound
+    compounds: Vec<(Combinator, Compound)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Compound {
+    kind: Option<String>,
+    predicates: Vec<Predicate>,
+    capture: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Predicate {
+    /// Text of the node if `kind` is `None`, otherwise of its descendants of `kind`
+    Text {
+        kind: Option<String>,
+        condition: Option<(Operator, String)>,
+    },
+    Has(Vec<Selector>),
+    Not(Vec<Selector>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Prefix,
+    Suffix,
+    Contains,
+}
+
+impl Operator {
+    fn eval(self, x: &str, value: &str) -> bool {
+        match self {
+            Operator::Equal => x == value,
+            Operator::NotEqual => x != value,
+            Operator::Prefix => x.starts_with(value),
+            Operator::Suffix => x.ends_with(value),
+            Operator::Contains => x.contains(value),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+impl Query {
+    pub fn new(s: &str) -> Result<Self, QueryError> {
+        let mut parser = QueryParser { s, pos: 0 };
+        let selectors = parser.selectors()?;
+        parser.space();
+        if parser.pos < s.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Query { selectors })
+    }
+
+    /// Returns the nodes of `index` matching the query in depth-first order.
+    /// `text` is the text where the nodes are located.
+    ///
+    /// Each selector and predicate is evaluated once for all nodes,
+    /// so the time is linear in the number of the nodes for a query.
+    pub fn matches<'a>(&self, index: &TreeIndex<'a>, text: &str) -> Vec<QueryMatch<'a>> {
+        let matcher = Matcher::new(index, text);
+        let tables: Vec<_> = self.selectors.iter().map(|x| matcher.selector(x)).collect();
+        let mut ret = Vec::new();
+        for (i, entry) in index.nodes.iter().enumerate() {
+            let matched = self
+                .selectors
+                .iter()
+                .zip(&tables)
+                .find(|(_, table)| table.last().unwrap()[i]);
+            if let Some((selector, table)) = matched {
+                ret.push(QueryMatch {
+                    node: entry.node.clone(),
+                    span: entry.span.clone(),
+                    captures: matcher.captures(selector, table, i),
+                });
+            }
+        }
+        ret
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::new(s)
+    }
+}
+
+impl core::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at {}", self.message, self.offset)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+struct QueryParser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn error(&self, message: &str) -> QueryError {
+        QueryError {
+            offset: self.pos,
+            message: String::from(message),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn space(&mut self) -> bool {
+        let len = self.rest().len() - self.rest().trim_start().len();
+        self.pos += len;
+        len > 0
+    }
+
+    fn eat(&mut self, t: &str) -> bool {
+        if self.rest().starts_with(t) {
+            self.pos += t.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, t: &str) -> Result<(), QueryError> {
+        if self.eat(t) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", t)))
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, QueryError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|x: char| !x.is_ascii_alphanumeric() && x != '_')
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn selectors(&mut self) -> Result<Vec<Selector>, QueryError> {
+        let mut ret = vec![self.selector()?];
+        loop {
+            self.space();
+            if !self.eat(",") {
+                return Ok(ret);
+            }
+            ret.push(self.selector()?);
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, QueryError> {
+        self.space();
+        let mut compounds = vec![(Combinator::Descendant, self.compound()?)];
+        loop {
+            let space = self.space();
+            let combinator = if self.eat(">") {
+                self.space();
+                Combinator::Child
+            } else if space && !matches!(self.peek(), None | Some(',') | Some(')')) {
+                Combinator::Descendant
+            } else {
+                return Ok(Selector { compounds });
+            };
+            compounds.push((combinator, self.compound()?));
+        }
+    }
+
+    fn compound(&mut self) -> Result<Compound, QueryError> {
+        let kind = if self.eat("*") || matches!(self.peek(), Some('[') | Some(':')) {
+            None
+        } else {
+            Some(String::from(self.name()?))
+        };
+
+        let mut predicates = Vec::new();
+        loop {
+            if self.eat("[") {
+                predicates.push(self.text_predicate()?);
+            } else if self.eat(":has(") {
+                predicates.push(Predicate::Has(self.selectors()?));
+                self.expect(")")?;
+            } else if self.eat(":not(") {
+                predicates.push(Predicate::Not(self.selectors()?));
+                self.expect(")")?;
+            } else if self.peek() == Some(':') {
+                return Err(self.error("expected `:has(` or `:not(`"));
+            } else {
+                break;
+            }
+        }
+
+        let capture = if self.eat("@") {
+            Some(String::from(self.name()?))
+        } else {
+            None
+        };
+        Ok(Compound {
+            kind,
+            predicates,
+            capture,
+        })
+    }
+
+    fn text_predicate(&mut self) -> Result<Predicate, QueryError> {
+        self.space();
+        let name = self.name()?;
+        let kind = if name == "text" {
+            None
+        } else {
+            Some(camel_case(name))
+        };
+        self.space();
+        if self.eat("]") {
+            return Ok(Predicate::Text {
+                kind,
+                condition: None,
+            });
+        }
+
+        let operator = if self.eat("=") {
+            Operator::Equal
+        } else if self.eat("!=") {
+            Operator::NotEqual
+        } else if self.eat("^=") {
+            Operator::Prefix
+        } else if self.eat("$=") {
+            Operator::Suffix
+        } else if self.eat("*=") {
+            Operator::Contains
+        } else {
+            return Err(self.error("expected an operator"));
+        };
+        self.space();
+        let value = self.value()?;
+        self.space();
+        self.expect("]")?;
+        Ok(Predicate::Text {
+            kind,
+            condition: Some((operator, value)),
+        })
+    }
+
+    fn value(&mut self) -> Result<String, QueryError> {
+        let rest = self.rest();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let len = quoted
+                .find('"')
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += len + 2;
+            Ok(String::from(&quoted[..len]))
+        } else {
+            let len = rest
+                .find(|x: char| x == ']' || x.is_whitespace())
+                .unwrap_or(rest.len());
+            self.pos += len;
+            Ok(String::from(&rest[..len]))
+        }
+    }
+}
+
+/// Converts `always_construct` to `AlwaysConstruct`. Variant names are kept as is.
+fn camel_case(name: &str) -> String {
+    let mut ret = String::new();
+    for x in name.split('_') {
+        let mut chars = x.chars();
+        if let Some(c) = chars.next() {
+            ret.push(c.to_ascii_uppercase());
+            ret.extend(chars);
+        }
+    }
+    ret
+}
+
+// -----------------------------------------------------------------------------
+
+/// Evaluation of queries on the nodes of an index.
+///
+/// Selectors and predicates are evaluated to tables of the results for each node,
+/// which are indexed by the depth-first order of the nodes.
+struct Matcher<'i, 'a> {
+    index: &'i TreeIndex<'a>,
+    text: &'i str,
+    /// Whether the node is or is in a white space
+    trivia: Vec<bool>,
+}
+
+impl<'i, 'a> Matcher<'i, 'a> {
+    fn new(index: &'i TreeIndex<'a>, text: &'i str) -> Self {
+        let mut trivia = Vec::with_capacity(index.nodes.len());
+        for x in &index.nodes {
+            let t = matches!(x.node, RefNode::WhiteSpace(_)) || x.parent.is_some_and(|x| trivia[x]);
+            trivia.push(t);
+        }
+        Matcher {
+            index,
+            text,
+            trivia,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.index.nodes.len()
+    }
+
+    fn parent(&self, i: usize) -> Option<usize> {
+        self.index.nodes[i].parent
+    }
+
+    fn text(&self, i: usize) -> &'i str {
+        self.index.nodes[i]
+            .span
+            .clone()
+            .map_or("", |x| &self.text[x])
+    }
+
+    /// Returns whether the compounds of `selector` until each one match each node,
+    /// which is the last node matched by the compounds.
+    fn selector(&self, selector: &Selector) -> Vec<Vec<bool>> {
+        let mut ret: Vec<Vec<bool>> = Vec::new();
+        for (combinator, compound) in &selector.compounds {
+            let mut table = self.compound(compound);
+            if let Some(prev) = ret.last() {
+                match combinator {
+                    Combinator::Child => {
+                        for (i, x) in table.iter_mut().enumerate() {
+                            *x = *x && self.parent(i).is_some_and(|p| prev[p]);
+                        }
+                    }
+                    Combinator::Descendant => {
+                        // Whether an ancestor is matched by the previous compounds
+                        let mut ancestor = vec![false; self.len()];
+                        for (i, x) in table.iter_mut().enumerate() {
+                            if let Some(p) = self.parent(i) {
+                                ancestor[i] = prev[p] || ancestor[p];
+                            }
+                            *x = *x && ancestor[i];
+                        }
+                    }
+                }
+            }
+            ret.push(table);
+        }
+        ret
+    }
+
+    /// Returns whether `selectors` match each node.
+    fn selectors(&self, selectors: &[Selector]) -> Vec<bool> {
+        let mut ret = vec![false; self.len()];
+        for selector in selectors {
+            let table = self.selector(selector);
+            for (x, y) in ret.iter_mut().zip(table.last().unwrap()) {
+                *x = *x || *y;
+            }
+        }
+        ret
+    }
+
+    /// Returns whether a descendant of each node matches `selectors`
+    /// with the ancestors matched by the compounds below the node.
+    fn has(&self, selectors: &[Selector]) -> Vec<bool> {
+        let mut ret = vec![false; self.len()];
+        for selector in selectors {
+            // Whether the compounds from each one match each node and its descendants
+            let mut table: Option<Vec<bool>> = None;
+            for (i, (_, compound)) in selector.compounds.iter().enumerate().rev() {
+                let mut x = self.compound(compound);
+                if let Some(next) = table {
+                    let below = match selector.compounds[i + 1].0 {
+                        Combinator::Child => self.in_children(&next),
+                        Combinator::Descendant => self.in_descendants(&next),
+                    };
+                    for (x, y) in x.iter_mut().zip(below) {
+                        *x = *x && y;
+                    }
+                }
+                table = Some(x);
+            }
+            for (x, y) in ret.iter_mut().zip(self.in_descendants(&table.unwrap())) {
+                *x = *x || y;
+            }
+        }
+        ret
+    }
+
+    /// Returns whether a child of each node is set in `table`.
+    fn in_children(&self, table: &[bool]) -> Vec<bool> {
+        let mut ret = vec![false; self.len()];
+        for (i, x) in table.iter().enumerate() {
+            if let (true, Some(p)) = (*x, self.parent(i)) {
+                ret[p] = true;
+            }
+        }
+        ret
+    }
+
+    /// Returns whether a descendant of each node is set in `table`.
+    fn in_descendants(&self, table: &[bool]) -> Vec<bool> {
+        // The numbers of the set nodes before each node
+        let mut count = vec![0; self.len() + 1];
+        for (i, x) in table.iter().enumerate() {
+            count[i + 1] = count[i] + *x as usize;
+        }
+        (0..self.len())
+            .map(|i| count[self.index.nodes[i].end] > count[i + 1])
+            .collect()
+    }
+
+    /// Returns whether `compound` matches each node.
+    fn compound(&self, compound: &Compound) -> Vec<bool> {
+        let mut ret: Vec<bool> = match &compound.kind {
+            Some(kind) => self
+                .index
+                .nodes
+                .iter()
+                .map(|x| x.node.kind() == kind)
+                .collect(),
+            None => vec![true; self.len()],
+        };
+        for predicate in &compound.predicates {
+            if !ret.iter().any(|x| *x) {
+                break;
+            }
+            let table = match predicate {
+                Predicate::Text {
+                    kind: None,
+                    condition,
+                } => (0..self.len())
+                    .map(|i| ret[i] && eval(condition, self.text(i)))
+                    .collect(),
+                Predicate::Text {
+                    kind: Some(kind),
+                    condition,
+                } => {
+                    let table: Vec<_> = self
+                        .index
+                        .nodes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, x)| {
+                            !self.trivia[i]
+                                && x.node.kind() == kind
+                                && eval(condition, self.text(i))
+                        })
+                        .collect();
+                    let below = self.in_descendants(&table);
+                    (0..self.len()).map(|i| table[i] || below[i]).collect()
+                }
+                Predicate::Has(selectors) => self.has(selectors),
+                Predicate::Not(selectors) => self.selectors(selectors).iter().map(|x| !x).collect(),
+            };
+            for (x, y) in ret.iter_mut().zip(table) {
+                *x = *x && y;
+            }
+        }
+        ret
+    }
+
+    /// Returns the captures of the match of `selector` at the node `i` from the left of the query.
+    ///
+    /// Each compound is matched to the nearest ancestor matched by the compounds until it.
+    fn captures(
+        &self,
+        selector: &Selector,
+        table: &[Vec<bool>],
+        i: usize,
+    ) -> Vec<(String, RefNode<'a>)> {
+        let mut ret = Vec::new();
+        let mut node = Some(i);
+        for (j, (combinator, compound)) in selector.compounds.iter().enumerate().rev() {
+            let i = node.unwrap();
+            if let Some(name) = &compound.capture {
+                ret.push((name.clone(), self.index.nodes[i].node.clone()));
+            }
+            if j == 0 {
+                break;
+            }
+            node = self.parent(i);
+            if *combinator == Combinator::Descendant {
+                while let Some(x) = node.filter(|x| !table[j - 1][*x]) {
+                    node = self.parent(x);
+                }
+            }
+        }
+        ret.reverse();
+        ret
+    }
+}
+
+fn eval(condition: &Option<(Operator, String)>, text: &str) -> bool {
+    match condition {
+        Some((op, v)) => op.eval(text, v),
+        None => true,
+    }
+}
@@ -12,6 +12,7 @@ pub enum ClassItem {
     LocalParameterDeclaration(Box<(LocalParameterDeclaration, Symbol)>),
     ParameterDeclaration(Box<(ParameterDeclaration, Symbol)>),
     Empty(Box<Symbol>),
+    TextMacroUsage(Box<(TextMacroUsage, Option<Symbol>)>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -157,6 +158,22 @@ pub enum MethodPrototype {
     FunctionPrototype(Box<FunctionPrototype>),
 }
 
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct DynamicOverrideSpecifiers {
+    pub nodes: (Option<InitialOrExtendsSpecifier>, Option<FinalSpecifier>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum InitialOrExtendsSpecifier {
+    Initial(Box<(Symbol, Keyword)>),
+    Extends(Box<(Symbol, Keyword)>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct FinalSpecifier {
+    pub nodes: (Symbol, Keyword),
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ClassConstructorDeclaration {
     pub nodes: (
@@ -6,6 +6,7 @@ use crate::*;
 pub enum InterfaceOrGenerateItem {
     Module(Box<InterfaceOrGenerateItemModule>),
     Extern(Box<InterfaceOrGenerateItemExtern>),
+    TextMacroUsage(Box<(TextMacroUsage, Option<Symbol>)>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
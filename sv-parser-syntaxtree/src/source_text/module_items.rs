@@ -71,6 +71,7 @@ pub enum ModuleOrGenerateItem {
     Udp(Box<ModuleOrGenerateItemUdp>),
     Module(Box<ModuleOrGenerateItemModule>),
     ModuleItem(Box<ModuleOrGenerateItemModuleItem>),
+    TextMacroUsage(Box<(TextMacroUsage, Option<Symbol>)>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
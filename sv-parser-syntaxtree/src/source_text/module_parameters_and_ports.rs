@@ -9,6 +9,29 @@ pub enum ParameterPortList {
     Empty(Box<(Symbol, Symbol, Symbol)>),
 }
 
+impl ParameterPortList {
+    /// Returns the value parameters. Type parameters are not included.
+    pub fn parameters(&self) -> Vec<&ParamAssignment> {
+        let mut ret = Vec::new();
+        match self {
+            ParameterPortList::Assignment(x) => {
+                let (ref a, ref b) = (x.nodes.1).nodes.1;
+                ret.append(&mut a.nodes.0.contents());
+                for (_, x) in b {
+                    ret.append(&mut x.parameters());
+                }
+            }
+            ParameterPortList::Declaration(x) => {
+                for x in (x.nodes.1).nodes.1.contents() {
+                    ret.append(&mut x.parameters());
+                }
+            }
+            ParameterPortList::Empty(_) => (),
+        }
+        ret
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ParameterPortListAssignment {
     pub nodes: (
@@ -33,6 +56,24 @@ pub enum ParameterPortDeclaration {
     TypeList(Box<ParameterPortDeclarationTypeList>),
 }
 
+impl ParameterPortDeclaration {
+    /// Returns the value parameters. Type parameters are not included.
+    pub fn parameters(&self) -> Vec<&ParamAssignment> {
+        match self {
+            ParameterPortDeclaration::ParameterDeclaration(x) => match x.as_ref() {
+                ParameterDeclaration::Param(x) => x.nodes.2.nodes.0.contents(),
+                ParameterDeclaration::Type(_) => Vec::new(),
+            },
+            ParameterPortDeclaration::LocalParameterDeclaration(x) => match x.as_ref() {
+                LocalParameterDeclaration::Param(x) => x.nodes.2.nodes.0.contents(),
+                LocalParameterDeclaration::Type(_) => Vec::new(),
+            },
+            ParameterPortDeclaration::ParamList(x) => x.nodes.1.nodes.0.contents(),
+            ParameterPortDeclaration::TypeList(_) => Vec::new(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ParameterPortDeclarationParamList {
     pub nodes: (DataType, ListOfParamAssignments),
@@ -40,7 +81,11 @@ pub struct ParameterPortDeclarationParamList {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ParameterPortDeclarationTypeList {
-    pub nodes: (Keyword, ListOfTypeAssignments),
+    pub nodes: (
+        Keyword,
+        Option<TypeDeclarationKeyword>,
+        ListOfTypeAssignments,
+    ),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -48,11 +93,26 @@ pub struct ListOfPorts {
     pub nodes: (Paren<List<Symbol, Port>>,),
 }
 
+impl ListOfPorts {
+    pub fn ports(&self) -> Vec<&Port> {
+        (self.nodes.0).nodes.1.contents()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ListOfPortDeclarations {
     pub nodes: (Paren<Option<List<Symbol, (Vec<AttributeInstance>, AnsiPortDeclaration)>>>,),
 }
 
+impl ListOfPortDeclarations {
+    pub fn ports(&self) -> Vec<&AnsiPortDeclaration> {
+        match &(self.nodes.0).nodes.1 {
+            Some(x) => x.contents().into_iter().map(|(_, x)| x).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub enum PortDeclaration {
     Inout(Box<PortDeclarationInout>),
@@ -93,6 +153,26 @@ pub enum Port {
     Named(Box<PortNamed>),
 }
 
+impl Port {
+    /// Returns the name of the port, or the names of the ports it references if not named.
+    pub fn names(&self) -> Vec<&PortIdentifier> {
+        match self {
+            Port::NonNamed(x) => match &x.nodes.0 {
+                Some(PortExpression::PortReference(x)) => vec![&x.nodes.0],
+                Some(PortExpression::Brace(x)) => (x.nodes.0)
+                    .nodes
+                    .1
+                    .contents()
+                    .into_iter()
+                    .map(|x| &x.nodes.0)
+                    .collect(),
+                None => Vec::new(),
+            },
+            Port::Named(x) => vec![&x.nodes.1],
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct PortNonNamed {
     pub nodes: (Option<PortExpression>,),
@@ -165,6 +245,16 @@ pub enum AnsiPortDeclaration {
     Paren(Box<AnsiPortDeclarationParen>),
 }
 
+impl AnsiPortDeclaration {
+    pub fn name(&self) -> &PortIdentifier {
+        match self {
+            AnsiPortDeclaration::Net(x) => &x.nodes.1,
+            AnsiPortDeclaration::Variable(x) => &x.nodes.1,
+            AnsiPortDeclaration::Paren(x) => &x.nodes.2,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct AnsiPortDeclarationNet {
     pub nodes: (
@@ -8,6 +8,7 @@ pub enum PackageItem {
     AnonymousProgram(Box<AnonymousProgram>),
     PackageExportDeclaration(Box<PackageExportDeclaration>),
     TimeunitsDeclaration(Box<TimeunitsDeclaration>),
+    TextMacroUsage(Box<(TextMacroUsage, Option<Symbol>)>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -72,6 +72,86 @@ pub enum ModuleDeclaration {
     ExternAnsi(Box<ModuleDeclarationExternAnsi>),
 }
 
+impl ModuleDeclaration {
+    pub fn name(&self) -> &ModuleIdentifier {
+        match self {
+            ModuleDeclaration::Nonansi(x) => &(x.nodes.0).nodes.3,
+            ModuleDeclaration::Ansi(x) => &(x.nodes.0).nodes.3,
+            ModuleDeclaration::Wildcard(x) => &x.nodes.3,
+            ModuleDeclaration::ExternNonansi(x) => &(x.nodes.1).nodes.3,
+            ModuleDeclaration::ExternAnsi(x) => &(x.nodes.1).nodes.3,
+        }
+    }
+
+    /// Returns the value parameters of the parameter port list.
+    pub fn parameters(&self) -> Vec<&ParamAssignment> {
+        let list = match self {
+            ModuleDeclaration::Nonansi(x) => &(x.nodes.0).nodes.5,
+            ModuleDeclaration::Ansi(x) => &(x.nodes.0).nodes.5,
+            ModuleDeclaration::Wildcard(_) => return Vec::new(),
+            ModuleDeclaration::ExternNonansi(x) => &(x.nodes.1).nodes.5,
+            ModuleDeclaration::ExternAnsi(x) => &(x.nodes.1).nodes.5,
+        };
+        list.as_ref().map_or_else(Vec::new, |x| x.parameters())
+    }
+
+    /// Returns the names of the ports in the header. Ports of `module m(.*);` are not known.
+    pub fn ports(&self) -> Vec<&PortIdentifier> {
+        let (nonansi, ansi) = match self {
+            ModuleDeclaration::Nonansi(x) => (Some(&(x.nodes.0).nodes.6), None),
+            ModuleDeclaration::Ansi(x) => (None, (x.nodes.0).nodes.6.as_ref()),
+            ModuleDeclaration::Wildcard(_) => (None, None),
+            ModuleDeclaration::ExternNonansi(x) => (Some(&(x.nodes.1).nodes.6), None),
+            ModuleDeclaration::ExternAnsi(x) => (None, (x.nodes.1).nodes.6.as_ref()),
+        };
+        let mut ret = Vec::new();
+        if let Some(x) = nonansi {
+            for x in x.ports() {
+                ret.append(&mut x.names());
+            }
+        }
+        if let Some(x) = ansi {
+            for x in x.ports() {
+                ret.push(x.name());
+            }
+        }
+        ret
+    }
+
+    /// Returns the port declarations in the body of a non-ANSI module.
+    pub fn port_declarations(&self) -> Vec<&PortDeclaration> {
+        let items = match self {
+            ModuleDeclaration::Nonansi(x) => &x.nodes.2,
+            ModuleDeclaration::Wildcard(x) => &x.nodes.7,
+            _ => return Vec::new(),
+        };
+        items
+            .iter()
+            .filter_map(|x| match x {
+                ModuleItem::PortDeclaration(x) => Some(&x.0),
+                ModuleItem::NonPortModuleItem(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the items in the body except port declarations.
+    pub fn items(&self) -> Vec<&NonPortModuleItem> {
+        let items = match self {
+            ModuleDeclaration::Nonansi(x) => &x.nodes.2,
+            ModuleDeclaration::Ansi(x) => return x.nodes.2.iter().collect(),
+            ModuleDeclaration::Wildcard(x) => &x.nodes.7,
+            _ => return Vec::new(),
+        };
+        items
+            .iter()
+            .filter_map(|x| match x {
+                ModuleItem::PortDeclaration(_) => None,
+                ModuleItem::NonPortModuleItem(x) => Some(x.as_ref()),
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ModuleDeclarationNonansi {
     pub nodes: (
@@ -313,6 +393,7 @@ pub struct ClassDeclaration {
     pub nodes: (
         Option<Virtual>,
         Keyword,
+        Option<FinalSpecifier>,
         Option<Lifetime>,
         ClassIdentifier,
         Option<ParameterPortList>,
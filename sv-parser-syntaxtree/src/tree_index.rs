@@ -0,0 +1,300 @@
+use crate::*;
+use std::collections::HashMap;
+use std::mem::{discriminant, Discriminant};
+use std::ops::Range;
+use std::path::Path;
+
+// -----------------------------------------------------------------------------
+
+/// Index of a syntax tree to navigate from a node to its parent and siblings,
+/// and to find the nodes at offsets of the parsed text or, through `TreeIndex::with_origins`,
+/// at offsets of the source files.
+///
+/// Nodes are identified by their addresses, so only nodes of the indexed tree can be looked up.
+/// Methods return `None` or nothing for other nodes.
+pub struct TreeIndex<'a> {
+    /// Nodes in depth-first order
+    pub(crate) nodes: Vec<Entry<'a>>,
+    ids: HashMap<(Discriminant<RefNode<'a>>, *const ()), usize>,
+    /// Indices of `Locate`s in the order of their offsets
+    locates: Vec<usize>,
+    /// Offset in the parsed text of an offset of a source file
+    origins: Option<Box<dyn Fn(&Path, usize) -> Option<usize> + 'a>>,
+}
+
+pub(crate) struct Entry<'a> {
+    pub(crate) node: RefNode<'a>,
+    pub(crate) parent: Option<usize>,
+    depth: usize,
+    first_child: Option<usize>,
+    prev_sibling: Option<usize>,
+    next_sibling: Option<usize>,
+    /// End of the indices of the node and its descendants
+    pub(crate) end: usize,
+    /// Range of the text without white spaces and comments
+    pub(crate) span: Option<Range<usize>>,
+}
+
+impl<'a> TreeIndex<'a> {
+    pub fn new<T: Into<RefNodes<'a>>>(nodes: T) -> Self {
+        let mut ret = TreeIndex {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            locates: Vec::new(),
+            origins: None,
+        };
+        // Indices of the entered nodes and their last children
+        let mut stack: Vec<(usize, Option<usize>)> = Vec::new();
+        let mut last_root = None;
+        for event in Iter::new(nodes.into()).event() {
+            match event {
+                NodeEvent::Enter(x) => {
+                    let index = ret.nodes.len();
+                    let (parent, prev_sibling) = match stack.last_mut() {
+                        Some((parent, last)) => (Some(*parent), last.replace(index)),
+                        None => (None, last_root.replace(index)),
+                    };
+                    if let Some(prev) = prev_sibling {
+                        ret.nodes[prev].next_sibling = Some(index);
+                    } else if let Some(parent) = parent {
+                        ret.nodes[parent].first_child = Some(index);
+                    }
+                    ret.ids.insert((discriminant(&x), x.ptr()), index);
+                    let span = if let RefNode::Locate(x) = x {
+                        ret.locates.push(index);
+                        Some(x.offset..x.offset + x.len)
+                    } else {
+                        None
+                    };
+                    ret.nodes.push(Entry {
+                        node: x,
+                        parent,
+                        depth: stack.len(),
+                        first_child: None,
+                        prev_sibling,
+                        next_sibling: None,
+                        end: index + 1,
+                        span,
+                    });
+                    stack.push((index, None));
+                }
+                NodeEvent::Leave(_) => {
+                    let (index, _) = stack.pop().unwrap();
+                    let end = ret.nodes.len();
+                    ret.nodes[index].end = end;
+
+                    // White spaces are excluded from the spans of the enclosing nodes
+                    let white_space = |x: &Entry| matches!(x.node, RefNode::WhiteSpace(_));
+                    if let Some(parent) = ret.nodes[index].parent {
+                        if !white_space(&ret.nodes[index]) && !white_space(&ret.nodes[parent]) {
+                            if let Some(x) = ret.nodes[index].span.clone() {
+                                let y = &mut ret.nodes[parent].span;
+                                *y = Some(y.as_ref().map_or(x.start, |y| y.start)..x.end);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Sets the mapping from an offset of a source file to the offset in the parsed text
+    /// where it is copied, which is used by `node_at_origin` and `nodes_covering_origin`.
+    pub fn with_origins<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Path, usize) -> Option<usize> + 'a,
+    {
+        self.origins = Some(Box::new(f));
+        self
+    }
+
+    fn index(&self, node: &RefNode<'a>) -> Option<usize> {
+        self.ids.get(&(discriminant(node), node.ptr())).copied()
+    }
+
+    fn get(&self, index: Option<usize>) -> Option<RefNode<'a>> {
+        index.map(|x| self.nodes[x].node.clone())
+    }
+
+    /// Number of the indexed nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, node: &RefNode<'a>) -> bool {
+        self.index(node).is_some()
+    }
+
+    pub fn parent(&self, node: &RefNode<'a>) -> Option<RefNode<'a>> {
+        self.get(self.nodes[self.index(node)?].parent)
+    }
+
+    /// Returns the ancestors of `node` from its parent to the root.
+    pub fn ancestors(&self, node: &RefNode<'a>) -> Ancestors<'_, 'a> {
+        Ancestors {
+            index: self,
+            next: self.index(node).and_then(|x| self.nodes[x].parent),
+        }
+    }
+
+    pub fn children(&self, node: &RefNode<'a>) -> Siblings<'_, 'a> {
+        Siblings {
+            index: self,
+            next: self.index(node).and_then(|x| self.nodes[x].first_child),
+        }
+    }
+
+    pub fn next_sibling(&self, node: &RefNode<'a>) -> Option<RefNode<'a>> {
+        self.get(self.nodes[self.index(node)?].next_sibling)
+    }
+
+    pub fn prev_sibling(&self, node: &RefNode<'a>) -> Option<RefNode<'a>> {
+        self.get(self.nodes[self.index(node)?].prev_sibling)
+    }
+
+    /// Returns the range of the text of `node` without white spaces and comments.
+    pub fn span(&self, node: &RefNode<'a>) -> Option<Range<usize>> {
+        self.nodes[self.index(node)?].span.clone()
+    }
+
+    /// Depth of `node`. The roots of the index have depth 0.
+    pub fn depth(&self, node: &RefNode<'a>) -> Option<usize> {
+        Some(self.nodes[self.index(node)?].depth)
+    }
+
+    /// Returns the innermost node at `offset`, which is a `Locate`, followed by its ancestors.
+    pub fn node_at(&self, offset: usize) -> Vec<RefNode<'a>> {
+        self.locate_at(offset)
+            .map_or_else(Vec::new, |x| self.chain(Some(x)))
+    }
+
+    /// Returns the innermost node covering `range` followed by its ancestors.
+    pub fn nodes_covering(&self, range: Range<usize>) -> Vec<RefNode<'a>> {
+        if range.is_empty() {
+            return self.node_at(range.start);
+        }
+        let (begin, end) = match (self.locate_at(range.start), self.locate_at(range.end - 1)) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Vec::new(),
+        };
+
+        // The lowest common ancestor of the first and last `Locate`s
+        let mut x = Some(begin);
+        let mut y = Some(end);
+        while x != y {
+            let (i, j) = (x.unwrap(), y.unwrap());
+            if self.nodes[i].depth >= self.nodes[j].depth {
+                x = self.nodes[i].parent;
+            } else {
+                y = self.nodes[j].parent;
+            }
+            if x.is_none() || y.is_none() {
+                return Vec::new();
+            }
+        }
+        self.chain(x)
+    }
+
+    /// Returns the innermost node at `offset` of the source file `path` followed by its ancestors.
+    ///
+    /// Nothing is returned without the mapping set by `with_origins`.
+    pub fn node_at_origin<T: AsRef<Path>>(&self, path: T, offset: usize) -> Vec<RefNode<'a>> {
+        match self.origin(path.as_ref(), offset) {
+            Some(x) => self.node_at(x),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the innermost node covering `range` of the source file `path` followed by its ancestors.
+    ///
+    /// Nothing is returned without the mapping set by `with_origins`.
+    pub fn nodes_covering_origin<T: AsRef<Path>>(
+        &self,
+        path: T,
+        range: Range<usize>,
+    ) -> Vec<RefNode<'a>> {
+        let path = path.as_ref();
+        let begin = self.origin(path, range.start);
+        let end = if range.is_empty() {
+            begin
+        } else {
+            self.origin(path, range.end - 1).map(|x| x + 1)
+        };
+        match (begin, end) {
+            (Some(begin), Some(end)) if begin <= end => self.nodes_covering(begin..end),
+            _ => Vec::new(),
+        }
+    }
+
+    fn origin(&self, path: &Path, offset: usize) -> Option<usize> {
+        self.origins.as_ref().and_then(|f| f(path, offset))
+    }
+
+    fn locate_at(&self, offset: usize) -> Option<usize> {
+        let locate = |x: usize| match self.nodes[x].node {
+            RefNode::Locate(x) => x,
+            _ => unreachable!(),
+        };
+        // The number of `Locate`s beginning at or before `offset`
+        let i = self
+            .locates
+            .binary_search_by(|&x| {
+                if locate(x).offset <= offset {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Greater
+                }
+            })
+            .unwrap_err();
+        let x = *self.locates.get(i.checked_sub(1)?)?;
+        if offset < locate(x).offset + locate(x).len {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
+    fn chain(&self, mut index: Option<usize>) -> Vec<RefNode<'a>> {
+        let mut ret = Vec::new();
+        while let Some(x) = index {
+            ret.push(self.nodes[x].node.clone());
+            index = self.nodes[x].parent;
+        }
+        ret
+    }
+}
+
+pub struct Ancestors<'i, 'a> {
+    index: &'i TreeIndex<'a>,
+    next: Option<usize>,
+}
+
+impl<'i, 'a> Iterator for Ancestors<'i, 'a> {
+    type Item = RefNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = &self.index.nodes[self.next?];
+        self.next = x.parent;
+        Some(x.node.clone())
+    }
+}
+
+pub struct Siblings<'i, 'a> {
+    index: &'i TreeIndex<'a>,
+    next: Option<usize>,
+}
+
+impl<'i, 'a> Iterator for Siblings<'i, 'a> {
+    type Item = RefNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = &self.index.nodes[self.next?];
+        self.next = x.next_sibling;
+        Some(x.node.clone())
+    }
+}
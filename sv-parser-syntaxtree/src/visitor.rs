@@ -0,0 +1,5 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+include!(concat!(env!("OUT_DIR"), "/visitor.rs"));
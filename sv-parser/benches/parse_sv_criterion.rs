@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{env, fs};
-use sv_parser::parse_sv;
+use sv_parser::{parse_sv, parse_sv_with_options, ParseOptions};
 
 fn get_path(s: &str) -> PathBuf {
     PathBuf::from(format!(
@@ -28,6 +28,15 @@ fn gen_benchmark_group(c: &mut Criterion, s: &str) {
     group.bench_function(s, |b| {
         b.iter_with_large_drop(|| parse_sv(&path, &defines, &includes, false))
     });
+    let options = ParseOptions {
+        outline: true,
+        ..ParseOptions::default()
+    };
+    group.bench_function(format!("{} outline", s), |b| {
+        b.iter_with_large_drop(|| {
+            parse_sv_with_options(&path, &defines, &includes, false, &options)
+        })
+    });
     group.finish();
 }
 
@@ -5,7 +5,7 @@ use std::io::Read;
 use std::path::PathBuf;
 use std::{cmp, process};
 use structopt::StructOpt;
-use sv_parser::parse_sv;
+use sv_parser::{parse_sv_files, CompilationUnit, ParseOptions};
 use sv_parser_error::Error;
 use sv_parser_pp::preprocess::preprocess;
 
@@ -34,8 +34,8 @@ fn main() {
     let opt = Opt::from_args();
     let mut defines = HashMap::new();
     let mut exit = 0;
-    for path in &opt.files {
-        if opt.pp {
+    if opt.pp {
+        for path in &opt.files {
             match preprocess(&path, &defines, &opt.includes, true, false) {
                 Ok((preprocessed_text, new_defines)) => {
                     println!("{}", preprocessed_text.text());
@@ -43,13 +43,22 @@ fn main() {
                 }
                 _ => (),
             }
-        } else {
-            match parse_sv(&path, &defines, &opt.includes, false) {
-                Ok((syntax_tree, new_defines)) => {
+        }
+    } else {
+        let results = parse_sv_files(
+            &opt.files,
+            &defines,
+            &opt.includes,
+            false,
+            CompilationUnit::MultiFile,
+            &ParseOptions::default(),
+        );
+        for (path, result) in opt.files.iter().zip(results) {
+            match result {
+                Ok((syntax_tree, _)) => {
                     if opt.tree {
                         println!("{}", syntax_tree);
                     }
-                    defines = new_defines;
                     if !opt.quiet {
                         println!("parse succeeded: {:?}", path);
                     }
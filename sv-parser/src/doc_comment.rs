@@ -0,0 +1,168 @@
+use crate::*;
+
+/// Documentation comments of a declaration.
+///
+/// Leading comments are a block of `//` comments or a `/** */` comment directly above the declaration,
+/// and trailing comments are `//<` or `/**<` comments after it, or after its following `,` or `;`,
+/// on the last line of the declaration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocComment {
+    pub leading: Vec<Locate>,
+    pub trailing: Vec<Locate>,
+    /// Lines of the comments without comment markers
+    pub text: String,
+}
+
+enum Piece {
+    Token(Locate),
+    Space(Locate),
+    Comment(Locate),
+    /// Compiler directive at the offset
+    Directive(usize),
+}
+
+impl Piece {
+    fn offset(&self) -> usize {
+        match self {
+            Piece::Token(x) | Piece::Space(x) | Piece::Comment(x) => x.offset,
+            Piece::Directive(x) => *x,
+        }
+    }
+}
+
+/// Tokens and white spaces of a tree in the order of their offsets.
+pub(crate) struct Pieces<'a> {
+    text: &'a str,
+    pieces: Vec<Piece>,
+}
+
+impl<'a> Pieces<'a> {
+    pub(crate) fn new(tree: &'a SyntaxTree) -> Self {
+        let mut pieces = Vec::new();
+        let mut in_white_space = 0;
+        for event in tree.into_iter().event() {
+            match event {
+                NodeEvent::Enter(RefNode::WhiteSpace(x)) => {
+                    if in_white_space == 0 {
+                        match x {
+                            WhiteSpace::Space(x) => pieces.push(Piece::Space(**x)),
+                            WhiteSpace::Comment(x) => pieces.push(Piece::Comment(x.nodes.0)),
+                            WhiteSpace::CompilerDirective(_) => {
+                                if let Some((x, _)) = extent(x.into(), true) {
+                                    pieces.push(Piece::Directive(x.start));
+                                }
+                            }
+                        }
+                    }
+                    in_white_space += 1;
+                }
+                NodeEvent::Leave(RefNode::WhiteSpace(_)) => in_white_space -= 1,
+                NodeEvent::Enter(RefNode::Locate(x)) if in_white_space == 0 => {
+                    pieces.push(Piece::Token(*x));
+                }
+                _ => (),
+            }
+        }
+        Pieces {
+            text: tree.text.text(),
+            pieces,
+        }
+    }
+
+    pub(crate) fn doc_comment(&self, nodes: RefNodes) -> Option<DocComment> {
+        let (range, _) = extent(nodes, false)?;
+        let first = self
+            .pieces
+            .binary_search_by_key(&range.start, |x| x.offset())
+            .ok()?;
+        let last = self.pieces.partition_point(|x| x.offset() < range.end) - 1;
+
+        let mut leading = Vec::new();
+        let mut next = range.start;
+        for x in self.pieces[..first].iter().rev() {
+            match x {
+                Piece::Space(_) => (),
+                Piece::Comment(x) => {
+                    let s = x.str(self.text);
+                    let doc = (s.starts_with("//") && !s.starts_with("//<"))
+                        || (s.starts_with("/**") && !s.starts_with("/**<") && s != "/**/");
+                    let end = x.offset + s.trim_end().len();
+                    let line = self.text[..x.offset].rsplit('\n').next().unwrap_or("");
+                    if !doc
+                        || self.text[end..next].matches('\n').count() > 1
+                        || !line.trim().is_empty()
+                    {
+                        break;
+                    }
+                    leading.push(*x);
+                    next = x.offset;
+                }
+                _ => break,
+            }
+        }
+        leading.reverse();
+
+        let mut trailing = Vec::new();
+        let mut separator = true;
+        for x in &self.pieces[last + 1..] {
+            match x {
+                Piece::Space(_) => (),
+                Piece::Comment(x) => {
+                    // Trailing comments start on the last line of the declaration
+                    if self.text[range.end..x.offset].contains('\n') {
+                        break;
+                    }
+                    let s = x.str(self.text);
+                    if s.starts_with("//<") || s.starts_with("/**<") {
+                        trailing.push(*x);
+                    }
+                }
+                Piece::Token(x) if separator && matches!(x.str(self.text), "," | ";") => {
+                    separator = false;
+                }
+                _ => break,
+            }
+        }
+
+        if leading.is_empty() && trailing.is_empty() {
+            return None;
+        }
+        let mut lines = Vec::new();
+        for x in leading.iter().chain(trailing.iter()) {
+            push_lines(&mut lines, x.str(self.text));
+        }
+        Some(DocComment {
+            leading,
+            trailing,
+            text: lines.join("\n"),
+        })
+    }
+}
+
+/// Pushes the lines of `comment` without comment markers.
+fn push_lines<'a>(lines: &mut Vec<&'a str>, comment: &'a str) {
+    let strip = |x: &'a str| x.strip_prefix(' ').unwrap_or(x).trim_end();
+    if let Some(x) = comment.strip_prefix("//") {
+        let x = x.strip_prefix(|c: char| c == '<' || c == '/').unwrap_or(x);
+        lines.push(strip(x));
+    } else {
+        let x = comment.trim_start_matches("/**");
+        let x = x.strip_prefix('<').unwrap_or(x);
+        let x = x.strip_suffix("*/").unwrap_or(x);
+        let mut block: Vec<_> = x
+            .lines()
+            .map(|x| {
+                let x = x.trim_start();
+                match x.strip_prefix('*') {
+                    Some(x) => strip(x),
+                    None => x.trim_end(),
+                }
+            })
+            .collect();
+        while block.last() == Some(&"") {
+            block.pop();
+        }
+        let begin = block.iter().position(|x| !x.is_empty()).unwrap_or(0);
+        lines.extend_from_slice(&block[begin..]);
+    }
+}
@@ -0,0 +1,524 @@
+use crate::*;
+
+/// Replacement of `range` in the source text by `text`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    pub range: std::ops::Range<usize>,
+    pub text: String,
+}
+
+/// Parser keeping the syntax tree of a source text to reparse it after each edit.
+///
+/// An edit which changes neither compiler directives, escaped identifiers nor the tokens after it
+/// is reparsed at the innermost module item or description enclosing it, and the other subtrees
+/// are reused. Other edits fall back to a full parse.
+///
+/// The descriptions are indexed by their offsets, so an edit visits only the description
+/// enclosing it. The locations in the following descriptions are shifted when the tree is
+/// requested by `syntax_tree`.
+pub struct IncrementalParser {
+    source: String,
+    path: PathBuf,
+    pre_defines: Defines,
+    include_paths: Vec<PathBuf>,
+    ignore_include: bool,
+    options: ParseOptions,
+    result: Option<(SyntaxTree, Defines)>,
+    /// Descriptions of the tree in the order of their offsets
+    descriptions: Vec<Entry>,
+}
+
+/// A description of the tree
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    /// Range in the preprocessed text including the trailing white spaces
+    begin: usize,
+    end: usize,
+    /// Shifts of the offsets and the lines of the locations which aren't applied yet
+    delta: isize,
+    line_delta: i64,
+    /// Version selected by `` `begin_keywords `` at the description
+    version: Option<Version>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Unit {
+    Description,
+    ModuleItem,
+    NonPortModuleItem,
+}
+
+impl Unit {
+    fn of(node: &RefNodeMut) -> Option<Unit> {
+        match node {
+            RefNodeMut::Description(_) => Some(Unit::Description),
+            RefNodeMut::ModuleItem(_) => Some(Unit::ModuleItem),
+            RefNodeMut::NonPortModuleItem(_) => Some(Unit::NonPortModuleItem),
+            _ => None,
+        }
+    }
+
+    fn parse(self, s: Span) -> Option<AnyNode> {
+        match self {
+            Unit::Description => all_consuming(Description::parse)(s)
+                .ok()
+                .map(|(_, x)| x.into()),
+            Unit::ModuleItem => all_consuming(ModuleItem::parse)(s)
+                .ok()
+                .map(|(_, x)| x.into()),
+            Unit::NonPortModuleItem => all_consuming(NonPortModuleItem::parse)(s)
+                .ok()
+                .map(|(_, x)| x.into()),
+        }
+    }
+}
+
+/// A node of `Unit` enclosing an edit
+struct Enclosing {
+    unit: Unit,
+    /// Indexes of the node and its ancestors of `Unit` among the sibling nodes of `Unit`
+    path: Vec<usize>,
+    begin: usize,
+    end: usize,
+    line: u32,
+    /// Version selected by `` `begin_keywords `` at the node
+    version: Option<Version>,
+}
+
+impl IncrementalParser {
+    /// Creates a parser of `s`. The source text is parsed by the first call of `parse` or `edit`.
+    pub fn new<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+        s: &str,
+        path: T,
+        pre_defines: &HashMap<String, Option<Define>, V>,
+        include_paths: &[U],
+        ignore_include: bool,
+        options: &ParseOptions,
+    ) -> Self {
+        IncrementalParser {
+            source: String::from(s),
+            path: PathBuf::from(path.as_ref()),
+            pre_defines: pre_defines
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            include_paths: include_paths
+                .iter()
+                .map(|x| PathBuf::from(x.as_ref()))
+                .collect(),
+            ignore_include,
+            options: options.clone(),
+            result: None,
+            descriptions: Vec::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The syntax tree of the last successful parse, or `None` if the last parse failed.
+    ///
+    /// The locations shifted by the edits since the last call are updated.
+    pub fn syntax_tree(&mut self) -> Option<&SyntaxTree> {
+        let (tree, _) = self.result.as_mut()?;
+        if let AnyNode::SourceText(ref mut x) = tree.node {
+            for (x, entry) in x.nodes.2.iter_mut().zip(self.descriptions.iter_mut()) {
+                if entry.delta != 0 || entry.line_delta != 0 {
+                    shift(x.into(), entry.delta, entry.line_delta);
+                    entry.delta = 0;
+                    entry.line_delta = 0;
+                }
+            }
+        }
+        Some(tree)
+    }
+
+    pub fn defines(&self) -> Option<&Defines> {
+        self.result.as_ref().map(|(_, x)| x)
+    }
+
+    /// Parses the whole source text.
+    pub fn parse(&mut self) -> Result<&SyntaxTree, Error> {
+        self.result = None;
+        self.descriptions.clear();
+        let (tree, defines) = parse_sv_str_with_options(
+            &self.source,
+            &self.path,
+            &self.pre_defines,
+            self.include_paths.as_slice(),
+            self.ignore_include,
+            &self.options,
+        )?;
+        self.descriptions = index(&tree);
+        self.result = Some((tree, defines));
+        Ok(&self.result.as_ref().unwrap().0)
+    }
+
+    /// Applies `edit` to the source text and reparses it. Returns whether it is reparsed
+    /// incrementally.
+    ///
+    /// The tree is got by `syntax_tree`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of `edit` is out of bounds or doesn't lie on `char` boundaries.
+    pub fn edit(&mut self, edit: &TextEdit) -> Result<bool, Error> {
+        let removed = String::from(&self.source[edit.range.clone()]);
+        self.source.replace_range(edit.range.clone(), &edit.text);
+
+        if let Some((mut tree, defines)) = self.result.take() {
+            if self.reparse(&mut tree, &removed, edit).is_some() {
+                self.result = Some((tree, defines));
+                return Ok(true);
+            }
+        }
+        self.parse().map(|_| false)
+    }
+
+    fn reparse(&mut self, tree: &mut SyntaxTree, removed: &str, edit: &TextEdit) -> Option<()> {
+        if self.options.outline {
+            return None;
+        }
+        let descriptions = match tree.node {
+            AnyNode::SourceText(ref mut x) => &mut x.nodes.2,
+            _ => return None,
+        };
+
+        // The description enclosing the edit in the preprocessed text
+        let begin = tree.text.position(&self.path, edit.range.start)?;
+        let end = begin + removed.len();
+        let k = self
+            .descriptions
+            .partition_point(|x| x.begin < begin)
+            .checked_sub(1)?;
+        let entry = self.descriptions[k];
+        if end >= entry.end {
+            return None;
+        }
+        let description = &mut descriptions[k];
+        shift(description.into(), entry.delta, entry.line_delta);
+        self.descriptions[k].delta = 0;
+        self.descriptions[k].line_delta = 0;
+
+        // The description is visited before the edit is applied to the preprocessed text,
+        // so the locations and `` `begin_keywords `` versions are read from the old text.
+        let delta = edit.text.len() as isize - removed.len() as isize;
+        let line_delta =
+            edit.text.matches('\n').count() as i64 - removed.matches('\n').count() as i64;
+        let mut walker = Walker {
+            text: tree.text.text(),
+            range: begin..end,
+            delta,
+            line_delta,
+            ret: Vec::new(),
+            stack: Vec::new(),
+            count: 0,
+            end: 0,
+            versions: vec![entry.version],
+            prev: None,
+            window_begin: None,
+            window_end: Window::None,
+        };
+        walker.walk(description.into());
+        let enclosing = walker.ret;
+        let window = match (walker.window_begin, walker.window_end) {
+            (Some(x), Window::End(y)) => x..y,
+            _ => return None,
+        };
+
+        // The window of the tokens around the edit in the source file
+        let source_begin = edit.range.start - (begin - window.start);
+        let source_end = edit.range.end + (window.end - end);
+        let text = &tree.text;
+        let origin = |x: usize| text.origin(x).map(|(x, y)| (x.clone(), y));
+        if origin(window.start) != Some((self.path.clone(), source_begin))
+            || origin(window.end - 1) != Some((self.path.clone(), source_end - 1))
+        {
+            return None;
+        }
+        let version = entry.version.or(self.options.version(&self.path));
+        if !self.is_local(removed, edit, source_begin..source_end, version) {
+            return None;
+        }
+
+        if tree
+            .text
+            .apply_edit(&self.path, edit.range.clone(), &edit.text)?
+            != (begin..end)
+        {
+            return None;
+        }
+        self.descriptions[k].end = (entry.end as isize + delta) as usize;
+        for x in &mut self.descriptions[k + 1..] {
+            x.begin = (x.begin as isize + delta) as usize;
+            x.end = (x.end as isize + delta) as usize;
+            x.delta += delta;
+            x.line_delta += line_delta;
+        }
+
+        for x in enclosing {
+            let end = (x.end as isize + delta) as usize;
+            let version = x.version.or(self.options.version(&self.path));
+            let state = self.options.parser_state();
+            let control = ParseControl {
+                cancel: self.options.cancel.as_ref(),
+                progress: None,
+            };
+            let extra = self.options.span_info(&state, version, control);
+            let fragment = &tree.text.text()[x.begin..end];
+            let node = x.unit.parse(Span::new_extra(fragment, extra));
+            self.options.collect_stats(&state);
+            let mut node = match node {
+                Some(node) => node,
+                None => continue,
+            };
+
+            // `fragment` is parsed from the beginning of the text, so the locations are moved to it
+            shift((&mut node).into(), x.begin as isize, i64::from(x.line) - 1);
+            replace(description.into(), &x.path, &mut 0, &mut Some(node));
+            return Some(());
+        }
+        None
+    }
+
+    /// Whether `edit` changes only the tokens in `window` of the source text, and neither compiler
+    /// directives nor escaped identifiers are in it. `window` begins at a token boundary before the edit, and ends with a token
+    /// after it, which must be tokenized the same after the edit.
+    fn is_local(
+        &self,
+        removed: &str,
+        edit: &TextEdit,
+        window: std::ops::Range<usize>,
+        version: Option<Version>,
+    ) -> bool {
+        let end = edit.range.start + edit.text.len();
+        let window_end = window.end + edit.text.len() - removed.len();
+        let old = format!(
+            "{}{}{}",
+            &self.source[window.start..edit.range.start],
+            removed,
+            &self.source[end..window_end]
+        );
+        let new = &self.source[window.start..window_end];
+
+        let tokens = |x: &str| {
+            let mut last = None;
+            for token in Tokenizer::new(x, version) {
+                // The preprocessor appends a space to escaped identifiers
+                let escaped =
+                    token.kind == TokenKind::Identifier && token.locate.str(x).starts_with('\\');
+                if token.kind == TokenKind::Directive || escaped {
+                    return None;
+                }
+                last = Some(token);
+            }
+            last.map(|y| (y.kind, String::from(y.locate.str(x))))
+        };
+        match (tokens(&old), tokens(new)) {
+            (Some(x), Some(y)) => x == y && window.end - x.1.len() >= edit.range.end,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the descriptions of `tree` in the order of their offsets.
+fn index(tree: &SyntaxTree) -> Vec<Entry> {
+    let mut ret: Vec<Entry> = Vec::new();
+    let mut versions = Vec::new();
+    for event in RefNode::from(&tree.node).into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::KeywordsDirective(x)) => {
+                let (_, _, _, ref version, _) = x.nodes;
+                let (ref version,) = version.nodes;
+                versions.push(version.nodes.0.str(tree.text.text()).parse().ok());
+            }
+            NodeEvent::Enter(RefNode::EndkeywordsDirective(_)) => {
+                versions.pop();
+            }
+            NodeEvent::Enter(RefNode::Description(_)) => {
+                let end = ret.last().map_or(0, |x| x.end);
+                ret.push(Entry {
+                    begin: usize::MAX,
+                    end,
+                    delta: 0,
+                    line_delta: 0,
+                    version: versions.last().copied().flatten(),
+                });
+            }
+            NodeEvent::Enter(RefNode::Locate(x)) => {
+                if let Some(entry) = ret.last_mut() {
+                    entry.begin = entry.begin.min(x.offset);
+                    entry.end = x.offset + x.len;
+                }
+            }
+            _ => (),
+        }
+    }
+    ret
+}
+
+/// Shifts the offsets and the lines of the locations in `node`.
+fn shift(node: RefNodeMut, delta: isize, line_delta: i64) {
+    if delta == 0 && line_delta == 0 {
+        return;
+    }
+    node.walk(&mut |x: &mut RefNodeMut| {
+        if let RefNodeMut::Locate(x) = x {
+            x.offset = (x.offset as isize + delta) as usize;
+            x.line = (x.line as i64 + line_delta) as u32;
+        }
+        true
+    });
+}
+
+/// A node of `Unit` being visited by `Walker`
+struct Frame {
+    unit: Unit,
+    path: Vec<usize>,
+    /// Offset and line of the first location
+    begin: Option<(usize, u32)>,
+    version: Option<Version>,
+}
+
+/// End of the window of the tokens around an edit
+#[derive(Clone, Copy)]
+enum Window {
+    None,
+    /// The location containing the end of the edit is visited
+    Edit,
+    /// End of the location after it
+    End(usize),
+}
+
+/// Visitor of a description finding the nodes strictly enclosing `range` from the innermost,
+/// and shifting the locations after the edit ending at `range.end`.
+struct Walker<'a> {
+    text: &'a str,
+    range: std::ops::Range<usize>,
+    delta: isize,
+    line_delta: i64,
+    ret: Vec<Enclosing>,
+    stack: Vec<Frame>,
+    /// Number of the visited nodes of `Unit` in the innermost frame
+    count: usize,
+    /// End of the last location
+    end: usize,
+    versions: Vec<Option<Version>>,
+    /// Offset of the last location
+    prev: Option<usize>,
+    /// Offset of the location before the location containing the beginning of the edit
+    window_begin: Option<usize>,
+    window_end: Window,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, node: RefNodeMut) {
+        match node {
+            RefNodeMut::Locate(x) => {
+                for frame in self.stack.iter_mut().rev() {
+                    if frame.begin.is_some() {
+                        break;
+                    }
+                    frame.begin = Some((x.offset, x.line));
+                }
+                self.end = x.offset + x.len;
+
+                let contains = |y: usize| x.offset <= y && y < x.offset + x.len;
+                if self.window_begin.is_none() && contains(self.range.start - 1) {
+                    self.window_begin = Some(self.prev.unwrap_or(x.offset));
+                }
+                self.window_end = match self.window_end {
+                    Window::None if contains(self.range.end) => Window::Edit,
+                    Window::Edit => Window::End(x.offset + x.len),
+                    y => y,
+                };
+                self.prev = Some(x.offset);
+
+                if x.offset >= self.range.end {
+                    x.offset = (x.offset as isize + self.delta) as usize;
+                    x.line = (x.line as i64 + self.line_delta) as u32;
+                }
+                return;
+            }
+            RefNodeMut::KeywordsDirective(ref x) => {
+                let (_, _, _, ref version, _) = x.nodes;
+                let (ref version,) = version.nodes;
+                let version = version.nodes.0.str(self.text).parse().ok();
+                self.versions.push(version);
+            }
+            RefNodeMut::EndkeywordsDirective(_) => {
+                self.versions.pop();
+            }
+            _ => (),
+        }
+
+        let unit = Unit::of(&node);
+        let count = self.count;
+        if let Some(unit) = unit {
+            let mut path = self.stack.last().map_or(Vec::new(), |x| x.path.clone());
+            path.push(count);
+            self.count = 0;
+            self.stack.push(Frame {
+                unit,
+                path,
+                begin: None,
+                version: self.versions.last().copied().flatten(),
+            });
+        }
+        for x in node.next_mut().0 {
+            self.walk(x);
+        }
+        if unit.is_some() {
+            self.count = count + 1;
+            let frame = self.stack.pop().unwrap();
+            if let Some((begin, line)) = frame.begin {
+                if begin < self.range.start && self.range.end < self.end {
+                    self.ret.push(Enclosing {
+                        unit: frame.unit,
+                        path: frame.path,
+                        begin,
+                        end: self.end,
+                        line,
+                        version: frame.version,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the node at `path` by `node`, where `count` is the number of the visited nodes of
+/// `Unit` among the siblings.
+fn replace(
+    tree: RefNodeMut,
+    path: &[usize],
+    count: &mut usize,
+    node: &mut Option<AnyNode>,
+) -> bool {
+    if Unit::of(&tree).is_none() {
+        return tree
+            .next_mut()
+            .0
+            .into_iter()
+            .any(|x| replace(x, path, count, node));
+    }
+    *count += 1;
+    if *count - 1 != path[0] {
+        return false;
+    }
+    if path.len() > 1 {
+        return tree
+            .next_mut()
+            .0
+            .into_iter()
+            .any(|x| replace(x, &path[1..], &mut 0, node));
+    }
+    match (tree, node.take().unwrap()) {
+        (RefNodeMut::Description(x), AnyNode::Description(y)) => *x = y,
+        (RefNodeMut::ModuleItem(x), AnyNode::ModuleItem(y)) => *x = y,
+        (RefNodeMut::NonPortModuleItem(x), AnyNode::NonPortModuleItem(y)) => *x = y,
+        _ => unreachable!(),
+    }
+    true
+}
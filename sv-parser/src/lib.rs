@@ -4,12 +4,39 @@ use nom::combinator::all_consuming;
 use nom_greedyerror::error_position;
 use std::collections::HashMap;
 use std::fmt;
+use std::fs;
 use std::hash::BuildHasher;
+use std::io;
 use std::path::{Path, PathBuf};
-pub use sv_parser_error::Error;
-use sv_parser_parser::{lib_parser, sv_parser, Span, SpanInfo};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+mod doc_comment;
+mod incremental;
+mod outline;
+mod rewrite;
+mod span;
+mod tests;
+pub use doc_comment::DocComment;
+use doc_comment::Pieces;
+pub use incremental::{IncrementalParser, TextEdit};
+pub use rewrite::Rewriter;
+use span::extent;
+pub use span::NodeSpan;
+pub use sv_parser_error::{Error, NotAllowed};
+use sv_parser_parser::lint::lint_version;
+use sv_parser_parser::stream::stream_parser;
+pub use sv_parser_parser::stream::StreamItem;
+pub use sv_parser_parser::tokenizer::{tokenize, Token, TokenKind, Tokenizer};
+use sv_parser_parser::{lib_parser, sv_parser, IResult, ParseControl, ParserState, Span, SpanInfo};
+pub use sv_parser_parser::{
+    CancellationToken, PackratConfig, PackratEviction, PackratStats, Parse, ProductionStats,
+    Version, DEFAULT_MAX_DEPTH,
+};
 pub use sv_parser_pp::preprocess::{
-    preprocess, preprocess_str, Define, DefineText, Defines, PreprocessedText,
+    preprocess, preprocess_str, preprocess_str_with_control, preprocess_with_control, Define,
+    DefineText, Defines, PreprocessOptions, PreprocessedText,
 };
 pub use sv_parser_syntaxtree::*;
 
@@ -20,29 +47,186 @@ pub struct SyntaxTree {
 
 impl SyntaxTree {
     pub fn get_str<'a, T: Into<RefNodes<'a>>>(&self, nodes: T) -> Option<&str> {
-        let mut beg = None;
-        let mut end = 0;
-        for n in Iter::new(nodes.into()) {
-            if let RefNode::Locate(x) = n {
-                if beg.is_none() {
-                    beg = Some(x.offset);
-                }
-                end = x.offset + x.len;
+        get_str(&self.text, nodes.into())
+    }
+
+    /// Returns the text of `nodes` without their trailing white spaces and comments.
+    ///
+    /// The text of a named declaration is given by such as `tree.get_str_trimmed(x.name())`.
+    pub fn get_str_trimmed<'a, T: Into<RefNodes<'a>>>(&self, nodes: T) -> Option<&str> {
+        let (range, _) = extent(nodes.into(), false)?;
+        self.text.text().get(range)
+    }
+
+    /// Returns the extent of `nodes` including their trailing white spaces and comments.
+    pub fn span<'a, T: Into<RefNodes<'a>>>(&self, nodes: T) -> Option<NodeSpan<'_>> {
+        let (range, line) = extent(nodes.into(), true)?;
+        Some(NodeSpan::new(&self.text, range, line))
+    }
+
+    /// Returns the extent of `nodes` without white spaces and comments.
+    pub fn span_trimmed<'a, T: Into<RefNodes<'a>>>(&self, nodes: T) -> Option<NodeSpan<'_>> {
+        let (range, line) = extent(nodes.into(), false)?;
+        Some(NodeSpan::new(&self.text, range, line))
+    }
+
+    pub fn get_origin(&self, locate: &Locate) -> Option<(&PathBuf, usize)> {
+        self.text.origin(locate.offset)
+    }
+
+    /// Regenerates the parsed text from the tokens and white spaces of the tree.
+    ///
+    /// The parsed text is the source text if parsed with `ParseOptions::keep_macros`,
+    /// otherwise the preprocessed text.
+    pub fn to_source(&self) -> String {
+        let mut ret = Vec::new();
+        self.write_source(&mut ret).unwrap();
+        // The tokens and white spaces are sliced from the parsed text at `char` boundaries
+        String::from_utf8(ret).unwrap()
+    }
+
+    /// Writes the text regenerated by `to_source` to `w`.
+    pub fn write_source<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        for x in self {
+            if let RefNode::Locate(x) = x {
+                w.write_all(x.str(self.text.text()).as_bytes())?;
             }
         }
-        if let Some(beg) = beg {
-            let ret = unsafe { self.text.text().get_unchecked(beg..end) };
-            Some(ret)
+        Ok(())
+    }
+
+    pub fn node(&self) -> &AnyNode {
+        &self.node
+    }
+
+    /// Returns the compact form of the tree with node kinds, spans and token texts.
+    pub fn to_compact(&self) -> CompactNode {
+        CompactNode::new((&self.node).into(), self.text.text())
+    }
+
+    /// Returns the offset in the parsed text where `offset` of the source file `path` is copied.
+    ///
+    /// The first copy is returned if the file is included several times.
+    pub fn position<T: AsRef<Path>>(&self, path: T, offset: usize) -> Option<usize> {
+        self.text.position(path, offset)
+    }
+
+    /// Returns the innermost node at `offset` of the source file `path` followed by its ancestors.
+    ///
+    /// The tree is walked for each call. For repeated lookups, `TreeIndex::node_at_origin`
+    /// of `SyntaxTree::index` finds the node by a binary search.
+    pub fn node_at<T: AsRef<Path>>(&self, path: T, offset: usize) -> Vec<RefNode<'_>> {
+        match self.text.position(path, offset) {
+            Some(x) => covering((&self.node).into(), x..x),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the innermost node covering `range` of the source file `path` followed by its ancestors.
+    ///
+    /// The tree is walked for each call as `SyntaxTree::node_at`, and
+    /// `TreeIndex::nodes_covering_origin` of `SyntaxTree::index` is for repeated lookups.
+    pub fn nodes_covering<T: AsRef<Path>>(
+        &self,
+        path: T,
+        range: std::ops::Range<usize>,
+    ) -> Vec<RefNode<'_>> {
+        let begin = self.text.position(&path, range.start);
+        let end = if range.is_empty() {
+            begin
         } else {
-            None
+            self.text.position(&path, range.end - 1).map(|x| x + 1)
+        };
+        match (begin, end) {
+            (Some(begin), Some(end)) if begin <= end => covering((&self.node).into(), begin..end),
+            _ => Vec::new(),
         }
     }
 
-    pub fn get_origin(&self, locate: &Locate) -> Option<(&PathBuf, usize)> {
-        self.text.origin(locate.offset)
+    /// Returns the nodes matching `query`. See `Query` for the syntax.
+    pub fn query(&self, query: &str) -> Result<Vec<QueryMatch<'_>>, Error> {
+        let query = Query::new(query).map_err(|x| Error::Query {
+            message: x.message,
+            offset: x.offset,
+        })?;
+        Ok(query.matches(&self.index(), self.text.text()))
+    }
+
+    /// Returns the documentation comments of `nodes`. See `DocComment` for the conventions.
+    pub fn doc_comment<'a, T: Into<RefNodes<'a>>>(&self, nodes: T) -> Option<DocComment> {
+        Pieces::new(self).doc_comment(nodes.into())
+    }
+
+    /// Returns the documented declarations with their documentation comments.
+    ///
+    /// Declarations are modules, interfaces, programs, packages, classes, ports, parameters,
+    /// functions, tasks, class items and typedefs. Only the outermost one of the declarations
+    /// beginning at the same token is returned.
+    pub fn doc_comments(&self) -> Vec<(RefNode<'_>, DocComment)> {
+        let pieces = Pieces::new(self);
+        let mut ret = Vec::new();
+        let mut begin = None;
+        for node in self {
+            match node {
+                RefNode::ModuleDeclaration(_)
+                | RefNode::InterfaceDeclaration(_)
+                | RefNode::ProgramDeclaration(_)
+                | RefNode::PackageDeclaration(_)
+                | RefNode::ClassDeclaration(_)
+                | RefNode::AnsiPortDeclaration(_)
+                | RefNode::PortDeclaration(_)
+                | RefNode::ParameterPortDeclaration(_)
+                | RefNode::ParameterDeclaration(_)
+                | RefNode::LocalParameterDeclaration(_)
+                | RefNode::FunctionDeclaration(_)
+                | RefNode::TaskDeclaration(_)
+                | RefNode::ClassItem(_)
+                | RefNode::TypeDeclaration(_) => (),
+                _ => continue,
+            }
+            let start = extent(vec![node.clone()].into(), false).map(|(x, _)| x.start);
+            if start.is_none() || start == begin {
+                continue;
+            }
+            begin = start;
+            if let Some(x) = pieces.doc_comment(vec![node.clone()].into()) {
+                ret.push((node, x));
+            }
+        }
+        ret
+    }
+
+    /// Returns the index of the tree to navigate to parents and siblings of its nodes,
+    /// and to find the nodes at positions of the source files.
+    pub fn index(&self) -> TreeIndex<'_> {
+        TreeIndex::new(vec![RefNode::from(&self.node)])
+            .with_origins(move |path, offset| self.text.position(path, offset))
+    }
+
+    /// Makes a tree of `node` constructed by `builder`, whose text is generated by `Builder::finish`.
+    pub fn build<T: Into<AnyNode>>(builder: Builder, node: T) -> SyntaxTree {
+        let (node, text) = builder.finish(node.into());
+        SyntaxTree {
+            node,
+            text: PreprocessedText::generated(&text),
+        }
+    }
+
+    /// Prints the tree by `printer` with normalized white spaces and indentation.
+    pub fn print(&self, printer: &Printer) -> String {
+        printer.print(vec![RefNode::from(&self.node)], self.text.text())
     }
 }
 
+// `SyntaxTree` can be sent to and shared between threads.
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    #[allow(dead_code)]
+    fn assert() {
+        assert_send_sync::<SyntaxTree>();
+    }
+};
+
 impl fmt::Display for SyntaxTree {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut ret = String::from("");
@@ -92,22 +276,605 @@ impl<'a> IntoIterator for &'a SyntaxTree {
     }
 }
 
+/// Options of `parse_sv_with_options` and its variants.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Keyword version used when no `` `begin_keywords `` directive is active.
+    /// `None` means IEEE 1800-2017.
+    pub default_version: Option<Version>,
+    /// Keyword versions selected by the path of the parsed file.
+    /// A match takes priority over `default_version`.
+    pub version_map: VersionMap,
+    /// Restrict the grammar to IEEE 1364 in regions selected as IEEE 1364.
+    ///
+    /// SystemVerilog-only keywords, operators, casts, literals, user-defined data types and
+    /// end labels don't parse there, and the parse fails with `Error::NotAllowed` listing them.
+    /// Not all SystemVerilog constructs are rejected.
+    /// See `lint::lint_version` of sv-parser-parser for the rejected constructs.
+    pub strict_version: bool,
+    /// Number of threads used by `parse_sv_files`. `0` means the available parallelism.
+    pub threads: usize,
+    /// Parse the source text as written instead of the preprocessed text.
+    ///
+    /// Text macro usages, `` `__FILE__ `` and `` `__LINE__ `` are kept as nodes in expression,
+    /// statement and item positions, and the other compiler directives are kept as white spaces,
+    /// so included files are not parsed. Conditional compilation directives are kept line by line, and all their branches
+    /// are parsed, so each branch has to be complete in its place.
+    /// The source text is still preprocessed for the returned `Defines` on a best-effort basis,
+    /// ignoring macros defined outside of it and the other preprocess errors.
+    pub keep_macros: bool,
+    /// Parse only the headers, port and parameter declarations of modules, interfaces,
+    /// programs, packages and classes.
+    ///
+    /// The other items in their bodies are skipped without parsing and replaced by white spaces
+    /// in the text of the tree, so `SyntaxTree::get_str` of them returns the spaces.
+    pub outline: bool,
+    /// Maximum nesting depth of brackets, statements and items. `None` means unlimited.
+    ///
+    /// Chains of binary and conditional operators aren't nested. Deeper input fails with
+    /// `Error::ExceedNestingLimit`. The parser grows its stack on the heap, so any thread
+    /// can parse up to the default `DEFAULT_MAX_DEPTH`.
+    pub max_depth: Option<usize>,
+    /// Token to cancel the parse from another thread. A cancelled parse fails with `Error::Cancelled`.
+    pub cancel: Option<CancellationToken>,
+    /// Callback receiving the progress of the parse.
+    pub progress: Option<ProgressCallback>,
+    /// Capacity and eviction policy of the packrat memoization.
+    pub packrat: PackratConfig,
+    /// Sink accumulating the packrat statistics of parses. `packrat.stats` is implied by `Some`.
+    pub packrat_stats: Option<Arc<Mutex<PackratStats>>>,
+}
+
+impl std::default::Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            default_version: None,
+            version_map: VersionMap::default(),
+            strict_version: false,
+            threads: 0,
+            keep_macros: false,
+            outline: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            cancel: None,
+            progress: None,
+            packrat: PackratConfig::default(),
+            packrat_stats: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    fn version<T: AsRef<Path>>(&self, path: T) -> Option<Version> {
+        self.version_map.get(path).or(self.default_version)
+    }
+
+    fn threads(&self, jobs: usize) -> usize {
+        let threads = if self.threads == 0 {
+            thread::available_parallelism().map_or(1, |x| x.get())
+        } else {
+            self.threads
+        };
+        threads.min(jobs).max(1)
+    }
+
+    fn span_info<'a>(
+        &self,
+        state: &'a ParserState,
+        version: Option<Version>,
+        control: ParseControl<'a>,
+    ) -> SpanInfo<'a> {
+        SpanInfo {
+            default_version: version,
+            keep_macros: self.keep_macros,
+            max_depth: self.max_depth,
+            strict_version: self.strict_version,
+            control,
+            ..SpanInfo::new(state)
+        }
+    }
+
+    fn control<'a>(&'a self, progress: &'a dyn Fn(usize)) -> ParseControl<'a> {
+        ParseControl {
+            cancel: self.cancel.as_ref(),
+            progress: self.progress.as_ref().map(|_| progress),
+        }
+    }
+
+    fn parser_state(&self) -> ParserState {
+        ParserState::with_packrat(PackratConfig {
+            stats: self.packrat.stats || self.packrat_stats.is_some(),
+            ..self.packrat
+        })
+    }
+
+    fn collect_stats(&self, state: &ParserState) {
+        if let Some(stats) = &self.packrat_stats {
+            stats.lock().unwrap().merge(&state.packrat_stats());
+        }
+    }
+
+    fn report(&self, path: &Path, phase: Phase, bytes: usize, files: usize) {
+        if let Some(progress) = &self.progress {
+            (progress.0)(&Progress {
+                path,
+                phase,
+                bytes,
+                files,
+            });
+        }
+    }
+}
+
+/// Phase of a parse reported by `Progress`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Preprocess,
+    Parse,
+    /// The parse of the file finished successfully or not.
+    Done,
+}
+
+/// Progress of `parse_sv_with_options` and its variants.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress<'a> {
+    pub path: &'a Path,
+    pub phase: Phase,
+    /// Consumed bytes of the file being preprocessed or the preprocessed text being parsed
+    pub bytes: usize,
+    /// Number of files done, including this file if `phase` is `Done`
+    pub files: usize,
+}
+
+/// Callback receiving `Progress` of parses, possibly from several threads.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F: Fn(&Progress) + Send + Sync + 'static>(f: F) -> Self {
+        ProgressCallback(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+/// Mapping from file extensions or glob patterns to keyword versions.
+///
+/// In a pattern, `*` matches any sequence of characters and `?` matches any single character.
+/// A pattern including `/` is matched against the whole path, otherwise against the file name.
+/// The first matching pattern is used.
+#[derive(Clone, Debug, Default)]
+pub struct VersionMap {
+    patterns: Vec<(String, Version)>,
+}
+
+impl VersionMap {
+    pub fn new() -> Self {
+        VersionMap {
+            patterns: Vec::new(),
+        }
+    }
+
+    pub fn insert<T: Into<String>>(&mut self, pattern: T, version: Version) {
+        self.patterns.push((pattern.into(), version));
+    }
+
+    pub fn insert_extension(&mut self, extension: &str, version: Version) {
+        self.insert(format!("*.{}", extension.trim_start_matches('.')), version);
+    }
+
+    pub fn get<T: AsRef<Path>>(&self, path: T) -> Option<Version> {
+        let path = path.as_ref();
+        let full = path.to_string_lossy().replace('\\', "/");
+        let name = path
+            .file_name()
+            .map(|x| x.to_string_lossy())
+            .unwrap_or_default();
+        for (pattern, version) in &self.patterns {
+            let target = if pattern.contains('/') {
+                full.as_str()
+            } else {
+                &*name
+            };
+            if glob_match(pattern, target) {
+                return Some(*version);
+            }
+        }
+        None
+    }
+}
+
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    let (mut i, mut j) = (0, 0);
+    let mut star = None;
+    while j < s.len() {
+        if i < p.len() && (p[i] == '?' || p[i] == s[j]) {
+            i += 1;
+            j += 1;
+        } else if i < p.len() && p[i] == '*' {
+            star = Some((i, j));
+            i += 1;
+        } else if let Some((si, sj)) = star {
+            i = si + 1;
+            j = sj + 1;
+            star = Some((si, sj + 1));
+        } else {
+            return false;
+        }
+    }
+    p[i..].iter().all(|x| *x == '*')
+}
+
 pub fn parse_sv<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     path: T,
     pre_defines: &HashMap<String, Option<Define>, V>,
     include_paths: &[U],
     ignore_include: bool,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let (text, defines) = preprocess(path, pre_defines, include_paths, false, ignore_include)?;
-    parse_sv_pp(text, defines)
+    parse_sv_with_options(
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        &ParseOptions::default(),
+    )
+}
+
+pub fn parse_sv_with_options<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    parse_sv_inner(
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        &AtomicUsize::new(0),
+    )
+}
+
+fn parse_sv_inner<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+    files: &AtomicUsize,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let version = options.version(&path);
+    let ret = preprocess_with_options(
+        &path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        files,
+    )
+    .and_then(|(text, defines)| {
+        parse_sv_pp_inner(text, defines, options, version, path.as_ref(), files)
+    });
+    done(ret, options, path.as_ref(), files)
+}
+
+/// Parses the file as `parse_sv_with_options`, but passes each `StreamItem` to `f` instead of
+/// building a `SyntaxTree`.
+///
+/// Only the preprocessed text and the item being parsed are kept, so huge netlists
+/// can be parsed in bounded memory. `f` receives the preprocessed text to get the strings
+/// and origins of the nodes of each item.
+/// `outline` is not applied.
+pub fn parse_sv_stream<T, U, V, F>(
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+    mut f: F,
+) -> Result<Defines, Error>
+where
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+    V: BuildHasher,
+    F: FnMut(&PreprocessedText, StreamItem),
+{
+    let version = options.version(&path);
+    let files = AtomicUsize::new(0);
+    let ret = preprocess_with_options(
+        &path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        &files,
+    )
+    .and_then(|(text, defines)| {
+        let progress = |x| options.report(path.as_ref(), Phase::Parse, x, 0);
+        let control = options.control(&progress);
+        let state = options.parser_state();
+        let span = Span::new_extra(text.text(), options.span_info(&state, version, control));
+        let result = stream_parser(span, |x| {
+            f(&text, x);
+            true
+        });
+        options.collect_stats(&state);
+        match result {
+            Ok(_) => Ok(defines),
+            Err(x) => Err(parse_error(x, &text, &state)),
+        }
+    });
+    done(ret, options, path.as_ref(), &files)
+}
+
+/// Counts the file as done and reports it.
+fn done<T>(
+    ret: Result<T, Error>,
+    options: &ParseOptions,
+    path: &Path,
+    files: &AtomicUsize,
+) -> Result<T, Error> {
+    let files = files.fetch_add(1, Ordering::SeqCst) + 1;
+    options.report(path, Phase::Done, 0, files);
+    ret
+}
+
+fn preprocess_with_options<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+    files: &AtomicUsize,
+) -> Result<(PreprocessedText, Defines), Error> {
+    if options.keep_macros {
+        let s = fs::read_to_string(path.as_ref()).map_err(|x| Error::File {
+            source: x,
+            path: PathBuf::from(path.as_ref()),
+        })?;
+        preprocess_str_with_options(
+            &s,
+            path,
+            pre_defines,
+            include_paths,
+            ignore_include,
+            options,
+            files,
+        )
+    } else {
+        let progress = |x| {
+            let files = files.load(Ordering::SeqCst);
+            options.report(path.as_ref(), Phase::Preprocess, x, files)
+        };
+        preprocess_with_control(
+            &path,
+            pre_defines,
+            include_paths,
+            false,
+            ignore_include,
+            options.control(&progress),
+        )
+    }
+}
+
+fn preprocess_str_with_options<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+    files: &AtomicUsize,
+) -> Result<(PreprocessedText, Defines), Error> {
+    let progress = |x| {
+        let files = files.load(Ordering::SeqCst);
+        options.report(path.as_ref(), Phase::Preprocess, x, files)
+    };
+    let preprocess = |pre_defines: &Defines| {
+        preprocess_str_with_control(
+            s,
+            &path,
+            pre_defines,
+            include_paths,
+            ignore_include,
+            PreprocessOptions {
+                control: options.control(&progress),
+                ..PreprocessOptions::default()
+            },
+        )
+    };
+    let mut defines: Defines = pre_defines
+        .iter()
+        .map(|(x, y)| (x.clone(), y.clone()))
+        .collect();
+    if !options.keep_macros {
+        return preprocess(&defines);
+    }
+
+    // The source text is parsed as written, so it's preprocessed only for the defines.
+    // Macros defined outside of it are taken as defined without text,
+    // and the other errors leave `pre_defines` unchanged.
+    let mut undefined = Vec::new();
+    loop {
+        match preprocess(&defines) {
+            Ok((_, mut x)) => {
+                for name in &undefined {
+                    if let Some(None) = x.get(name) {
+                        x.remove(name);
+                    }
+                }
+                return Ok((PreprocessedText::verbatim(s, path), x));
+            }
+            Err(Error::DefineNotFound(x)) if !defines.contains_key(&x) => {
+                defines.insert(x.clone(), None);
+                undefined.push(x);
+            }
+            Err(Error::Cancelled) => return Err(Error::Cancelled),
+            Err(_) => {
+                for name in &undefined {
+                    defines.remove(name);
+                }
+                return Ok((PreprocessedText::verbatim(s, path), defines));
+            }
+        }
+    }
+}
+
+/// Compilation unit semantics of `parse_sv_files`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompilationUnit {
+    /// All files are one compilation unit, so `` `define ``s carry forward to the following files.
+    MultiFile,
+    /// Each file is a separate compilation unit.
+    SingleFile,
+}
+
+/// Parses files in parallel and returns the results in the order of `paths`.
+///
+/// In `CompilationUnit::MultiFile` mode, files are preprocessed in order and
+/// the returned `Defines` of each file include the defines of the preceding files.
+/// A file which fails to preprocess doesn't change the defines seen by the following files.
+pub fn parse_sv_files<T, U, V>(
+    paths: &[T],
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    unit: CompilationUnit,
+    options: &ParseOptions,
+) -> Vec<Result<(SyntaxTree, Defines), Error>>
+where
+    T: AsRef<Path> + Sync,
+    U: AsRef<Path> + Sync,
+    V: BuildHasher + Sync,
+{
+    let threads = options.threads(paths.len());
+    let files = AtomicUsize::new(0);
+
+    match unit {
+        CompilationUnit::SingleFile => parallel(paths.len(), threads, |i| {
+            parse_sv_inner(
+                &paths[i],
+                pre_defines,
+                include_paths,
+                ignore_include,
+                options,
+                &files,
+            )
+        }),
+        CompilationUnit::MultiFile => {
+            // Preprocessing is sequential because each file depends on the defines of the preceding files,
+            // but the preprocessed files are parsed in parallel.
+            let mut defines: Defines = pre_defines
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            let mut texts = Vec::new();
+            for path in paths {
+                let ret = preprocess_with_options(
+                    path,
+                    &defines,
+                    include_paths,
+                    ignore_include,
+                    options,
+                    &files,
+                );
+                if let Ok((_, ref new_defines)) = ret {
+                    defines = new_defines.clone();
+                }
+                texts.push(Mutex::new(Some(ret)));
+            }
+            parallel(paths.len(), threads, |i| {
+                let path = paths[i].as_ref();
+                let ret = texts[i].lock().unwrap().take().unwrap();
+                let ret = ret.and_then(|(text, defines)| {
+                    let version = options.version(path);
+                    parse_sv_pp_inner(text, defines, options, version, path, &files)
+                });
+                done(ret, options, path, &files)
+            })
+        }
+    }
+}
+
+/// Calls `f` with indexes from `0` to `len` on `threads` threads, and returns the results in order.
+fn parallel<T, F>(len: usize, threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..len).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= len {
+                    break;
+                }
+                let ret = f(i);
+                results.lock().unwrap()[i] = Some(ret);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|x| x.unwrap())
+        .collect()
 }
 
 pub fn parse_sv_pp(
     text: PreprocessedText,
     defines: Defines,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let span = Span::new_extra(text.text(), SpanInfo::default());
+    parse_sv_pp_with_options(text, defines, &ParseOptions::default())
+}
+
+/// `version_map` is matched against the path of the file which `text` is preprocessed from.
+pub fn parse_sv_pp_with_options(
+    text: PreprocessedText,
+    defines: Defines,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let path = PathBuf::from(text.path().unwrap_or_else(|| Path::new("")));
+    let version = options.version(&path);
+    let files = AtomicUsize::new(0);
+    let ret = parse_sv_pp_inner(text, defines, options, version, &path, &files);
+    done(ret, options, &path, &files)
+}
+
+fn parse_sv_pp_inner(
+    mut text: PreprocessedText,
+    defines: Defines,
+    options: &ParseOptions,
+    version: Option<Version>,
+    path: &Path,
+    files: &AtomicUsize,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let progress = |x| {
+        let files = files.load(Ordering::SeqCst);
+        options.report(path, Phase::Parse, x, files)
+    };
+    let control = options.control(&progress);
+
+    if options.outline {
+        // The outline has the same offsets as the preprocessed text
+        let outline = outline::outline(text.text(), version);
+        text.replace_text(outline);
+    }
+
+    let state = options.parser_state();
+    let span = Span::new_extra(text.text(), options.span_info(&state, version, control));
     let result = all_consuming(sv_parser)(span);
+    options.collect_stats(&state);
     match result {
         Ok((_, x)) => Ok((
             SyntaxTree {
@@ -116,24 +883,119 @@ pub fn parse_sv_pp(
             },
             defines,
         )),
-        Err(x) => {
-            let pos = match x {
-                nom::Err::Incomplete(_) => None,
-                nom::Err::Error(e) => error_position(&e),
-                nom::Err::Failure(e) => error_position(&e),
-            };
-            let origin = if let Some(pos) = pos {
-                if let Some(origin) = text.origin(pos) {
-                    Some((origin.0.clone(), origin.1))
-                } else {
-                    None
+        Err(x) => Err(not_allowed(sv_parser, &text, options, version)
+            .unwrap_or_else(|| parse_error(x, &text, &state))),
+    }
+}
+
+/// Returns the innermost node in `node` covering `range` of the parsed text followed by its ancestors.
+/// An empty range is covered by the `Locate` at its position.
+fn covering(node: RefNode, range: std::ops::Range<usize>) -> Vec<RefNode> {
+    let last = if range.is_empty() {
+        range.start
+    } else {
+        range.end - 1
+    };
+    let contains = |x: &Locate, pos: usize| x.offset <= pos && pos < x.offset + x.len;
+
+    // Entered nodes with their numbers in depth-first order
+    let mut stack: Vec<(usize, RefNode)> = Vec::new();
+    let mut first: Option<Vec<(usize, RefNode)>> = None;
+    for (i, event) in node.into_iter().event().enumerate() {
+        match event {
+            NodeEvent::Enter(x) => {
+                stack.push((i, x.clone()));
+                if let RefNode::Locate(x) = x {
+                    if first.is_none() && contains(x, range.start) {
+                        first = Some(stack.clone());
+                    }
+                    if contains(x, last) {
+                        // The lowest common ancestor of the first and last `Locate`s
+                        let first = match first {
+                            Some(x) => x,
+                            None => return Vec::new(),
+                        };
+                        let common = first
+                            .iter()
+                            .zip(&stack)
+                            .take_while(|(x, y)| x.0 == y.0)
+                            .count();
+                        stack.truncate(common);
+                        return stack.into_iter().rev().map(|x| x.1).collect();
+                    }
                 }
-            } else {
-                None
-            };
-            Err(Error::Parse(origin))
+            }
+            NodeEvent::Leave(_) => {
+                stack.pop();
+            }
         }
     }
+    Vec::new()
+}
+
+fn parse_error(
+    x: nom::Err<nom_greedyerror::GreedyError<Span>>,
+    text: &PreprocessedText,
+    state: &ParserState,
+) -> Error {
+    if state.cancelled() {
+        return Error::Cancelled;
+    }
+    if let Some(pos) = state.exceeded_depth() {
+        let origin = text.origin(pos).map(|(x, y)| (x.clone(), y));
+        return Error::ExceedNestingLimit(origin);
+    }
+    let pos = match x {
+        nom::Err::Incomplete(_) => None,
+        nom::Err::Error(e) => error_position(&e),
+        nom::Err::Failure(e) => error_position(&e),
+    };
+    let origin = if let Some(pos) = pos {
+        if let Some(origin) = text.origin(pos) {
+            Some((origin.0.clone(), origin.1))
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    Error::Parse(origin)
+}
+
+/// Reparses `text` failed with `strict_version` by the unrestricted grammar,
+/// and returns the SystemVerilog constructs rejected by the restriction.
+fn not_allowed<F, O>(
+    f: F,
+    text: &PreprocessedText,
+    options: &ParseOptions,
+    version: Option<Version>,
+) -> Option<Error>
+where
+    F: Fn(Span) -> IResult<Span, O>,
+    for<'a> &'a O: Into<RefNode<'a>>,
+{
+    if !options.strict_version {
+        return None;
+    }
+    let state = options.parser_state();
+    let info = SpanInfo {
+        strict_version: false,
+        ..options.span_info(&state, version, ParseControl::default())
+    };
+    let (_, x) = all_consuming(f)(Span::new_extra(text.text(), info)).ok()?;
+    let violations: Vec<_> = lint_version((&x).into(), text.text(), version)
+        .into_iter()
+        .map(|x| NotAllowed {
+            construct: x.construct,
+            version: x.version.to_string(),
+            origin: text.origin(x.offset).map(|(x, y)| (x.clone(), y)),
+        })
+        .collect();
+    if violations.is_empty() {
+        None
+    } else {
+        Some(Error::NotAllowed(violations))
+    }
 }
 
 pub fn parse_sv_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
@@ -143,16 +1005,39 @@ pub fn parse_sv_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     include_paths: &[U],
     ignore_include: bool,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let (text, defines) = preprocess_str(
+    parse_sv_str_with_options(
         s,
         path,
         pre_defines,
         include_paths,
         ignore_include,
-        false,
-        0,
-    )?;
-    parse_sv_pp(text, defines)
+        &ParseOptions::default(),
+    )
+}
+
+pub fn parse_sv_str_with_options<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let version = options.version(&path);
+    let files = AtomicUsize::new(0);
+    let ret = preprocess_str_with_options(
+        s,
+        &path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        &files,
+    )
+    .and_then(|(text, defines)| {
+        parse_sv_pp_inner(text, defines, options, version, path.as_ref(), &files)
+    });
+    done(ret, options, path.as_ref(), &files)
 }
 
 pub fn parse_lib<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
@@ -161,8 +1046,40 @@ pub fn parse_lib<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     include_paths: &[U],
     ignore_include: bool,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let (text, defines) = preprocess(path, pre_defines, include_paths, false, ignore_include)?;
-    parse_lib_pp(text, defines)
+    parse_lib_with_options(
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        &ParseOptions::default(),
+    )
+}
+
+pub fn parse_lib_with_options<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let options = &ParseOptions {
+        keep_macros: false,
+        ..options.clone()
+    };
+    let version = options.version(&path);
+    let files = AtomicUsize::new(0);
+    let ret = preprocess_with_options(
+        &path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        &files,
+    )
+    .and_then(|(text, defines)| {
+        parse_lib_pp_inner(text, defines, options, version, path.as_ref(), &files)
+    });
+    done(ret, options, path.as_ref(), &files)
 }
 
 pub fn parse_lib_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
@@ -172,24 +1089,83 @@ pub fn parse_lib_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     include_paths: &[U],
     ignore_include: bool,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let (text, defines) = preprocess_str(
+    parse_lib_str_with_options(
         s,
         path,
         pre_defines,
         include_paths,
         ignore_include,
-        false,
-        0,
-    )?;
-    parse_lib_pp(text, defines)
+        &ParseOptions::default(),
+    )
+}
+
+pub fn parse_lib_str_with_options<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let options = &ParseOptions {
+        keep_macros: false,
+        ..options.clone()
+    };
+    let version = options.version(&path);
+    let files = AtomicUsize::new(0);
+    let ret = preprocess_str_with_options(
+        s,
+        &path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        &files,
+    )
+    .and_then(|(text, defines)| {
+        parse_lib_pp_inner(text, defines, options, version, path.as_ref(), &files)
+    });
+    done(ret, options, path.as_ref(), &files)
 }
 
 pub fn parse_lib_pp(
     text: PreprocessedText,
     defines: Defines,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let span = Span::new_extra(text.text(), SpanInfo::default());
+    parse_lib_pp_with_options(text, defines, &ParseOptions::default())
+}
+
+/// `version_map` is matched against the path of the file which `text` is preprocessed from.
+/// `strict_version`, `keep_macros` and `outline` are not applied to library map files.
+pub fn parse_lib_pp_with_options(
+    text: PreprocessedText,
+    defines: Defines,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let path = PathBuf::from(text.path().unwrap_or_else(|| Path::new("")));
+    let version = options.version(&path);
+    let files = AtomicUsize::new(0);
+    let ret = parse_lib_pp_inner(text, defines, options, version, &path, &files);
+    done(ret, options, &path, &files)
+}
+
+fn parse_lib_pp_inner(
+    text: PreprocessedText,
+    defines: Defines,
+    options: &ParseOptions,
+    version: Option<Version>,
+    path: &Path,
+    files: &AtomicUsize,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let progress = |x| {
+        let files = files.load(Ordering::SeqCst);
+        options.report(path, Phase::Parse, x, files)
+    };
+    let control = options.control(&progress);
+    let state = options.parser_state();
+    let span = Span::new_extra(text.text(), options.span_info(&state, version, control));
     let result = all_consuming(lib_parser)(span);
+    options.collect_stats(&state);
     match result {
         Ok((_, x)) => Ok((
             SyntaxTree {
@@ -198,26 +1174,93 @@ pub fn parse_lib_pp(
             },
             defines,
         )),
-        Err(x) => {
-            let pos = match x {
-                nom::Err::Incomplete(_) => None,
-                nom::Err::Error(e) => error_position(&e),
-                nom::Err::Failure(e) => error_position(&e),
-            };
-            let origin = if let Some(pos) = pos {
-                if let Some(origin) = text.origin(pos) {
-                    Some((origin.0.clone(), origin.1))
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-            Err(Error::Parse(origin))
+        Err(x) => Err(parse_error(x, &text, &state)),
+    }
+}
+
+/// A production parsed by `parse_construct_str`.
+pub struct Parsed<T> {
+    node: T,
+    text: PreprocessedText,
+}
+
+impl<T> Parsed<T> {
+    pub fn node(&self) -> &T {
+        &self.node
+    }
+
+    pub fn into_node(self) -> T {
+        self.node
+    }
+
+    pub fn get_str<'a, U: Into<RefNodes<'a>>>(&self, nodes: U) -> Option<&str> {
+        get_str(&self.text, nodes.into())
+    }
+
+    pub fn get_origin(&self, locate: &Locate) -> Option<(&PathBuf, usize)> {
+        self.text.origin(locate.offset)
+    }
+}
+
+impl<T: Into<AnyNode>> From<Parsed<T>> for SyntaxTree {
+    fn from(x: Parsed<T>) -> Self {
+        SyntaxTree {
+            node: x.node.into(),
+            text: x.text,
         }
     }
 }
 
+/// Returns the text of `nodes` including their trailing white spaces and comments.
+fn get_str<'a>(text: &'a PreprocessedText, nodes: RefNodes) -> Option<&'a str> {
+    let (range, _) = extent(nodes, true)?;
+    text.text().get(range)
+}
+
+/// Parses `s` as a single production `P` such as `Expression` or `Statement`.
+///
+/// `s` is preprocessed as `parse_sv_str_with_options`.
+/// Leading white spaces are skipped, and trailing white spaces are included in the last token.
+pub fn parse_construct_str<P, T, U, V>(
+    s: &str,
+    path: T,
+    pre_defines: &HashMap<String, Option<Define>, V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    options: &ParseOptions,
+) -> Result<(Parsed<P>, Defines), Error>
+where
+    P: Parse,
+    for<'a> &'a P: Into<RefNode<'a>>,
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+    V: BuildHasher,
+{
+    let version = options.version(&path);
+    let (text, defines) = preprocess_str_with_options(
+        s,
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        options,
+        &AtomicUsize::new(0),
+    )?;
+    let state = options.parser_state();
+    let control = ParseControl {
+        cancel: options.cancel.as_ref(),
+        progress: None,
+    };
+    let span = Span::new_extra(text.text(), options.span_info(&state, version, control));
+    let result = all_consuming(P::parse)(span);
+    options.collect_stats(&state);
+    match result {
+        Ok((_, x)) => Ok((Parsed { node: x, text }, defines)),
+        Err(x) => Err(not_allowed(P::parse, &text, options, version)
+            .unwrap_or_else(|| parse_error(x, &text, &state))),
+    }
+}
+
 #[macro_export]
 macro_rules! unwrap_node {
     ($n:expr, $( $ty:tt ),+) => {{
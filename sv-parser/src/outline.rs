@@ -0,0 +1,284 @@
+use crate::*;
+
+const ELEMENTS: &[&str] = &[
+    "module",
+    "macromodule",
+    "interface",
+    "program",
+    "package",
+    "class",
+];
+
+const OPENERS: &[&str] = &[
+    "begin",
+    "fork",
+    "function",
+    "task",
+    "case",
+    "casex",
+    "casez",
+    "randcase",
+    "randsequence",
+    "generate",
+    "specify",
+    "covergroup",
+    "clocking",
+    "property",
+    "sequence",
+    "checker",
+    "config",
+    "primitive",
+    "table",
+    "module",
+    "macromodule",
+    "interface",
+    "program",
+    "package",
+    "class",
+];
+
+const CLOSERS: &[&str] = &[
+    "end",
+    "join",
+    "join_any",
+    "join_none",
+    "endfunction",
+    "endtask",
+    "endcase",
+    "endsequence",
+    "endgenerate",
+    "endspecify",
+    "endgroup",
+    "endclocking",
+    "endproperty",
+    "endchecker",
+    "endconfig",
+    "endprimitive",
+    "endtable",
+    "endmodule",
+    "endinterface",
+    "endprogram",
+    "endpackage",
+    "endclass",
+];
+
+const PORTS_AND_PARAMETERS: &[&str] =
+    &["input", "output", "inout", "ref", "parameter", "localparam"];
+
+const OPEN: &[&str] = &["(", "[", "{", "'{"];
+const CLOSE: &[&str] = &[")", "]", "}"];
+
+/// Returns `text` with the bodies of design elements replaced by white spaces.
+///
+/// Headers, port and parameter declarations and nested design elements are kept,
+/// and the offsets and lines of the kept text are unchanged.
+/// Bodies are skipped by balancing keywords like `begin`/`end` without parsing.
+pub(crate) fn outline(text: &str, version: Option<Version>) -> String {
+    let tokens = Tokenizer::new(text, version)
+        .filter(|x| x.kind != TokenKind::WhiteSpace && x.kind != TokenKind::Comment)
+        .collect();
+    let mut outline = Outline {
+        text,
+        tokens,
+        blanks: Vec::new(),
+    };
+
+    let mut i = 0;
+    while i < outline.tokens.len() {
+        i = if outline.is_element(i) {
+            outline.element(i)
+        } else {
+            i + 1
+        };
+    }
+    outline.blank_text()
+}
+
+struct Outline<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    /// Ranges of the text replaced by white spaces in ascending order
+    blanks: Vec<(usize, usize)>,
+}
+
+impl<'a> Outline<'a> {
+    fn str(&self, i: usize) -> &'a str {
+        self.tokens.get(i).map_or("", |x| x.locate.str(self.text))
+    }
+
+    fn is_kind(&self, i: usize, kind: TokenKind) -> bool {
+        self.tokens.get(i).is_some_and(|x| x.kind == kind)
+    }
+
+    fn is_keyword(&self, i: usize, keywords: &[&str]) -> bool {
+        self.is_kind(i, TokenKind::Keyword) && keywords.contains(&self.str(i))
+    }
+
+    fn is_operator(&self, i: usize, operators: &[&str]) -> bool {
+        self.is_kind(i, TokenKind::Operator) && operators.contains(&self.str(i))
+    }
+
+    fn begin(&self, i: usize) -> usize {
+        self.tokens
+            .get(i)
+            .map_or(self.text.len(), |x| x.locate.offset)
+    }
+
+    fn end(&self, i: usize) -> usize {
+        self.tokens
+            .get(i)
+            .map_or(self.text.len(), |x| x.locate.offset + x.locate.len)
+    }
+
+    /// Whether the keyword at `i` begins a design element, not a prototype or a type.
+    fn is_element(&self, i: usize) -> bool {
+        if !self.is_keyword(i, ELEMENTS) {
+            return false;
+        }
+        let mut prev = i.wrapping_sub(1);
+        match self.str(i) {
+            "interface"
+                if self.is_keyword(prev, &["virtual"]) || self.is_keyword(i + 1, &["class"]) =>
+            {
+                return false;
+            }
+            "class" if self.is_keyword(prev, &["interface", "virtual"]) => {
+                prev = prev.wrapping_sub(1);
+            }
+            _ => (),
+        }
+        !self.is_keyword(prev, &["extern", "typedef"])
+    }
+
+    /// Whether the keyword at `i` in the item beginning at `item` needs the corresponding end keyword.
+    fn is_opener(&self, i: usize, item: usize) -> bool {
+        if !self.is_keyword(i, OPENERS) {
+            return false;
+        }
+        let prev = i.wrapping_sub(1);
+        match self.str(i) {
+            "fork" => !self.is_keyword(prev, &["wait", "disable"]),
+            "function" | "task" => {
+                // Prototypes may follow attributes and qualifiers
+                !(item..i).any(|x| self.is_keyword(x, &["extern", "pure", "import", "export"]))
+            }
+            "clocking" => {
+                !(self.is_kind(i + 1, TokenKind::Identifier) && self.is_operator(i + 2, &[";"]))
+            }
+            "property" | "sequence" => {
+                !self.is_keyword(prev, &["assert", "assume", "cover", "restrict", "expect"])
+            }
+            x if ELEMENTS.contains(&x) => self.is_element(i),
+            _ => true,
+        }
+    }
+
+    /// Skips the attribute instances at `i`.
+    fn attributes(&self, mut i: usize) -> usize {
+        while self.is_operator(i, &["("]) && self.is_operator(i + 1, &["*"]) {
+            i += 2;
+            while i < self.tokens.len()
+                && !(self.is_operator(i, &["*"]) && self.is_operator(i + 1, &[")"]))
+            {
+                i += 1;
+            }
+            i += 2;
+        }
+        i
+    }
+
+    /// Skips the label after an end keyword.
+    fn label(&self, i: usize) -> usize {
+        if self.is_operator(i, &[":"]) && self.is_kind(i + 1, TokenKind::Identifier) {
+            i + 2
+        } else {
+            i
+        }
+    }
+
+    /// Blanks the body of the design element beginning at `i`, and returns the index after its end keyword.
+    fn element(&mut self, i: usize) -> usize {
+        // The header ends at `;` except `;`s of package import declarations
+        let mut j = i + 1;
+        let mut paren = 0;
+        let mut import = false;
+        while j < self.tokens.len() {
+            if self.is_operator(j, OPEN) {
+                paren += 1;
+            } else if self.is_operator(j, CLOSE) {
+                paren -= 1;
+            } else if paren == 0 && self.is_keyword(j, &["import"]) {
+                import = true;
+            } else if paren == 0 && self.is_operator(j, &[";"]) {
+                if !import {
+                    break;
+                }
+                import = false;
+            }
+            j += 1;
+        }
+        let mut cursor = self.end(j);
+        j += 1;
+
+        let mut item = j;
+        let mut depth = 0;
+        paren = 0;
+        while j < self.tokens.len() {
+            if self.is_operator(j, OPEN) {
+                paren += 1;
+            } else if self.is_operator(j, CLOSE) {
+                paren -= 1;
+            } else if paren == 0 {
+                if depth == 0 && self.is_element(j) {
+                    self.blanks.push((cursor, self.begin(item)));
+                    j = self.element(j);
+                    j = self.label(j);
+                    cursor = self.end(j - 1);
+                    item = j;
+                    continue;
+                } else if self.is_keyword(j, CLOSERS) {
+                    if depth == 0 {
+                        self.blanks.push((cursor, self.begin(j)));
+                        return j + 1;
+                    }
+                    depth -= 1;
+                    j = self.label(j + 1);
+                    item = j;
+                    continue;
+                } else if self.is_opener(j, item) {
+                    depth += 1;
+                } else if self.is_operator(j, &[";"]) {
+                    if depth == 0 && self.is_keyword(self.attributes(item), PORTS_AND_PARAMETERS) {
+                        self.blanks.push((cursor, self.begin(item)));
+                        cursor = self.end(j);
+                    }
+                    item = j + 1;
+                }
+            }
+            j += 1;
+        }
+        self.blanks.push((cursor, self.text.len()));
+        j
+    }
+
+    fn blank_text(&self) -> String {
+        let mut ret = String::with_capacity(self.text.len());
+        let mut pos = 0;
+        for &(begin, end) in &self.blanks {
+            if begin < pos || end <= begin {
+                continue;
+            }
+            ret.push_str(&self.text[pos..begin]);
+            for c in self.text[begin..end].chars() {
+                if c == '\n' {
+                    ret.push('\n');
+                } else {
+                    ret.push_str(&" ".repeat(c.len_utf8()));
+                }
+            }
+            pos = end;
+        }
+        ret.push_str(&self.text[pos..]);
+        ret
+    }
+}
@@ -0,0 +1,103 @@
+use crate::*;
+
+/// Rewriting of a `SyntaxTree` by replacements, insertions and deletions of its nodes.
+///
+/// The text of nodes is edited without their leading and trailing white spaces and comments,
+/// and the other text is kept as is.
+pub struct Rewriter<'a> {
+    tree: &'a SyntaxTree,
+    /// Edits of the parsed text in the recorded order
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Rewriter<'a> {
+    pub fn new(tree: &'a SyntaxTree) -> Self {
+        Rewriter {
+            tree,
+            edits: Vec::new(),
+        }
+    }
+
+    /// Replaces `nodes` by `text`. Nodes without text are ignored.
+    pub fn replace<'b, T: Into<RefNodes<'b>>>(&mut self, nodes: T, text: &str) {
+        if let Some((range, _)) = extent(nodes.into(), false) {
+            self.push(range, text);
+        }
+    }
+
+    pub fn delete<'b, T: Into<RefNodes<'b>>>(&mut self, nodes: T) {
+        self.replace(nodes, "");
+    }
+
+    /// Inserts `text` before `nodes`. Insertions at the same position are kept in the recorded order.
+    pub fn insert_before<'b, T: Into<RefNodes<'b>>>(&mut self, nodes: T, text: &str) {
+        if let Some((range, _)) = extent(nodes.into(), false) {
+            self.push(range.start..range.start, text);
+        }
+    }
+
+    pub fn insert_after<'b, T: Into<RefNodes<'b>>>(&mut self, nodes: T, text: &str) {
+        if let Some((range, _)) = extent(nodes.into(), false) {
+            self.push(range.end..range.end, text);
+        }
+    }
+
+    fn push(&mut self, range: std::ops::Range<usize>, text: &str) {
+        self.edits.push(TextEdit {
+            range,
+            text: String::from(text),
+        });
+    }
+
+    /// Returns the edits sorted by their positions in the parsed text.
+    fn sorted(&self) -> Result<Vec<&TextEdit>, Error> {
+        let mut ret: Vec<_> = self.edits.iter().collect();
+        // The stable sort keeps the recorded order of insertions at the same position
+        ret.sort_by_key(|x| (x.range.start, !x.range.is_empty()));
+        for x in ret.windows(2) {
+            if x[0].range.end > x[1].range.start {
+                return Err(Error::OverlappingEdits(x[1].range.start));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Returns the parsed text with the edits applied.
+    pub fn to_source(&self) -> Result<String, Error> {
+        let text = self.tree.text.text();
+        let mut ret = String::with_capacity(text.len());
+        let mut pos = 0;
+        for x in self.sorted()? {
+            ret.push_str(&text[pos..x.range.start]);
+            ret.push_str(&x.text);
+            pos = x.range.end;
+        }
+        ret.push_str(&text[pos..]);
+        Ok(ret)
+    }
+
+    /// Returns the edits in the coordinates of the source files, sorted by paths and positions.
+    ///
+    /// Fails if an edited node isn't copied verbatim from a source file,
+    /// for example if it's expanded from a text macro.
+    pub fn file_edits(&self) -> Result<Vec<(PathBuf, TextEdit)>, Error> {
+        let mut ret = Vec::new();
+        for x in self.sorted()? {
+            let (path, range) = self
+                .tree
+                .text
+                .origin_range(x.range.clone())
+                .ok_or(Error::EditOrigin(x.range.start))?;
+            ret.push((
+                path.clone(),
+                TextEdit {
+                    range,
+                    text: x.text.clone(),
+                },
+            ));
+        }
+        // Edits of an included file may be separated by the other files
+        ret.sort_by(|x, y| x.0.cmp(&y.0));
+        Ok(ret)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::*;
+use std::ops::Range;
+
+/// Extent of nodes in the parsed text and in their source file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeSpan<'a> {
+    /// Byte range in the parsed text
+    pub range: Range<usize>,
+    /// Line of the first byte in the parsed text
+    pub start_line: u32,
+    /// Line of the last byte in the parsed text
+    pub end_line: u32,
+    /// Source file and byte range in it.
+    /// `None` if the first and last bytes aren't copied from the same file.
+    pub origin: Option<(&'a PathBuf, Range<usize>)>,
+}
+
+impl<'a> NodeSpan<'a> {
+    pub(crate) fn new(text: &'a PreprocessedText, range: Range<usize>, line: u32) -> Self {
+        let last = range.end.saturating_sub(1).max(range.start);
+        let end_line = line + text.text()[range.start..last].matches('\n').count() as u32;
+        let origin = match (text.origin(range.start), text.origin(last)) {
+            (Some((path, begin)), Some((end_path, end)))
+                if path == end_path && begin <= end + 1 =>
+            {
+                let end = if range.is_empty() { begin } else { end + 1 };
+                Some((path, begin..end))
+            }
+            _ => None,
+        };
+        NodeSpan {
+            range,
+            start_line: line,
+            end_line,
+            origin,
+        }
+    }
+}
+
+/// Returns the byte range of the text of `nodes` and the line of its first byte.
+///
+/// White spaces and comments are excluded unless `trivia`.
+pub(crate) fn extent(nodes: RefNodes, trivia: bool) -> Option<(Range<usize>, u32)> {
+    let mut ret: Option<(Range<usize>, u32)> = None;
+    let mut in_white_space = 0;
+    for event in Iter::new(nodes).event() {
+        match event {
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => in_white_space += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => in_white_space -= 1,
+            NodeEvent::Enter(RefNode::Locate(x)) if trivia || in_white_space == 0 => {
+                let end = x.offset + x.len;
+                ret = Some(match ret {
+                    Some((y, line)) => (y.start..end, line),
+                    None => (x.offset..end, x.line),
+                });
+            }
+            _ => (),
+        }
+    }
+    ret
+}
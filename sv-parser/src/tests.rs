@@ -0,0 +1,937 @@
+#![cfg(test)]
+
+extern crate self as sv_parser;
+
+use crate::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn testcase(s: &str) -> PathBuf {
+    PathBuf::from(format!("{}/testcases/{}", env!("CARGO_MANIFEST_DIR"), s))
+}
+
+fn module_name(tree: &SyntaxTree) -> Option<String> {
+    let id = unwrap_node!(tree, ModuleIdentifier)?;
+    let id = unwrap_locate!(id)?;
+    tree.get_str(id).map(String::from)
+}
+
+#[test]
+fn test_parse_sv_files() {
+    let paths = [
+        testcase("test1.sv"),
+        testcase("test3.sv"),
+        testcase("test4.sv"),
+        testcase("test5.sv"),
+    ];
+    let options = ParseOptions {
+        threads: 2,
+        ..ParseOptions::default()
+    };
+    let parse = |unit| {
+        parse_sv_files(
+            &paths,
+            &HashMap::new(),
+            &[] as &[PathBuf],
+            false,
+            unit,
+            &options,
+        )
+    };
+
+    let ret = parse(CompilationUnit::MultiFile);
+    assert_eq!(ret.len(), 4);
+    let names: Vec<_> = ret[..3]
+        .iter()
+        .map(|x| module_name(&x.as_ref().unwrap().0))
+        .collect();
+    assert_eq!(
+        names,
+        [Some("dimm".into()), Some("c".into()), Some("d".into())]
+    );
+    // `W` of test3.sv carries forward to test4.sv
+    assert!(ret[2].as_ref().unwrap().1.contains_key("W"));
+    match &ret[3] {
+        Err(Error::Parse(Some((path, pos)))) => {
+            assert_eq!(path, &paths[3]);
+            assert_eq!(*pos, 12);
+        }
+        x => panic!("{:?}", x.as_ref().map(|_| ())),
+    }
+
+    let ret = parse(CompilationUnit::SingleFile);
+    assert_eq!(ret.len(), 4);
+    assert!(ret[0].is_ok());
+    assert!(ret[1].is_ok());
+    match &ret[2] {
+        Err(Error::DefineNotFound(x)) => assert_eq!(x, "W"),
+        x => panic!("{:?}", x.as_ref().map(|_| ())),
+    }
+    assert!(matches!(ret[3], Err(Error::Parse(_))));
+}
+
+/// Applies `edits` by `IncrementalParser`, and checks each reparse is incremental and
+/// equal to a full parse.
+fn check_incremental(text: &str, edits: &[(&str, &str)], options: ParseOptions) {
+    let edits: Vec<_> = edits.iter().map(|&(x, y)| (x, y, true)).collect();
+    check_edits(text, &edits, options);
+}
+
+/// Applies `edits` by `IncrementalParser`, and checks each reparse is equal to a full parse.
+/// The third element of each edit is whether it is reparsed incrementally.
+fn check_edits(text: &str, edits: &[(&str, &str, bool)], options: ParseOptions) {
+    let mut parser = IncrementalParser::new(
+        text,
+        "test.sv",
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+        &options,
+    );
+    parser.parse().unwrap();
+
+    for (from, to, incremental) in edits {
+        let begin = parser.source().find(from).unwrap();
+        let edit = TextEdit {
+            range: begin..begin + from.len(),
+            text: String::from(*to),
+        };
+        assert_eq!(parser.edit(&edit).unwrap(), *incremental, "{:?}", edit);
+
+        let (expected, _) = parse_sv_str_with_options(
+            parser.source(),
+            "test.sv",
+            &HashMap::new(),
+            &[] as &[PathBuf],
+            false,
+            &options,
+        )
+        .unwrap();
+        let tree = parser.syntax_tree().unwrap();
+        assert_eq!(format!("{:?}", tree.node), format!("{:?}", expected.node));
+        assert_eq!(tree.text.text(), expected.text.text());
+        for i in 0..expected.text.text().len() {
+            assert_eq!(tree.text.origin(i), expected.text.origin(i));
+        }
+    }
+}
+
+#[test]
+fn test_incremental_parser() {
+    let text = r##"module a;
+  logic b, c;
+  always_comb begin
+    b = c;
+  end
+endmodule
+module d(input e);
+  assign f = e;
+endmodule
+"##;
+    check_incremental(
+        text,
+        &[
+            ("b = c;", "b = c + 1;"),
+            ("  end\n", "    c = 0;\n  end\n"),
+            ("assign f = e;", "assign f = ~e;\n  assign g = f;"),
+            ("logic b, c;", "logic b;\n  logic c;"),
+        ],
+        ParseOptions::default(),
+    );
+}
+
+#[test]
+fn test_incremental_parser_begin_keywords() {
+    // `logic` is an identifier in the module `a`
+    let text = r##"`begin_keywords "1364-2005"
+module a;
+  reg logic;
+  initial logic = 1;
+endmodule
+`end_keywords
+module b;
+  logic c;
+  initial c = 1;
+endmodule
+"##;
+    check_incremental(
+        text,
+        &[("logic = 1", "logic = logic + 1"), ("c = 1", "c = 2")],
+        ParseOptions {
+            strict_version: true,
+            ..ParseOptions::default()
+        },
+    );
+}
+
+#[test]
+fn test_incremental_parser_token_boundary() {
+    let text = r##"module a;
+  logic b, c; // comment
+  initial $display("b = %d", b);
+  /* comment */ assign c = b;
+endmodule
+module d;
+endmodule
+"##;
+    check_edits(
+        text,
+        &[
+            ("logic b, c;", "logic b, c, e;", true),
+            ("(\"b = %d\", b)", "(\"b = %d\", b + 1)", true),
+            ("assign c = b;", "assign c = ~b;", true),
+            ("comment\n", "comment e\n", true),
+            ("\"b = %d\"", "\"b, c = %d\"", true),
+            ("assign c", "// assign c", false),
+            ("// assign c", "assign c", true),
+            ("= ~b", "= `__LINE__", false),
+            ("= `__LINE__", "= b", false),
+            ("logic b", "logic \\b ", false),
+        ],
+        ParseOptions::default(),
+    );
+}
+
+#[test]
+fn test_incremental_parser_edits() {
+    let text = r##"module a;
+  logic b;
+endmodule
+module c;
+  logic d;
+endmodule
+`begin_keywords "1364-2005"
+module e;
+  reg logic;
+endmodule
+`end_keywords
+"##;
+    let mut parser = IncrementalParser::new(
+        text,
+        "test.sv",
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+        &ParseOptions {
+            strict_version: true,
+            ..ParseOptions::default()
+        },
+    );
+    parser.parse().unwrap();
+    for (from, to) in &[
+        ("logic b;", "logic b;\n  logic f;"),
+        ("logic d;", "logic d, g;"),
+        ("logic b;", "logic b, h;"),
+        ("reg logic;", "reg logic;\n  initial logic = 1;"),
+    ] {
+        let begin = parser.source().find(from).unwrap();
+        let edit = TextEdit {
+            range: begin..begin + from.len(),
+            text: String::from(*to),
+        };
+        assert!(parser.edit(&edit).unwrap(), "{:?}", edit);
+    }
+
+    let (expected, _) = parse_sv_str_with_options(
+        parser.source(),
+        "test.sv",
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+        &ParseOptions {
+            strict_version: true,
+            ..ParseOptions::default()
+        },
+    )
+    .unwrap();
+    let tree = parser.syntax_tree().unwrap();
+    assert_eq!(format!("{:?}", tree.node), format!("{:?}", expected.node));
+    assert_eq!(tree.text.text(), expected.text.text());
+}
+
+#[test]
+fn test_strict_version() {
+    let options = ParseOptions {
+        default_version: Some(Version::Ieee1364_2005),
+        strict_version: true,
+        ..ParseOptions::default()
+    };
+    let parse = |text: &str| {
+        parse_sv_str_with_options(
+            text,
+            "test.sv",
+            &HashMap::new(),
+            &[] as &[PathBuf],
+            false,
+            &options,
+        )
+    };
+
+    let text = "module a;\n  reg logic;\n  initial begin : b\n    logic = 1;\n  end\nendmodule\n";
+    assert!(parse(text).is_ok());
+
+    let text = "module a;\n  always_ff @(posedge c) d <= e;\n  initial f++;\nendmodule : a\n";
+    match parse(text) {
+        Err(Error::NotAllowed(x)) => {
+            let x: Vec<_> = x
+                .iter()
+                .map(|x| (x.construct.as_str(), x.version.as_str(), x.origin.clone()))
+                .collect();
+            let origin = |x| Some((PathBuf::from("test.sv"), x));
+            assert_eq!(
+                x,
+                vec![
+                    ("always_ff", "1364-2005", origin(12)),
+                    ("++", "1364-2005", origin(54)),
+                    ("endmodule : a", "1364-2005", origin(58)),
+                ]
+            );
+        }
+        x => panic!("{:?}", x.map(|_| ())),
+    }
+
+    // A syntax error is not reported as `NotAllowed`
+    let text = "module a;\n  always_ff @(posedge c) d <= ;\nendmodule\n";
+    assert!(matches!(parse(text), Err(Error::Parse(_))));
+}
+
+#[test]
+fn test_keep_macros() {
+    let options = ParseOptions {
+        keep_macros: true,
+        ..ParseOptions::default()
+    };
+    let parse = |text: &str| {
+        parse_sv_str_with_options(
+            text,
+            "test.sv",
+            &HashMap::new(),
+            &[] as &[PathBuf],
+            false,
+            &options,
+        )
+    };
+
+    let text = "`define W 8\nmodule a;\n  logic [`W-1:0] b;\nendmodule\n";
+    let (tree, defines) = parse(text).unwrap();
+    assert_eq!(tree.to_source(), text);
+    assert!(defines.contains_key("W"));
+    assert!(unwrap_node!(&tree, TextMacroUsage).is_some());
+
+    // Conditional directives are kept, and all the branches are parsed
+    let text = "`ifndef A_SV\n`define A_SV\nmodule a;\n`ifdef W\n  logic b;\n`else\n  logic c;\n`endif\nendmodule\n`endif\n";
+    let (tree, defines) = parse(text).unwrap();
+    assert_eq!(tree.to_source(), text);
+    assert!(defines.contains_key("A_SV"));
+    let lines = (&tree)
+        .into_iter()
+        .filter(|x| matches!(x, RefNode::ConditionalDirectiveLine(_)))
+        .count();
+    assert_eq!(lines, 5);
+    assert_eq!(
+        (&tree)
+            .into_iter()
+            .filter(|x| matches!(x, RefNode::DataDeclaration(_)))
+            .count(),
+        2
+    );
+
+    // Macros defined outside of the file don't fail the parse
+    let text = "`define G 1\nmodule a;\n  logic [`W-1:0] b;\n  initial `uvm_info(\"a\", \"b\", UVM_LOW)\n  assign b = `F(c);\nendmodule\n";
+    let (tree, defines) = parse(text).unwrap();
+    assert_eq!(tree.to_source(), text);
+    assert!(defines.contains_key("G"));
+    assert!(!defines.contains_key("W"));
+    assert!(!defines.contains_key("uvm_info"));
+
+    // Defines carry forward to the following files
+    let paths = [testcase("test3.sv"), testcase("test4.sv")];
+    let ret = parse_sv_files(
+        &paths,
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+        CompilationUnit::MultiFile,
+        &options,
+    );
+    let (tree, defines) = ret[1].as_ref().unwrap();
+    assert!(defines.contains_key("W"));
+    assert_eq!(tree.to_source(), fs::read_to_string(&paths[1]).unwrap());
+}
+
+#[test]
+fn test_to_source() {
+    let dirs = [
+        format!("{}/testcases", env!("CARGO_MANIFEST_DIR")),
+        format!("{}/../sv-parser-pp/testcases", env!("CARGO_MANIFEST_DIR")),
+    ];
+    let mut paths: Vec<_> = dirs
+        .iter()
+        .flat_map(|x| fs::read_dir(x).unwrap())
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().map_or(false, |x| x == "sv"))
+        .collect();
+    paths.sort();
+
+    for path in &paths {
+        let include_paths = [path.parent().unwrap()];
+        // Some testcases check the errors of the preprocessor and the parser
+        let tree = match parse_sv(path, &HashMap::new(), &include_paths, false) {
+            Ok((x, _)) => x,
+            Err(_) => continue,
+        };
+        let text = fs::read_to_string(path).unwrap();
+        let options = ParseOptions {
+            keep_macros: true,
+            ..ParseOptions::default()
+        };
+        let (kept, _) =
+            parse_sv_with_options(path, &HashMap::new(), &include_paths, false, &options)
+                .unwrap_or_else(|x| panic!("{:?}: {}", path, x));
+        assert_eq!(kept.to_source(), text, "{:?}", path);
+
+        // Without `keep_macros` the preprocessed text is regenerated
+        let (text, _) = preprocess(path, &HashMap::new(), &include_paths, false, false).unwrap();
+        let mut source = Vec::new();
+        tree.write_source(&mut source).unwrap();
+        assert_eq!(
+            String::from_utf8(source).unwrap(),
+            text.text(),
+            "{:?}",
+            path
+        );
+        assert_eq!(tree.to_source(), text.text(), "{:?}", path);
+    }
+}
+
+#[test]
+fn test_outline() {
+    let text = r##"module a #(parameter W = 8) (clk, b);
+  input clk;
+  (* keep *) input b;
+  logic [W-1:0] c;
+  always_ff @(posedge clk) begin
+    c <= c + 1;
+  end
+endmodule
+class d;
+  local int e;
+  extern protected function void f();
+  (* x *) extern task g();
+  function void h();
+    e = 1;
+  endfunction
+  localparam int I = 1;
+endclass
+"##;
+    let options = ParseOptions {
+        outline: true,
+        ..ParseOptions::default()
+    };
+    let (tree, _) = parse_sv_str_with_options(
+        text,
+        "test.sv",
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+        &options,
+    )
+    .unwrap();
+    let source = tree.to_source();
+    assert_eq!(source.len(), text.len());
+    assert_eq!(source.lines().count(), text.lines().count());
+    for x in &[
+        "input clk;",
+        "(* keep *) input b;",
+        "localparam int I = 1;",
+        "endclass",
+    ] {
+        assert!(source.contains(x), "{}", x);
+    }
+    for x in &["logic", "c + 1", "local int", "function", "e = 1"] {
+        assert!(!source.contains(x), "{}", x);
+    }
+
+    let ports: Vec<_> = tree
+        .into_iter()
+        .filter(|x| matches!(x, RefNode::PortDeclaration(_)))
+        .collect();
+    assert_eq!(ports.len(), 2);
+    assert!(unwrap_node!(&tree, ClassItem).is_some());
+}
+
+#[test]
+fn test_outline_cost() {
+    // The productions called by the parser are counted as its work
+    let calls = |outline: bool| {
+        let stats = Arc::new(Mutex::new(PackratStats::default()));
+        let options = ParseOptions {
+            outline,
+            packrat_stats: Some(stats.clone()),
+            ..ParseOptions::default()
+        };
+        for name in &["test1.sv", "test2.sv"] {
+            parse_sv_with_options(
+                testcase(name),
+                &HashMap::new(),
+                &[] as &[PathBuf],
+                false,
+                &options,
+            )
+            .unwrap();
+        }
+        let stats = stats.lock().unwrap();
+        stats.productions.values().map(|x| x.calls).sum::<usize>()
+    };
+    let (full, outline) = (calls(false), calls(true));
+    assert!(outline * 4 < full, "{} {}", outline, full);
+}
+
+#[test]
+fn test_parse_control() {
+    // The progress is reported at intervals of 64 KiB
+    let text = format!(
+        "{}module a;\n  initial b = c;\nendmodule\n",
+        "// padding\n".repeat(10000)
+    );
+    let path = std::env::temp_dir().join(format!("sv-parser-{}.sv", std::process::id()));
+    fs::write(&path, &text).unwrap();
+    let parse = |options: &ParseOptions| {
+        parse_sv_with_options(&path, &HashMap::new(), &[] as &[PathBuf], false, options)
+    };
+
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let sink = reported.clone();
+    let options = ParseOptions {
+        progress: Some(ProgressCallback::new(move |x| {
+            sink.lock().unwrap().push((x.phase, x.bytes, x.files));
+        })),
+        ..ParseOptions::default()
+    };
+    assert!(parse(&options).is_ok());
+    let reported = reported.lock().unwrap();
+    for phase in &[Phase::Preprocess, Phase::Parse] {
+        let bytes: Vec<_> = reported
+            .iter()
+            .filter(|x| x.0 == *phase)
+            .map(|x| x.1)
+            .collect();
+        assert!(!bytes.is_empty());
+        assert!(bytes.windows(2).all(|x| x[0] < x[1]));
+        assert!(bytes.iter().all(|x| *x <= text.len()));
+    }
+    assert_eq!(reported.last(), Some(&(Phase::Done, 0, 1)));
+
+    // The parse stops when cancelled during the parse
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    let parsed = Arc::new(AtomicUsize::new(0));
+    let count = parsed.clone();
+    let options = ParseOptions {
+        cancel: Some(cancel.clone()),
+        progress: Some(ProgressCallback::new(move |x| {
+            if x.phase == Phase::Parse {
+                count.fetch_add(1, Ordering::SeqCst);
+                token.cancel();
+            }
+        })),
+        ..ParseOptions::default()
+    };
+    assert!(matches!(parse(&options), Err(Error::Cancelled)));
+    assert_eq!(parsed.load(Ordering::SeqCst), 1);
+
+    let options = ParseOptions {
+        cancel: Some(cancel),
+        ..ParseOptions::default()
+    };
+    assert!(matches!(parse(&options), Err(Error::Cancelled)));
+    fs::remove_file(&path).unwrap();
+}
+
+fn identifiers(tree: &SyntaxTree) -> Vec<&Identifier> {
+    tree.into_iter()
+        .filter_map(|x| match x {
+            RefNode::Identifier(x) => Some(x),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_max_depth() {
+    let nested = |n: usize| {
+        format!(
+            "module a;\n  initial b = {}1{};\nendmodule\n",
+            "(".repeat(n),
+            ")".repeat(n)
+        )
+    };
+    let path = std::env::temp_dir().join(format!("sv-parser-depth-{}.sv", std::process::id()));
+    fs::write(&path, nested(10000)).unwrap();
+
+    let ret = parse_sv_files(
+        &[&path],
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+        CompilationUnit::SingleFile,
+        &ParseOptions::default(),
+    );
+    fs::remove_file(&path).unwrap();
+    match &ret[0] {
+        Err(Error::ExceedNestingLimit(Some((x, pos)))) => {
+            assert_eq!(x, &path);
+            assert!(*pos > 23 && *pos < 23 + 10000);
+        }
+        x => panic!("{:?}", x.as_ref().err()),
+    }
+
+    // The parse on the test thread grows its stack
+    let parse = |text: String, max_depth| {
+        let options = ParseOptions {
+            max_depth,
+            ..ParseOptions::default()
+        };
+        parse_sv_str_with_options(
+            &text,
+            "test.sv",
+            &HashMap::new(),
+            &[] as &[PathBuf],
+            false,
+            &options,
+        )
+        .map(|_| ())
+    };
+    assert!(parse(nested(40), Some(DEFAULT_MAX_DEPTH)).is_ok());
+    assert!(matches!(
+        parse(nested(10000), Some(DEFAULT_MAX_DEPTH)),
+        Err(Error::ExceedNestingLimit(Some(_)))
+    ));
+    assert!(matches!(
+        parse(nested(10), Some(8)),
+        Err(Error::ExceedNestingLimit(Some(_)))
+    ));
+    assert!(parse(nested(100), None).is_ok());
+    let terms: Vec<_> = (0..140).map(|i| format!("c{}", i)).collect();
+    let text = format!(
+        "module a;\n  assign b = {};\nendmodule\n",
+        terms.join(" + ")
+    );
+    assert!(parse(text, Some(DEFAULT_MAX_DEPTH)).is_ok());
+}
+
+#[test]
+fn test_rewriter() {
+    let text = "module a;\n  assign b = c + d; // e\nendmodule\n";
+    let (tree, _) =
+        parse_sv_str(text, "test.sv", &HashMap::new(), &[] as &[PathBuf], false).unwrap();
+    let ids = identifiers(&tree);
+    let assign = unwrap_node!(&tree, ContinuousAssign).unwrap();
+
+    let mut rewriter = Rewriter::new(&tree);
+    rewriter.replace(ids[2], "x");
+    rewriter.insert_before(ids[3], "1 + ");
+    rewriter.insert_before(ids[3], "2 + ");
+    rewriter.insert_after(ids[3], " + 3");
+    rewriter.delete(ids[0]);
+    rewriter.insert_before(ids[0], "z");
+    assert_eq!(
+        rewriter.to_source().unwrap(),
+        "module z;\n  assign b = x + 1 + 2 + d + 3; // e\nendmodule\n"
+    );
+
+    // An edit inside another edit is rejected
+    let mut rewriter = Rewriter::new(&tree);
+    rewriter.replace(vec![assign], "");
+    rewriter.replace(ids[1], "x");
+    match rewriter.to_source() {
+        Err(Error::OverlappingEdits(x)) => assert_eq!(x, text.find("b =").unwrap()),
+        x => panic!("{:?}", x),
+    }
+    assert!(rewriter.file_edits().is_err());
+}
+
+#[test]
+fn test_rewriter_file_edits() {
+    let path = testcase("test6.sv");
+    let (tree, _) = parse_sv(&path, &HashMap::new(), &[testcase("")], false).unwrap();
+    let ids = identifiers(&tree);
+    let pos = fs::read_to_string(&path).unwrap().find("g =").unwrap();
+
+    let mut rewriter = Rewriter::new(&tree);
+    rewriter.replace(ids[2], "h");
+    rewriter.replace(ids[1], "h");
+    let edits = rewriter.file_edits().unwrap();
+    assert_eq!(
+        edits,
+        [
+            (
+                path.clone(),
+                TextEdit {
+                    range: pos..pos + 1,
+                    text: String::from("h")
+                }
+            ),
+            (
+                testcase("test6.svh"),
+                TextEdit {
+                    range: 8..9,
+                    text: String::from("h")
+                }
+            ),
+        ]
+    );
+
+    // `__LINE__` is expanded to a text which isn't in the source files
+    let text = "module a;\n  assign b = `__LINE__;\nendmodule\n";
+    let (tree, _) =
+        parse_sv_str(text, "test.sv", &HashMap::new(), &[] as &[PathBuf], false).unwrap();
+    let mut rewriter = Rewriter::new(&tree);
+    rewriter.replace(vec![unwrap_node!(&tree, Number).unwrap()], "0");
+    assert!(matches!(rewriter.file_edits(), Err(Error::EditOrigin(_))));
+}
+
+#[test]
+fn test_span() {
+    let text = "module a;\n  // b\n  assign c = 1; // d\n\nendmodule\n";
+    let (tree, _) =
+        parse_sv_str(text, "test.sv", &HashMap::new(), &[] as &[PathBuf], false).unwrap();
+    let path = PathBuf::from("test.sv");
+
+    // Trailing white spaces and comments are included only by `span`
+    let assign = unwrap_node!(&tree, ContinuousAssign).unwrap();
+    let span = tree.span(vec![assign.clone()]).unwrap();
+    assert_eq!(&text[span.range.clone()], "assign c = 1; // d\n\n");
+    assert_eq!((span.start_line, span.end_line), (3, 4));
+    assert_eq!(span.origin, Some((&path, span.range.clone())));
+    let span = tree.span_trimmed(vec![assign]).unwrap();
+    assert_eq!(&text[span.range.clone()], "assign c = 1;");
+    assert_eq!((span.start_line, span.end_line), (3, 3));
+
+    // Leading comments belong to the previous token
+    let module = unwrap_node!(&tree, ModuleDeclaration).unwrap();
+    let span = tree.span_trimmed(vec![module.clone()]).unwrap();
+    assert_eq!(span.range, 0..text.len() - 1);
+    assert_eq!((span.start_line, span.end_line), (1, 5));
+    let header = unwrap_node!(&tree, ModuleAnsiHeader).unwrap();
+    assert_eq!(
+        tree.span(vec![header.clone()]).map(|x| &text[x.range]),
+        Some("module a;\n  // b\n  ")
+    );
+    assert_eq!(
+        tree.span_trimmed(vec![header.clone()])
+            .map(|x| &text[x.range]),
+        Some("module a;")
+    );
+    assert_eq!(tree.get_str_trimmed(vec![header]), Some("module a;"));
+    if let RefNode::ModuleDeclaration(x) = module {
+        assert_eq!(tree.get_str_trimmed(x.name()), Some("a"));
+    }
+
+    // White spaces have only trivia
+    let comment = (&tree)
+        .into_iter()
+        .find(|x| matches!(x, RefNode::WhiteSpace(WhiteSpace::Comment(_))))
+        .unwrap();
+    assert_eq!(
+        tree.span(vec![comment.clone()]).map(|x| &text[x.range]),
+        Some("// b")
+    );
+    assert_eq!(tree.span_trimmed(vec![comment]), None);
+
+    // Nodes without text
+    let select = unwrap_node!(&tree, ConstantBitSelect).unwrap();
+    assert_eq!(tree.span(vec![select.clone()]), None);
+    assert_eq!(tree.span_trimmed(vec![select]), None);
+    assert_eq!(tree.span(vec![]), None);
+
+    // Origins of included files
+    let path = testcase("test6.sv");
+    let header = testcase("test6.svh");
+    let (tree, _) = parse_sv(&path, &HashMap::new(), &[testcase("")], false).unwrap();
+    let source = fs::read_to_string(&path).unwrap();
+    let declaration = unwrap_node!(&tree, DataDeclaration).unwrap();
+    let span = tree.span_trimmed(vec![declaration]).unwrap();
+    assert_eq!(span.origin, Some((&header, 2..10)));
+    let module = unwrap_node!(&tree, ModuleDeclaration).unwrap();
+    let span = tree.span_trimmed(vec![module]).unwrap();
+    assert_eq!(span.origin, Some((&path, 0..source.len() - 1)));
+    assert_eq!((span.start_line, span.end_line), (1, 5));
+}
+
+#[test]
+fn test_node_at() {
+    let path = testcase("test6.sv");
+    let header = testcase("test6.svh");
+    let (tree, _) = parse_sv(&path, &HashMap::new(), &[testcase("")], false).unwrap();
+    let source = fs::read_to_string(&path).unwrap();
+
+    let kinds = |nodes: Vec<RefNode>| nodes.iter().map(|x| x.kind()).collect::<Vec<_>>();
+    let pos = source.find("g =").unwrap();
+    let nodes = tree.node_at(&path, pos);
+    assert_eq!(tree.get_str(vec![nodes[0].clone()]), Some("g"));
+    assert_eq!(
+        kinds(nodes)[..3],
+        ["Locate", "SimpleIdentifier", "Identifier"]
+    );
+
+    // Included files are mapped to their copies
+    let nodes = tree.node_at(&header, 8);
+    assert_eq!(tree.get_str(vec![nodes[0].clone()]), Some("g"));
+    assert!(kinds(nodes).contains(&"DataDeclarationVariable"));
+
+    // Directives and white spaces
+    assert!(tree
+        .node_at(&path, source.find("`include").unwrap())
+        .is_empty());
+    assert_eq!(kinds(tree.node_at(&path, 0))[..2], ["Locate", "Keyword"]);
+    assert!(tree.node_at(&header, 100).is_empty());
+
+    let nodes = tree.nodes_covering(&path, pos..pos + 5);
+    assert_eq!(nodes[0].kind(), "NetAssignment");
+    assert_eq!(tree.get_str(vec![nodes[0].clone()]), Some("g = 1"));
+    assert_eq!(
+        tree.nodes_covering(&path, pos..pos),
+        tree.node_at(&path, pos)
+    );
+
+    // The same nodes as the lookups by the index
+    let index = tree.index();
+    for offset in 0..source.len() {
+        let expected = tree
+            .position(&path, offset)
+            .map_or_else(Vec::new, |x| index.node_at(x));
+        assert_eq!(index.node_at_origin(&path, offset), expected);
+        assert_eq!(tree.node_at(&path, offset), expected);
+        if offset < pos + 5 {
+            let expected = match (tree.position(&path, offset), tree.position(&path, pos + 4)) {
+                (Some(x), Some(y)) => index.nodes_covering(x..y + 1),
+                _ => Vec::new(),
+            };
+            assert_eq!(
+                index.nodes_covering_origin(&path, offset..pos + 5),
+                expected
+            );
+            assert_eq!(tree.nodes_covering(&path, offset..pos + 5), expected);
+        }
+    }
+    assert_eq!(index.node_at_origin(&header, 8), tree.node_at(&header, 8));
+
+    // Offsets of the parsed text only without the mapping of origins
+    let index = TreeIndex::new(vec![RefNode::from(tree.node())]);
+    assert!(index.node_at_origin(&path, pos).is_empty());
+}
+
+#[test]
+fn test_query() {
+    let (tree, _) = parse_sv(
+        testcase("test1.sv"),
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+    )
+    .unwrap();
+
+    // The spans of the index are the extents of `span_trimmed`
+    let index = tree.index();
+    for x in &tree {
+        assert_eq!(
+            index.span(&x),
+            tree.span_trimmed(vec![x.clone()]).map(|x| x.range)
+        );
+    }
+
+    let matches: Vec<_> = tree
+        .query("ModuleNonansiHeader > ModuleIdentifier, ParameterDeclaration")
+        .unwrap()
+        .into_iter()
+        .map(|x| tree.get_str(vec![x.node]).unwrap().trim_end())
+        .collect();
+    assert_eq!(
+        matches,
+        ["dimm", "parameter [31:0] MEM_WIDTH = 16, MEM_SIZE = 8"]
+    );
+    assert!(matches!(
+        tree.query("ModuleIdentifier["),
+        Err(Error::Query { offset: 17, .. })
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let (tree, _) = parse_sv(
+        testcase("test1.sv"),
+        &HashMap::new(),
+        &[] as &[PathBuf],
+        false,
+    )
+    .unwrap();
+    let json = serde_json::to_string(&tree.node).unwrap();
+    let node: AnyNode = serde_json::from_str(&json).unwrap();
+    assert_eq!(node, tree.node);
+
+    // Nodes have the representation derived by serde
+    assert_eq!(
+        serde_json::to_string(identifiers(&tree)[0]).unwrap(),
+        r#"{"SimpleIdentifier":{"nodes":[{"offset":7,"line":1,"len":4},[]]}}"#
+    );
+
+    let compact = tree.to_compact();
+    let json = serde_json::to_string(&compact).unwrap();
+    assert!(json.starts_with(r#"{"kind":"SourceText","span":[0,"#));
+    let node: CompactNode = serde_json::from_str(&json).unwrap();
+    assert_eq!(node, compact);
+}
+
+#[test]
+fn test_doc_comment() {
+    let text = r#"// Adder
+//
+// with a carry
+module a (
+  input b, //< Operand
+  /** Result */
+  output c
+);
+  // Not attached
+
+  localparam d = 1; /**< Width */
+endmodule
+"#;
+    let (tree, _) =
+        parse_sv_str(text, "test.sv", &HashMap::new(), &[] as &[PathBuf], false).unwrap();
+    let texts: Vec<_> = tree
+        .doc_comments()
+        .into_iter()
+        .map(|(_, x)| x.text)
+        .collect();
+    assert_eq!(
+        texts,
+        ["Adder\n\nwith a carry", "Operand", "Result", "Width"]
+    );
+
+    let module = unwrap_node!(&tree, ModuleDeclaration).unwrap();
+    let doc = tree.doc_comment(vec![module]).unwrap();
+    assert_eq!((doc.leading.len(), doc.trailing.len()), (3, 0));
+    let port = unwrap_node!(&tree, AnsiPortDeclaration).unwrap();
+    let doc = tree.doc_comment(vec![port]).unwrap();
+    assert_eq!(tree.get_str(&doc.trailing[0]), Some("//< Operand"));
+    let param = unwrap_node!(&tree, LocalParameterDeclaration).unwrap();
+    assert!(tree.doc_comment(vec![param]).unwrap().leading.is_empty());
+
+    // A trailing comment on the next line isn't attached
+    let text = r#"module a;
+  localparam b = 1;
+  //< Not attached
+  localparam c = 2; /**< Attached */
+endmodule
+"#;
+    let (tree, _) =
+        parse_sv_str(text, "test.sv", &HashMap::new(), &[] as &[PathBuf], false).unwrap();
+    let texts: Vec<_> = tree
+        .doc_comments()
+        .into_iter()
+        .map(|(_, x)| x.text)
+        .collect();
+    assert_eq!(texts, ["Attached"]);
+}
@@ -0,0 +1,4 @@
+`define W 8
+module c;
+  logic [`W-1:0] x;
+endmodule
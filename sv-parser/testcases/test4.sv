@@ -0,0 +1,3 @@
+module d;
+  logic [`W-1:0] y;
+endmodule
@@ -0,0 +1,4 @@
+module f;
+`include "test6.svh"
+  assign g = 1;
+endmodule